@@ -1,3 +1,6 @@
+#[cfg(feature = "rsa")]
+pub mod rsa;
+
 #[repr(C)]
 pub struct BigModExpParams {
     pub base: *const u8,
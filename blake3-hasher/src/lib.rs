@@ -1,5 +1,11 @@
 //! Hashing with the [blake3] hash function.
 //!
+//! [`hash`] and [`hashv`] call the `sol_blake3` syscall on-chain. The syscall
+//! only computes plain hashes, so the keyed hash ([`keyed_hash`]), key
+//! derivation ([`derive_key`]) and extendable output ([`OutputReader`]) modes,
+//! like the streaming [`Hasher`], are computed by the `blake3` crate, which is
+//! only available on-chain with the `blake3` feature.
+//!
 //! [blake3]: https://github.com/BLAKE3-team/BLAKE3
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
@@ -37,6 +43,9 @@ use {
 #[repr(transparent)]
 pub struct Hash(pub [u8; HASH_BYTES]);
 
+/// The length of a key of the keyed hash mode
+pub const KEY_BYTES: usize = 32;
+
 #[cfg(any(feature = "blake3", not(target_os = "solana")))]
 #[derive(Clone, Default)]
 pub struct Hasher {
@@ -45,6 +54,20 @@ pub struct Hasher {
 
 #[cfg(any(feature = "blake3", not(target_os = "solana")))]
 impl Hasher {
+    /// A hasher in the keyed hash mode, a MAC with a secret `key`
+    pub fn new_keyed(key: &[u8; KEY_BYTES]) -> Self {
+        Self {
+            hasher: blake3::Hasher::new_keyed(key),
+        }
+    }
+    /// A hasher in the key derivation mode, deriving keys from the key
+    /// material it hashes. `context` should be hardcoded, globally unique and
+    /// application-specific, like `"example.com 2025-01-01 session tokens v1"`.
+    pub fn new_derive_key(context: &str) -> Self {
+        Self {
+            hasher: blake3::Hasher::new_derive_key(context),
+        }
+    }
     pub fn hash(&mut self, val: &[u8]) {
         self.hasher.update(val);
     }
@@ -56,6 +79,54 @@ impl Hasher {
     pub fn result(self) -> Hash {
         Hash(*self.hasher.finalize().as_bytes())
     }
+    /// An output of any length, whose first [`HASH_BYTES`] bytes are the
+    /// [`result`](Self::result)
+    pub fn result_xof(self) -> OutputReader {
+        OutputReader {
+            reader: self.hasher.finalize_xof(),
+        }
+    }
+}
+
+#[cfg(all(feature = "std", any(feature = "blake3", not(target_os = "solana"))))]
+impl std::io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.hash(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The extendable output of a [`Hasher`], read from any position
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+#[derive(Clone)]
+pub struct OutputReader {
+    reader: blake3::OutputReader,
+}
+
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+impl OutputReader {
+    /// Fill `buf` with the output from the current position, and move past it
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        self.reader.fill(buf);
+    }
+    /// The position in the output, in bytes
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+    pub fn set_position(&mut self, position: u64) {
+        self.reader.set_position(position);
+    }
+}
+
+#[cfg(all(feature = "std", any(feature = "blake3", not(target_os = "solana"))))]
+impl std::io::Read for OutputReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.fill(buf);
+        Ok(buf.len())
+    }
 }
 
 impl From<solana_hash::Hash> for Hash {
@@ -152,6 +223,50 @@ pub fn hash(val: &[u8]) -> Hash {
     hashv(&[val])
 }
 
+/// Return a keyed Blake3 hash, a MAC, for the given data.
+///
+/// `Hash` compares bytes in variable time, so check tags with
+/// [`verify_keyed_hashv`] rather than `==`.
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+pub fn keyed_hashv(key: &[u8; KEY_BYTES], vals: &[&[u8]]) -> Hash {
+    let mut hasher = Hasher::new_keyed(key);
+    hasher.hashv(vals);
+    hasher.result()
+}
+
+/// Return a keyed Blake3 hash, a MAC, for the given data.
+///
+/// `Hash` compares bytes in variable time, so check tags with
+/// [`verify_keyed_hash`] rather than `==`.
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+pub fn keyed_hash(key: &[u8; KEY_BYTES], val: &[u8]) -> Hash {
+    keyed_hashv(key, &[val])
+}
+
+/// Whether `tag` is the keyed hash of the given data, compared in constant
+/// time.
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+pub fn verify_keyed_hashv(key: &[u8; KEY_BYTES], vals: &[&[u8]], tag: &Hash) -> bool {
+    // `blake3::Hash` compares in constant time
+    blake3::Hash::from(keyed_hashv(key, vals).to_bytes()) == tag.to_bytes()
+}
+
+/// Whether `tag` is the keyed hash of the given data, compared in constant
+/// time.
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+pub fn verify_keyed_hash(key: &[u8; KEY_BYTES], val: &[u8], tag: &Hash) -> bool {
+    verify_keyed_hashv(key, &[val], tag)
+}
+
+/// Derive a key from the given key material, for the given context. See
+/// [`Hasher::new_derive_key`].
+#[cfg(any(feature = "blake3", not(target_os = "solana")))]
+pub fn derive_key(context: &str, key_material: &[u8]) -> [u8; KEY_BYTES] {
+    let mut hasher = Hasher::new_derive_key(context);
+    hasher.hash(key_material);
+    hasher.result().to_bytes()
+}
+
 #[cfg(feature = "std")]
 /// Return the hash of the given hash extended with the given value.
 pub fn extend_and_hash(id: &Hash, val: &[u8]) -> Hash {
@@ -162,7 +277,7 @@ pub fn extend_and_hash(id: &Hash, val: &[u8]) -> Hash {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, hex::FromHex};
 
     #[test]
     fn test_new_unique() {
@@ -219,4 +334,82 @@ mod tests {
         let ext_hash = extend_and_hash(&val_hash, ext.as_bytes());
         assert!(ext_hash == hash(&hash_ext));
     }
+
+    // The official test vectors, for an empty input
+    const KEY: &[u8; KEY_BYTES] = b"whats the Elvish word for friend";
+    const CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    #[test]
+    fn test_keyed_hash() {
+        assert_eq!(
+            keyed_hash(KEY, &[]).to_bytes(),
+            <[u8; 32]>::from_hex(
+                "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            keyed_hashv(KEY, &[b"gHil", b"jKpq"]),
+            keyed_hash(KEY, b"gHiljKpq")
+        );
+        assert_ne!(keyed_hash(KEY, b"gHiljKpq"), hash(b"gHiljKpq"));
+
+        let tag = keyed_hash(KEY, b"gHiljKpq");
+        assert!(verify_keyed_hash(KEY, b"gHiljKpq", &tag));
+        assert!(verify_keyed_hashv(KEY, &[b"gHil", b"jKpq"], &tag));
+        assert!(!verify_keyed_hash(KEY, b"gHiljKpr", &tag));
+        assert!(!verify_keyed_hash(&[0; KEY_BYTES], b"gHiljKpq", &tag));
+    }
+
+    #[test]
+    fn test_derive_key() {
+        assert_eq!(
+            derive_key(CONTEXT, &[]),
+            <[u8; 32]>::from_hex(
+                "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d"
+            )
+            .unwrap()
+        );
+        assert_ne!(
+            derive_key(CONTEXT, b"key"),
+            derive_key("other context", b"key")
+        );
+    }
+
+    #[test]
+    fn test_result_xof() {
+        let mut output = [0; 64];
+        Hasher::default().result_xof().fill(&mut output);
+        assert_eq!(
+            output,
+            <[u8; 64]>::from_hex(
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262\
+                 e00f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a"
+            )
+            .unwrap()
+        );
+
+        let mut hasher = Hasher::default();
+        hasher.hash(b"gHiljKpq");
+        let mut reader = hasher.result_xof();
+        let mut prefix = [0; HASH_BYTES];
+        reader.fill(&mut prefix);
+        assert_eq!(prefix, hash(b"gHiljKpq").to_bytes());
+        assert_eq!(reader.position(), HASH_BYTES as u64);
+
+        let mut rest = [0; 100];
+        reader.fill(&mut rest);
+        let mut whole = [0; 132];
+        reader.set_position(0);
+        std::io::Read::read_exact(&mut reader, &mut whole).unwrap();
+        assert_eq!(whole[..HASH_BYTES], prefix);
+        assert_eq!(whole[HASH_BYTES..], rest);
+    }
+
+    #[test]
+    fn test_hasher_write() {
+        let mut hasher = Hasher::new_keyed(KEY);
+        std::io::copy(&mut &b"gHiljKpq"[..], &mut hasher).unwrap();
+        assert_eq!(hasher.result(), keyed_hash(KEY, b"gHiljKpq"));
+    }
 }
@@ -0,0 +1,45 @@
+use {
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion},
+    solana_bls_signatures::{
+        batch::{verify_batch, BatchItem},
+        Keypair, SignatureProjective,
+    },
+};
+
+fn bench_batch_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bls verify");
+    for count in [1, 8, 64, 256] {
+        let keypairs: Vec<Keypair> = (0..count).map(|_| Keypair::new()).collect();
+        let messages: Vec<Vec<u8>> = (0..count)
+            .map(|i| format!("vote for slot {i}").into_bytes())
+            .collect();
+        let signatures: Vec<SignatureProjective> = keypairs
+            .iter()
+            .zip(&messages)
+            .map(|(keypair, message)| keypair.sign(message))
+            .collect();
+        let items: Vec<BatchItem> = keypairs
+            .iter()
+            .zip(&messages)
+            .zip(&signatures)
+            .map(|((keypair, message), signature)| {
+                BatchItem::new(&keypair.public, message, signature)
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::new("individual", count), &items, |b, items| {
+            b.iter(|| {
+                items
+                    .iter()
+                    .all(|item| item.pubkey.verify(item.signature, item.message))
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batch", count), &items, |b, items| {
+            b.iter(|| verify_batch(items))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_verify);
+criterion_main!(benches);
@@ -0,0 +1,211 @@
+//! Batch verification of BLS signatures.
+//!
+//! Verifying `n` independent signatures one by one takes `2n` pairings.
+//! [`verify_batch`] instead checks a random linear combination of them,
+//!
+//! ```text
+//! e(g1, sum(r_i * sig_i)) == prod(e(r_i * pk_i, H(m_i)))
+//! ```
+//!
+//! with a single multi-pairing of `n + 1` terms and one final
+//! exponentiation. The random scalars `r_i` are secret and fresh for every
+//! batch, so that invalid signatures can't be crafted to cancel each other
+//! out. Signatures of the same message share one term.
+//!
+//! A batch that fails only says that some signature is invalid.
+//! [`find_invalid`] finds which ones by bisection, which takes a number of
+//! batch verifications logarithmic in the size of the batch for each
+//! invalid signature.
+
+use {
+    crate::{pubkey::PubkeyProjective, signature::SignatureProjective},
+    blstrs::{Bls12, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar},
+    ff::Field,
+    group::{prime::PrimeCurveAffine, Group},
+    pairing::{MillerLoopResult, MultiMillerLoop},
+    rand::{rngs::OsRng, RngCore},
+    std::{collections::BTreeMap, vec::Vec},
+};
+
+/// A signature to verify in a batch, along with its pubkey and message
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BatchItem<'a> {
+    pub pubkey: &'a PubkeyProjective,
+    pub message: &'a [u8],
+    pub signature: &'a SignatureProjective,
+}
+
+impl<'a> BatchItem<'a> {
+    pub fn new(
+        pubkey: &'a PubkeyProjective,
+        message: &'a [u8],
+        signature: &'a SignatureProjective,
+    ) -> Self {
+        Self {
+            pubkey,
+            message,
+            signature,
+        }
+    }
+}
+
+/// A random non-zero scalar of 128 bits, which bounds the probability that an
+/// invalid batch verifies by 2^-128
+fn random_scalar() -> Scalar {
+    loop {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes[..16]);
+        // Always canonical, since the scalar field modulus exceeds 2^128
+        let scalar = Scalar::from_bytes_le(&bytes).unwrap();
+        if !bool::from(scalar.is_zero()) {
+            return scalar;
+        }
+    }
+}
+
+/// Verify every signature of `items` at once. Returns true for an empty
+/// batch.
+#[allow(clippy::arithmetic_side_effects)]
+pub fn verify_batch(items: &[BatchItem]) -> bool {
+    if items.is_empty() {
+        return true;
+    }
+
+    let mut aggregate_signature = G2Projective::identity();
+    let mut pubkeys_by_message: BTreeMap<&[u8], G1Projective> = BTreeMap::new();
+    for item in items {
+        let scalar = random_scalar();
+        aggregate_signature += item.signature.0 * scalar;
+        *pubkeys_by_message
+            .entry(item.message)
+            .or_insert_with(G1Projective::identity) += item.pubkey.0 * scalar;
+    }
+
+    let mut g1_points = Vec::with_capacity(pubkeys_by_message.len().saturating_add(1));
+    let mut g2_points = Vec::with_capacity(pubkeys_by_message.len().saturating_add(1));
+    g1_points.push(-G1Affine::generator());
+    g2_points.push(G2Prepared::from(G2Affine::from(aggregate_signature)));
+    for (message, pubkey) in pubkeys_by_message {
+        g1_points.push(G1Affine::from(pubkey));
+        g2_points.push(G2Prepared::from(G2Affine::from(
+            crate::hash::hash_message_to_point(message),
+        )));
+    }
+    let terms: Vec<(&G1Affine, &G2Prepared)> = g1_points.iter().zip(&g2_points).collect();
+    Bls12::multi_miller_loop(&terms).final_exponentiation() == Gt::identity()
+}
+
+/// The indexes of the invalid signatures of `items`, in increasing order
+pub fn find_invalid(items: &[BatchItem]) -> Vec<usize> {
+    let mut invalid = Vec::new();
+    find_invalid_in(items, 0, &mut invalid);
+    invalid
+}
+
+fn find_invalid_in(items: &[BatchItem], offset: usize, invalid: &mut Vec<usize>) {
+    if verify_batch(items) {
+        return;
+    }
+    if items.len() == 1 {
+        invalid.push(offset);
+        return;
+    }
+    let middle = items.len() / 2;
+    let (left, right) = items.split_at(middle);
+    find_invalid_in(left, offset, invalid);
+    find_invalid_in(right, offset.saturating_add(middle), invalid);
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::keypair::Keypair,
+        std::{format, vec},
+    };
+
+    fn signed_messages(count: usize) -> (Vec<Keypair>, Vec<Vec<u8>>, Vec<SignatureProjective>) {
+        let keypairs: Vec<Keypair> = (0..count).map(|_| Keypair::new()).collect();
+        let messages: Vec<Vec<u8>> = (0..count)
+            .map(|i| format!("vote for slot {i}").into_bytes())
+            .collect();
+        let signatures = keypairs
+            .iter()
+            .zip(&messages)
+            .map(|(keypair, message)| keypair.sign(message))
+            .collect();
+        (keypairs, messages, signatures)
+    }
+
+    fn batch<'a>(
+        keypairs: &'a [Keypair],
+        messages: &'a [Vec<u8>],
+        signatures: &'a [SignatureProjective],
+    ) -> Vec<BatchItem<'a>> {
+        keypairs
+            .iter()
+            .zip(messages)
+            .zip(signatures)
+            .map(|((keypair, message), signature)| {
+                BatchItem::new(&keypair.public, message, signature)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let (keypairs, messages, signatures) = signed_messages(9);
+        let items = batch(&keypairs, &messages, &signatures);
+        assert!(verify_batch(&items));
+        assert!(verify_batch(&items[..1]));
+        assert!(verify_batch(&[]));
+        assert!(find_invalid(&items).is_empty());
+
+        // Swapping two signatures invalidates both, even though their sum is
+        // unchanged
+        let mut swapped = signatures.clone();
+        swapped.swap(2, 5);
+        let items = batch(&keypairs, &messages, &swapped);
+        assert!(!verify_batch(&items));
+        assert_eq!(find_invalid(&items), [2, 5]);
+    }
+
+    #[test]
+    fn test_verify_batch_same_message() {
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::new()).collect();
+        let message = b"same message";
+        let mut signatures: Vec<SignatureProjective> = keypairs
+            .iter()
+            .map(|keypair| keypair.sign(message))
+            .collect();
+        let items: Vec<BatchItem> = keypairs
+            .iter()
+            .zip(&signatures)
+            .map(|(keypair, signature)| BatchItem::new(&keypair.public, message, signature))
+            .collect();
+        assert!(verify_batch(&items));
+
+        signatures[3] = keypairs[3].sign(b"other message");
+        let items: Vec<BatchItem> = keypairs
+            .iter()
+            .zip(&signatures)
+            .map(|(keypair, signature)| BatchItem::new(&keypair.public, message, signature))
+            .collect();
+        assert!(!verify_batch(&items));
+        assert_eq!(find_invalid(&items), [3]);
+    }
+
+    #[test]
+    fn test_find_invalid() {
+        let (keypairs, messages, mut signatures) = signed_messages(16);
+        for i in [0, 7, 8, 15] {
+            signatures[i] = SignatureProjective::default();
+        }
+        let items = batch(&keypairs, &messages, &signatures);
+        assert_eq!(find_invalid(&items), [0, 7, 8, 15]);
+
+        let signatures = vec![SignatureProjective::default(); 16];
+        let items = batch(&keypairs, &messages, &signatures);
+        assert_eq!(find_invalid(&items), (0..16).collect::<Vec<_>>());
+    }
+}
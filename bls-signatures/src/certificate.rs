@@ -0,0 +1,696 @@
+//! Vote certificates: an aggregate signature with a bitmap of its signers.
+//!
+//! A [`Certificate`] proves that a subset of an ordered set of keys signed a
+//! message. It holds one aggregate [`Signature`] and a [`SignerBitmap`] with
+//! a bit for every key of the set, so that its size doesn't grow with the
+//! number of signers. Verification aggregates the pubkeys of the signers and
+//! checks the aggregate signature against them with a single verification.
+//!
+//! Aggregating pubkeys is only sound if every key's owner proved possession
+//! of its secret key, otherwise a rogue key can cancel out the keys of
+//! honest signers. A [`KeySet`] therefore checks the proof of possession of
+//! every key when it's registered.
+//!
+//! Certificates serialize with serde, and compactly with
+//! [`Certificate::to_bytes`]: a [`CertificateHeader`], which is `Pod` with the
+//! `bytemuck` feature, followed by the bitmap in a [`BitmapEncoding`].
+
+#[cfg(not(target_os = "solana"))]
+use crate::{
+    proof_of_possession::ProofOfPossessionProjective, pubkey::PubkeyProjective,
+    signature::SignatureProjective,
+};
+#[cfg(feature = "bytemuck")]
+use bytemuck::{Pod, Zeroable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use {
+    crate::{
+        error::BlsError,
+        signature::{Signature, BLS_SIGNATURE_AFFINE_SIZE},
+    },
+    core::mem::size_of,
+    std::{vec, vec::Vec},
+};
+
+/// The largest number of keys that a certificate can refer to
+pub const MAX_CERTIFICATE_KEYS: usize = u16::MAX as usize;
+
+/// How the bitmap of a certificate is encoded in its byte representation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum BitmapEncoding {
+    /// One bit per key, least significant bit first, padded with zeros to a
+    /// whole number of bytes
+    Raw = 0,
+    /// The lengths of alternating runs of non-signers and signers, starting
+    /// with non-signers, each as an unsigned LEB128 integer. Only the first
+    /// run can be empty. Compact when most keys signed, or few did.
+    RunLength = 1,
+}
+
+impl TryFrom<u8> for BitmapEncoding {
+    type Error = BlsError;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(Self::Raw),
+            1 => Ok(Self::RunLength),
+            _ => Err(BlsError::InvalidBitmap),
+        }
+    }
+}
+
+/// The set of keys of an ordered key set that signed a certificate
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "RawSignerBitmap", into = "RawSignerBitmap")
+)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SignerBitmap {
+    len: u16,
+    bits: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct RawSignerBitmap {
+    len: u16,
+    bits: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawSignerBitmap> for SignerBitmap {
+    type Error = BlsError;
+
+    fn try_from(raw: RawSignerBitmap) -> Result<Self, Self::Error> {
+        Self::decode(BitmapEncoding::Raw, usize::from(raw.len), &raw.bits)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SignerBitmap> for RawSignerBitmap {
+    fn from(bitmap: SignerBitmap) -> Self {
+        Self {
+            len: bitmap.len,
+            bits: bitmap.bits,
+        }
+    }
+}
+
+impl SignerBitmap {
+    /// An empty bitmap for a key set of `len` keys
+    pub fn new(len: usize) -> Result<Self, BlsError> {
+        let len = u16::try_from(len).map_err(|_| BlsError::InvalidBitmap)?;
+        Ok(Self {
+            len,
+            bits: vec![0; usize::from(len).div_ceil(8)],
+        })
+    }
+
+    /// The number of keys in the key set
+    pub fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// Returns true if the key set has no keys
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the key at `index` signed
+    pub fn get(&self, index: usize) -> bool {
+        index < self.len() && self.bits[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    /// Mark the key at `index` as a signer
+    pub fn set(&mut self, index: usize) -> Result<(), BlsError> {
+        if index >= self.len() {
+            return Err(BlsError::SignerIndexOutOfRange(index));
+        }
+        if self.get(index) {
+            return Err(BlsError::DuplicateSigner(index));
+        }
+        self.bits[index / 8] |= 1 << (index % 8);
+        Ok(())
+    }
+
+    /// The number of signers
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// The indexes of the signers, in increasing order
+    pub fn signers(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(|index| self.get(*index))
+    }
+
+    /// Encode the bitmap. The number of keys is not included.
+    pub fn encode(&self, encoding: BitmapEncoding) -> Vec<u8> {
+        match encoding {
+            BitmapEncoding::Raw => self.bits.clone(),
+            BitmapEncoding::RunLength => {
+                let mut encoded = Vec::new();
+                let mut value = false;
+                let mut run = 0usize;
+                for index in 0..self.len() {
+                    if self.get(index) != value {
+                        write_leb128(&mut encoded, run);
+                        value = !value;
+                        run = 0;
+                    }
+                    run = run.saturating_add(1);
+                }
+                if run > 0 {
+                    write_leb128(&mut encoded, run);
+                }
+                encoded
+            }
+        }
+    }
+
+    /// Decode a bitmap of `len` keys. Only the canonical encoding is
+    /// accepted, so that every bitmap has exactly one encoding.
+    pub fn decode(encoding: BitmapEncoding, len: usize, bytes: &[u8]) -> Result<Self, BlsError> {
+        let mut bitmap = Self::new(len)?;
+        match encoding {
+            BitmapEncoding::Raw => {
+                if bytes.len() != bitmap.bits.len() {
+                    return Err(BlsError::InvalidBitmap);
+                }
+                bitmap.bits.copy_from_slice(bytes);
+                // Padding bits must be zero
+                let used_bits = len % 8;
+                if used_bits != 0
+                    && bitmap
+                        .bits
+                        .last()
+                        .is_some_and(|last| last >> used_bits != 0)
+                {
+                    return Err(BlsError::InvalidBitmap);
+                }
+            }
+            BitmapEncoding::RunLength => {
+                let mut rest = bytes;
+                let mut index = 0usize;
+                let mut value = false;
+                let mut first = true;
+                while !rest.is_empty() {
+                    let run = read_leb128(&mut rest)?;
+                    let end = index
+                        .checked_add(run)
+                        .filter(|end| *end <= len)
+                        .ok_or(BlsError::InvalidBitmap)?;
+                    // Only the first run may be empty, and only if a run of
+                    // signers follows
+                    if run == 0 && (!first || rest.is_empty()) {
+                        return Err(BlsError::InvalidBitmap);
+                    }
+                    if value {
+                        for signer in index..end {
+                            bitmap.set(signer)?;
+                        }
+                    }
+                    index = end;
+                    value = !value;
+                    first = false;
+                }
+                if index != len {
+                    return Err(BlsError::InvalidBitmap);
+                }
+            }
+        }
+        Ok(bitmap)
+    }
+}
+
+fn write_leb128(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Read a canonical unsigned LEB128 integer of at most `MAX_CERTIFICATE_KEYS`
+fn read_leb128(bytes: &mut &[u8]) -> Result<usize, BlsError> {
+    let mut value = 0usize;
+    for shift in [0, 7, 14] {
+        let (&byte, rest) = bytes.split_first().ok_or(BlsError::InvalidBitmap)?;
+        *bytes = rest;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            // A trailing zero byte would be an overlong encoding
+            if byte == 0 && shift > 0 {
+                return Err(BlsError::InvalidBitmap);
+            }
+            return if value <= MAX_CERTIFICATE_KEYS {
+                Ok(value)
+            } else {
+                Err(BlsError::InvalidBitmap)
+            };
+        }
+    }
+    Err(BlsError::InvalidBitmap)
+}
+
+/// The fixed-size header of the byte representation of a certificate, which
+/// is followed by the encoded bitmap
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(C)]
+pub struct CertificateHeader {
+    /// The aggregate signature
+    pub signature: Signature,
+    /// The number of keys in the key set, little-endian
+    pub num_keys: [u8; 2],
+    /// The [`BitmapEncoding`] of the bitmap that follows
+    pub encoding: u8,
+}
+
+/// Size of a certificate header in bytes
+pub const CERTIFICATE_HEADER_SIZE: usize = size_of::<CertificateHeader>();
+
+const _: () = assert!(CERTIFICATE_HEADER_SIZE == BLS_SIGNATURE_AFFINE_SIZE + 3);
+
+// The header only consists of byte arrays, so it has no padding
+#[cfg(feature = "bytemuck")]
+unsafe impl Zeroable for CertificateHeader {}
+#[cfg(feature = "bytemuck")]
+unsafe impl Pod for CertificateHeader {}
+
+/// An aggregate signature by a subset of an ordered key set
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Certificate {
+    /// The aggregate signature of the signers
+    pub signature: Signature,
+    /// The keys of the key set that signed
+    pub signers: SignerBitmap,
+}
+
+impl Certificate {
+    /// The byte representation of the certificate, with the bitmap in
+    /// `encoding`
+    pub fn to_bytes(&self, encoding: BitmapEncoding) -> Vec<u8> {
+        let bitmap = self.signers.encode(encoding);
+        let mut bytes = Vec::with_capacity(CERTIFICATE_HEADER_SIZE.saturating_add(bitmap.len()));
+        bytes.extend_from_slice(&self.signature.0);
+        bytes.extend_from_slice(&self.signers.len.to_le_bytes());
+        bytes.push(encoding as u8);
+        bytes.extend_from_slice(&bitmap);
+        bytes
+    }
+
+    /// Parse the byte representation of a certificate
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BlsError> {
+        if bytes.len() < CERTIFICATE_HEADER_SIZE {
+            return Err(BlsError::ParseFromBytes);
+        }
+        let (header, bitmap) = bytes.split_at(CERTIFICATE_HEADER_SIZE);
+        let (signature, rest) = header.split_at(BLS_SIGNATURE_AFFINE_SIZE);
+        let num_keys = u16::from_le_bytes([rest[0], rest[1]]);
+        let encoding = BitmapEncoding::try_from(rest[2])?;
+        Ok(Self {
+            // The length was checked above
+            signature: Signature(signature.try_into().unwrap()),
+            signers: SignerBitmap::decode(encoding, usize::from(num_keys), bitmap)?,
+        })
+    }
+}
+
+/// An ordered set of keys whose proofs of possession have been checked
+#[cfg(not(target_os = "solana"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeySet {
+    pubkeys: Vec<PubkeyProjective>,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl KeySet {
+    /// Register keys along with their proofs of possession, failing on the
+    /// first invalid proof
+    pub fn new<'a, I>(keys: I) -> Result<Self, BlsError>
+    where
+        I: IntoIterator<Item = (&'a PubkeyProjective, &'a ProofOfPossessionProjective)>,
+    {
+        let pubkeys = keys
+            .into_iter()
+            .enumerate()
+            .map(|(index, (pubkey, proof))| {
+                if pubkey.verify_proof_of_possession(proof) {
+                    Ok(*pubkey)
+                } else {
+                    Err(BlsError::InvalidProofOfPossession(index))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if pubkeys.len() > MAX_CERTIFICATE_KEYS {
+            return Err(BlsError::InvalidBitmap);
+        }
+        Ok(Self { pubkeys })
+    }
+
+    /// The pubkeys, in order
+    pub fn pubkeys(&self) -> &[PubkeyProjective] {
+        &self.pubkeys
+    }
+
+    /// The number of keys
+    pub fn len(&self) -> usize {
+        self.pubkeys.len()
+    }
+
+    /// Returns true if the set has no keys
+    pub fn is_empty(&self) -> bool {
+        self.pubkeys.is_empty()
+    }
+}
+
+#[cfg(not(target_os = "solana"))]
+impl Certificate {
+    /// Verify the certificate against `message` and the keys of `key_set`
+    pub fn verify(&self, key_set: &KeySet, message: &[u8]) -> Result<bool, BlsError> {
+        if self.signers.len() != key_set.len() {
+            return Err(BlsError::BitmapLengthMismatch);
+        }
+        let aggregate_pubkey = PubkeyProjective::aggregate(
+            self.signers.signers().map(|index| &key_set.pubkeys[index]),
+        )?;
+        let signature = SignatureProjective::try_from(&self.signature)?;
+        Ok(aggregate_pubkey.verify(&signature, message))
+    }
+
+    /// Add the signatures of another certificate of the same message and key
+    /// set, whose signers must be disjoint from those of this certificate
+    pub fn aggregate_with(&mut self, other: &Certificate) -> Result<(), BlsError> {
+        if self.signers.len() != other.signers.len() {
+            return Err(BlsError::BitmapLengthMismatch);
+        }
+        let mut signers = self.signers.clone();
+        for index in other.signers.signers() {
+            signers.set(index)?;
+        }
+        let mut signature = SignatureProjective::try_from(&self.signature)?;
+        signature.aggregate_with([&SignatureProjective::try_from(&other.signature)?]);
+        self.signature = signature.into();
+        self.signers = signers;
+        Ok(())
+    }
+}
+
+/// Builds a certificate by adding the signatures of members of a key set one
+/// by one
+#[cfg(not(target_os = "solana"))]
+#[derive(Clone, Debug)]
+pub struct CertificateBuilder<'a> {
+    key_set: &'a KeySet,
+    signature: SignatureProjective,
+    signers: SignerBitmap,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl<'a> CertificateBuilder<'a> {
+    /// A builder without signers
+    pub fn new(key_set: &'a KeySet) -> Self {
+        Self {
+            key_set,
+            signature: SignatureProjective::default(),
+            // `KeySet` never has more than `MAX_CERTIFICATE_KEYS` keys
+            signers: SignerBitmap::new(key_set.len()).unwrap(),
+        }
+    }
+
+    /// Add the signature of the key at `index` without verifying it
+    pub fn add(&mut self, index: usize, signature: &SignatureProjective) -> Result<(), BlsError> {
+        self.signers.set(index)?;
+        self.signature.aggregate_with([signature]);
+        Ok(())
+    }
+
+    /// Verify the signature of `message` by the key at `index` and add it
+    pub fn add_verified(
+        &mut self,
+        index: usize,
+        signature: &SignatureProjective,
+        message: &[u8],
+    ) -> Result<(), BlsError> {
+        let pubkey = self
+            .key_set
+            .pubkeys
+            .get(index)
+            .ok_or(BlsError::SignerIndexOutOfRange(index))?;
+        if !pubkey.verify(signature, message) {
+            return Err(BlsError::InvalidSignature);
+        }
+        self.add(index, signature)
+    }
+
+    /// The number of signers added so far
+    pub fn signer_count(&self) -> usize {
+        self.signers.count()
+    }
+
+    /// The certificate of the signatures added so far
+    pub fn build(&self) -> Result<Certificate, BlsError> {
+        if self.signers.count() == 0 {
+            return Err(BlsError::EmptyAggregation);
+        }
+        Ok(Certificate {
+            signature: (&self.signature).into(),
+            signers: self.signers.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::keypair::Keypair};
+
+    fn new_key_set(count: usize) -> (Vec<Keypair>, KeySet) {
+        let keypairs: Vec<Keypair> = (0..count).map(|_| Keypair::new()).collect();
+        let proofs: Vec<ProofOfPossessionProjective> =
+            keypairs.iter().map(Keypair::proof_of_possession).collect();
+        let key_set =
+            KeySet::new(keypairs.iter().map(|keypair| &keypair.public).zip(&proofs)).unwrap();
+        (keypairs, key_set)
+    }
+
+    #[test]
+    fn test_certificate_build_and_verify() {
+        let (keypairs, key_set) = new_key_set(10);
+        let message = b"vote for slot 42";
+        let mut builder = CertificateBuilder::new(&key_set);
+        assert_eq!(builder.build(), Err(BlsError::EmptyAggregation));
+        for index in [1, 4, 5, 9] {
+            builder
+                .add_verified(index, &keypairs[index].sign(message), message)
+                .unwrap();
+        }
+        assert_eq!(
+            builder.add(4, &keypairs[4].sign(message)),
+            Err(BlsError::DuplicateSigner(4))
+        );
+        assert_eq!(
+            builder.add_verified(10, &keypairs[0].sign(message), message),
+            Err(BlsError::SignerIndexOutOfRange(10))
+        );
+        assert_eq!(
+            builder.add_verified(0, &keypairs[0].sign(b"other message"), message),
+            Err(BlsError::InvalidSignature)
+        );
+        assert_eq!(builder.signer_count(), 4);
+
+        let certificate = builder.build().unwrap();
+        assert_eq!(
+            certificate.signers.signers().collect::<Vec<_>>(),
+            [1, 4, 5, 9]
+        );
+        assert_eq!(certificate.verify(&key_set, message), Ok(true));
+        assert_eq!(certificate.verify(&key_set, b"other message"), Ok(false));
+
+        // The certificate claims a signer that didn't sign
+        let mut forged = certificate.clone();
+        forged.signers.set(0).unwrap();
+        assert_eq!(forged.verify(&key_set, message), Ok(false));
+
+        let (_, other_key_set) = new_key_set(3);
+        assert_eq!(
+            certificate.verify(&other_key_set, message),
+            Err(BlsError::BitmapLengthMismatch)
+        );
+    }
+
+    #[test]
+    fn test_certificate_aggregate_with() {
+        let (keypairs, key_set) = new_key_set(6);
+        let message = b"vote";
+        let mut first = CertificateBuilder::new(&key_set);
+        let mut second = CertificateBuilder::new(&key_set);
+        for index in [0, 2] {
+            first.add(index, &keypairs[index].sign(message)).unwrap();
+        }
+        for index in [3, 5] {
+            second.add(index, &keypairs[index].sign(message)).unwrap();
+        }
+        let mut certificate = first.build().unwrap();
+        certificate
+            .aggregate_with(&second.build().unwrap())
+            .unwrap();
+        assert_eq!(
+            certificate.signers.signers().collect::<Vec<_>>(),
+            [0, 2, 3, 5]
+        );
+        assert_eq!(certificate.verify(&key_set, message), Ok(true));
+
+        // Overlapping certificates would count a signature twice
+        assert_eq!(
+            certificate.aggregate_with(&second.build().unwrap()),
+            Err(BlsError::DuplicateSigner(3))
+        );
+        assert_eq!(certificate.verify(&key_set, message), Ok(true));
+    }
+
+    #[test]
+    fn test_key_set_requires_proof_of_possession() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        let mut proofs: Vec<ProofOfPossessionProjective> =
+            keypairs.iter().map(Keypair::proof_of_possession).collect();
+        proofs.swap(1, 2);
+        assert_eq!(
+            KeySet::new(keypairs.iter().map(|keypair| &keypair.public).zip(&proofs)),
+            Err(BlsError::InvalidProofOfPossession(1))
+        );
+    }
+
+    #[test]
+    fn test_bitmap_encodings() {
+        let cases: [(usize, &[usize]); 6] = [
+            (0, &[]),
+            (5, &[]),
+            (5, &[0, 1, 2, 3, 4]),
+            (9, &[0, 8]),
+            (16, &[3, 4, 5, 10]),
+            (1000, &[0, 1, 500, 999]),
+        ];
+        for (len, signers) in cases {
+            let mut bitmap = SignerBitmap::new(len).unwrap();
+            for signer in signers {
+                bitmap.set(*signer).unwrap();
+            }
+            assert_eq!(bitmap.count(), signers.len());
+            for encoding in [BitmapEncoding::Raw, BitmapEncoding::RunLength] {
+                let encoded = bitmap.encode(encoding);
+                assert_eq!(
+                    SignerBitmap::decode(encoding, len, &encoded).unwrap(),
+                    bitmap
+                );
+            }
+        }
+
+        // Run lengths: 3 non-signers, 3 signers, 10 non-signers
+        let mut bitmap = SignerBitmap::new(16).unwrap();
+        for signer in 3..6 {
+            bitmap.set(signer).unwrap();
+        }
+        assert_eq!(bitmap.encode(BitmapEncoding::RunLength), [3, 3, 10]);
+        assert_eq!(bitmap.encode(BitmapEncoding::Raw), [0b0011_1000, 0]);
+        // A long run takes two bytes
+        let bitmap = SignerBitmap::new(200).unwrap();
+        assert_eq!(bitmap.encode(BitmapEncoding::RunLength), [0xc8, 0x01]);
+
+        // Non-canonical encodings are rejected
+        for (len, encoded) in [
+            (16, &[3, 3, 9][..]),
+            (16, &[3, 3, 11]),
+            (16, &[3, 0, 3, 10]),
+            (3, &[0]),
+            (3, &[0x83, 0x00]),
+            (3, &[0x80]),
+            (0, &[0]),
+        ] {
+            assert_eq!(
+                SignerBitmap::decode(BitmapEncoding::RunLength, len, encoded),
+                Err(BlsError::InvalidBitmap),
+                "{encoded:?}"
+            );
+        }
+        assert_eq!(
+            SignerBitmap::decode(BitmapEncoding::RunLength, 3, &[0, 3]),
+            Ok({
+                let mut bitmap = SignerBitmap::new(3).unwrap();
+                (0..3).for_each(|signer| bitmap.set(signer).unwrap());
+                bitmap
+            })
+        );
+        for (len, encoded) in [(9, &[0, 0b10][..]), (9, &[0]), (8, &[0, 0])] {
+            assert_eq!(
+                SignerBitmap::decode(BitmapEncoding::Raw, len, encoded),
+                Err(BlsError::InvalidBitmap)
+            );
+        }
+        assert_eq!(
+            SignerBitmap::new(MAX_CERTIFICATE_KEYS + 1),
+            Err(BlsError::InvalidBitmap)
+        );
+    }
+
+    #[test]
+    fn test_certificate_serialization() {
+        let (keypairs, key_set) = new_key_set(20);
+        let message = b"vote";
+        let mut builder = CertificateBuilder::new(&key_set);
+        for (index, keypair) in keypairs.iter().enumerate().take(15) {
+            builder.add(index, &keypair.sign(message)).unwrap();
+        }
+        let certificate = builder.build().unwrap();
+
+        for encoding in [BitmapEncoding::Raw, BitmapEncoding::RunLength] {
+            let bytes = certificate.to_bytes(encoding);
+            assert_eq!(Certificate::from_bytes(&bytes), Ok(certificate.clone()));
+        }
+        let bytes = certificate.to_bytes(BitmapEncoding::RunLength);
+        // Runs of 0 non-signers, 15 signers and 5 non-signers
+        assert_eq!(bytes.len(), CERTIFICATE_HEADER_SIZE + 3);
+        assert_eq!(
+            Certificate::from_bytes(&bytes[..CERTIFICATE_HEADER_SIZE - 1]),
+            Err(BlsError::ParseFromBytes)
+        );
+        let mut bad_encoding = bytes.clone();
+        bad_encoding[CERTIFICATE_HEADER_SIZE - 1] = 2;
+        assert_eq!(
+            Certificate::from_bytes(&bad_encoding),
+            Err(BlsError::InvalidBitmap)
+        );
+
+        #[cfg(feature = "bytemuck")]
+        {
+            let header: &CertificateHeader =
+                bytemuck::from_bytes(&bytes[..CERTIFICATE_HEADER_SIZE]);
+            assert_eq!(header.signature, certificate.signature);
+            assert_eq!(u16::from_le_bytes(header.num_keys), 20);
+            assert_eq!(header.encoding, BitmapEncoding::RunLength as u8);
+        }
+
+        #[cfg(feature = "serde")]
+        {
+            let serialized = bincode::serialize(&certificate).unwrap();
+            let deserialized: Certificate = bincode::deserialize(&serialized).unwrap();
+            assert_eq!(deserialized, certificate);
+            assert_eq!(deserialized.verify(&key_set, message), Ok(true));
+
+            // Bitmaps with padding bits set are rejected
+            let mut invalid = serialized;
+            let last = invalid.len() - 1;
+            invalid[last] |= 0x80;
+            assert!(bincode::deserialize::<Certificate>(&invalid).is_err());
+        }
+    }
+}
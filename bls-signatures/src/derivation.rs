@@ -0,0 +1,261 @@
+//! Hierarchical derivation of BLS keys.
+//!
+//! [EIP-2333] derives a tree of BLS secret keys from a seed of at least 32
+//! bytes, such as the BIP-39 seed of a seed phrase. Unlike BIP-32 there are
+//! no hardened or public derivations: every child key is derived from its
+//! parent secret key. Paths into the tree are written like
+//! `m/12381/501/0/0/0` and follow the layout of [EIP-2334],
+//! `m/12381/<coin type>/<account>/<use>`.
+//!
+//! [EIP-2333]: https://eips.ethereum.org/EIPS/eip-2333
+//! [EIP-2334]: https://eips.ethereum.org/EIPS/eip-2334
+
+use {
+    crate::{error::BlsError, keypair::Keypair, pubkey::PubkeyProjective, secret_key::SecretKey},
+    core::{fmt, str::FromStr},
+    std::{vec, vec::Vec},
+};
+
+/// The purpose of EIP-2334 paths, the first index below the master key
+pub const EIP2334_PURPOSE: u32 = 12381;
+
+/// The coin type of Solana in EIP-2334 paths, as registered in SLIP-44
+pub const SOLANA_COIN_TYPE: u32 = 501;
+
+/// A path from the master key to a key in an EIP-2333 key tree
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The path of the signing key of `account`, `m/12381/501/<account>/0/0`
+    pub fn new_eip2334_signing(account: u32) -> Self {
+        Self(vec![EIP2334_PURPOSE, SOLANA_COIN_TYPE, account, 0, 0])
+    }
+
+    /// The path of the withdrawal key of `account`, `m/12381/501/<account>/0`
+    pub fn new_eip2334_withdrawal(account: u32) -> Self {
+        Self(vec![EIP2334_PURPOSE, SOLANA_COIN_TYPE, account, 0])
+    }
+
+    /// The child indexes of the path, starting below the master key
+    pub fn path(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// The path to the child at `index` of the key at this path
+    pub fn child(&self, index: u32) -> Self {
+        let mut path = self.0.clone();
+        path.push(index);
+        Self(path)
+    }
+}
+
+impl From<Vec<u32>> for DerivationPath {
+    fn from(path: Vec<u32>) -> Self {
+        Self(path)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = BlsError;
+
+    /// Parse a path like `m/12381/501/0/0/0`. Indexes are decimal and can't
+    /// be hardened.
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(BlsError::ParseFromString);
+        }
+        components
+            .map(|index| {
+                if index.is_empty() || !index.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return Err(BlsError::ParseFromString);
+                }
+                index.parse().map_err(|_| BlsError::ParseFromString)
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("m")?;
+        for index in &self.0 {
+            write!(f, "/{index}")?;
+        }
+        Ok(())
+    }
+}
+
+impl SecretKey {
+    /// Derive the `BlsSecretKey` at `path` in the EIP-2333 key tree of `seed`
+    pub fn derive_from_seed_and_path(seed: &[u8], path: &DerivationPath) -> Result<Self, BlsError> {
+        path.path()
+            .iter()
+            .try_fold(Self::derive_master_eip2333(seed)?, |parent, index| {
+                parent.derive_child_eip2333(*index)
+            })
+    }
+
+    /// Derive the `BlsSecretKey` at `path` in the EIP-2333 key tree of the
+    /// BIP-39 seed of a seed phrase and passphrase
+    #[cfg(feature = "seed-phrase")]
+    pub fn derive_from_seed_phrase_and_passphrase(
+        seed_phrase: &str,
+        passphrase: &str,
+        path: &DerivationPath,
+    ) -> Result<Self, BlsError> {
+        let seed = solana_seed_phrase::generate_seed_from_seed_phrase_and_passphrase(
+            seed_phrase,
+            passphrase,
+        );
+        Self::derive_from_seed_and_path(&seed, path)
+    }
+}
+
+impl Keypair {
+    /// Derive the `Keypair` at `path` in the EIP-2333 key tree of `seed`
+    pub fn derive_from_seed_and_path(seed: &[u8], path: &DerivationPath) -> Result<Self, BlsError> {
+        let secret = SecretKey::derive_from_seed_and_path(seed, path)?;
+        let public = PubkeyProjective::from_secret(&secret);
+        Ok(Self { secret, public })
+    }
+
+    /// Derive the `Keypair` at `path` in the EIP-2333 key tree of the BIP-39
+    /// seed of a seed phrase and passphrase
+    #[cfg(feature = "seed-phrase")]
+    pub fn derive_from_seed_phrase_and_passphrase(
+        seed_phrase: &str,
+        passphrase: &str,
+        path: &DerivationPath,
+    ) -> Result<Self, BlsError> {
+        let secret =
+            SecretKey::derive_from_seed_phrase_and_passphrase(seed_phrase, passphrase, path)?;
+        let public = PubkeyProjective::from_secret(&secret);
+        Ok(Self { secret, public })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, blstrs::Scalar, ff::PrimeField, std::string::ToString};
+
+    fn scalar(decimal: &str) -> Scalar {
+        Scalar::from_str_vartime(decimal).unwrap()
+    }
+
+    #[test]
+    fn test_eip2333_vectors() {
+        // The test cases of EIP-2333
+        let cases = [
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "6083874454709270928345386274498605044986640685124978867557563392430687146096",
+                0,
+                "20397789859736650942317412262472558107875392172444076792671091975210932703118",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "29757020647961307431480504535336562678282505419141012933316116377660817309383",
+                3141592653,
+                "25457201688850691947727629385191704516744796114925897962676248250929345014287",
+            ),
+            (
+                "0099FF991111002299DD7744EE3355BBDD8844115566CC55663355668888CC00",
+                "27580842291869792442942448775674722299803720648445448686099262467207037398656",
+                4294967295,
+                "29358610794459428860402234341874281240803786294062035874021252734817515685787",
+            ),
+            (
+                "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "19022158461524446591288038168518313374041767046816487870552872741050760015818",
+                42,
+                "31372231650479070279774297061823572166496564838472787488249775572789064611981",
+            ),
+        ];
+        for (seed, master, index, child) in cases {
+            let seed = hex::decode(seed).unwrap();
+            let master_key = SecretKey::derive_master_eip2333(&seed).unwrap();
+            assert_eq!(master_key.0, scalar(master));
+            let child_key = master_key.derive_child_eip2333(index).unwrap();
+            assert_eq!(child_key.0, scalar(child));
+
+            let path = DerivationPath::from(vec![index]);
+            assert_eq!(
+                SecretKey::derive_from_seed_and_path(&seed, &path).unwrap(),
+                child_key
+            );
+        }
+
+        assert_eq!(
+            SecretKey::derive_master_eip2333(&[0; 31]),
+            Err(BlsError::KeyDerivation)
+        );
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        let path: DerivationPath = "m/12381/501/7/0/0".parse().unwrap();
+        assert_eq!(path, DerivationPath::new_eip2334_signing(7));
+        assert_eq!(path.to_string(), "m/12381/501/7/0/0");
+        assert_eq!(
+            DerivationPath::new_eip2334_withdrawal(7).child(0),
+            DerivationPath::new_eip2334_signing(7)
+        );
+        assert_eq!("m".parse(), Ok(DerivationPath::default()));
+        assert_eq!(
+            "m/4294967295".parse::<DerivationPath>().unwrap().path(),
+            [u32::MAX]
+        );
+
+        for invalid in [
+            "",
+            "12381/501",
+            "m/",
+            "m/12381/",
+            "m/12381'/501",
+            "m/+1",
+            "m/4294967296",
+            "n/0",
+        ] {
+            assert_eq!(
+                invalid.parse::<DerivationPath>(),
+                Err(BlsError::ParseFromString),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "seed-phrase")]
+    fn test_derive_from_seed_phrase() {
+        let seed_phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let path = DerivationPath::new_eip2334_signing(0);
+        let keypair =
+            Keypair::derive_from_seed_phrase_and_passphrase(seed_phrase, "", &path).unwrap();
+        let seed =
+            solana_seed_phrase::generate_seed_from_seed_phrase_and_passphrase(seed_phrase, "");
+        assert_eq!(
+            keypair,
+            Keypair::derive_from_seed_and_path(&seed, &path).unwrap()
+        );
+        assert_eq!(
+            keypair.public,
+            PubkeyProjective::from_secret(&keypair.secret)
+        );
+
+        // Other accounts and passphrases derive other keys
+        let other_account = DerivationPath::new_eip2334_signing(1);
+        assert_ne!(
+            Keypair::derive_from_seed_phrase_and_passphrase(seed_phrase, "", &other_account)
+                .unwrap(),
+            keypair
+        );
+        assert_ne!(
+            Keypair::derive_from_seed_phrase_and_passphrase(seed_phrase, "passphrase", &path)
+                .unwrap(),
+            keypair
+        );
+    }
+}
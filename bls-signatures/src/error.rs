@@ -14,4 +14,24 @@ pub enum BlsError {
     ParseFromString, // TODO: update after more precise error handling
     #[error("Failed to parse from bytes")]
     ParseFromBytes,
+    #[error("Invalid threshold")]
+    InvalidThreshold,
+    #[error("Invalid share index")]
+    InvalidShareIndex,
+    #[error("Duplicate share index {0}")]
+    DuplicateShareIndex(u32),
+    #[error("Not enough shares: {provided} provided, {threshold} required")]
+    NotEnoughShares { threshold: usize, provided: usize },
+    #[error("Invalid proof of possession for key {0}")]
+    InvalidProofOfPossession(usize),
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Signer index {0} out of range")]
+    SignerIndexOutOfRange(usize),
+    #[error("Duplicate signer {0}")]
+    DuplicateSigner(usize),
+    #[error("Signer bitmap length does not match the key set")]
+    BitmapLengthMismatch,
+    #[error("Invalid signer bitmap encoding")]
+    InvalidBitmap,
 }
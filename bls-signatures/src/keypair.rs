@@ -1,3 +1,5 @@
+#[cfg(feature = "keystore")]
+use crate::keystore::{Keystore, KeystoreKdf};
 use crate::{
     error::BlsError,
     proof_of_possession::ProofOfPossessionProjective,
@@ -11,12 +13,14 @@ use solana_signer::Signer;
 use std::{
     boxed::Box,
     error,
+    fmt::Write as _,
     fs::{self, File, OpenOptions},
     io::{Read, Write},
     path::Path,
     string::String,
     vec::Vec,
 };
+use zeroize::Zeroizing;
 
 /// Size of BLS keypair in bytes
 pub const BLS_KEYPAIR_SIZE: usize = BLS_SECRET_KEY_SIZE + BLS_PUBLIC_KEY_AFFINE_SIZE;
@@ -81,22 +85,30 @@ impl TryFrom<&[u8]> for Keypair {
     }
 }
 
-impl From<&Keypair> for [u8; BLS_KEYPAIR_SIZE] {
-    fn from(keypair: &Keypair) -> Self {
-        let mut bytes = [0u8; BLS_KEYPAIR_SIZE];
-        bytes[..BLS_SECRET_KEY_SIZE]
-            .copy_from_slice(&Into::<[u8; BLS_SECRET_KEY_SIZE]>::into(&keypair.secret));
+impl Keypair {
+    /// Returns this `Keypair` as a byte array which is zeroized on drop
+    pub fn to_zeroizing_bytes(&self) -> Zeroizing<[u8; BLS_KEYPAIR_SIZE]> {
+        let mut bytes = Zeroizing::new([0u8; BLS_KEYPAIR_SIZE]);
+        bytes[..BLS_SECRET_KEY_SIZE].copy_from_slice(self.secret.to_zeroizing_bytes().as_slice());
         bytes[BLS_SECRET_KEY_SIZE..].copy_from_slice(
-            &Into::<[u8; BLS_PUBLIC_KEY_AFFINE_SIZE]>::into(&keypair.public),
+            &Into::<[u8; BLS_PUBLIC_KEY_AFFINE_SIZE]>::into(&self.public),
         );
         bytes
     }
 }
 
+/// The returned array is not zeroized on drop; prefer
+/// [`Keypair::to_zeroizing_bytes`]
+impl From<&Keypair> for [u8; BLS_KEYPAIR_SIZE] {
+    fn from(keypair: &Keypair) -> Self {
+        *keypair.to_zeroizing_bytes()
+    }
+}
+
 #[cfg(feature = "std")]
 impl Keypair {
     pub fn read_json<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
-        let bytes: Vec<u8> = serde_json::from_reader(reader)?;
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(serde_json::from_reader(reader)?);
         Self::try_from(bytes.as_slice())
             .ok()
             .ok_or_else(|| std::io::Error::other("Invalid BLS keypair").into())
@@ -107,42 +119,110 @@ impl Keypair {
         Self::read_json(&mut file)
     }
 
-    pub fn write_json<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>> {
-        let json = serde_json::to_string(&Into::<[u8; BLS_KEYPAIR_SIZE]>::into(self).as_slice())?;
-        writer.write_all(&json.clone().into_bytes())?;
+    /// Writes the keypair as a JSON array of bytes, returning the JSON which
+    /// is zeroized on drop
+    pub fn write_json<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+        let bytes = self.to_zeroizing_bytes();
+        // Capacity: up to 3 digits and a comma per byte, plus 2 brackets, so
+        // writing into it doesn't reallocate and leave copies behind
+        let mut json = Zeroizing::new(String::with_capacity(BLS_KEYPAIR_SIZE * 4 + 2));
+        json.push('[');
+        for (i, byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(*json, "{byte}")?;
+        }
+        json.push(']');
+        writer.write_all(json.as_bytes())?;
         Ok(json)
     }
 
+    /// Writes the keypair to a file that only the owner can read, returning
+    /// the JSON which is zeroized on drop
     pub fn write_json_file<F: AsRef<Path>>(
         &self,
         outfile: F,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let outfile = outfile.as_ref();
+    ) -> Result<Zeroizing<String>, Box<dyn std::error::Error>> {
+        let mut f = create_key_file(outfile.as_ref())?;
+        self.write_json(&mut f)
+    }
+}
 
-        if let Some(outdir) = outfile.parent() {
-            fs::create_dir_all(outdir)?;
-        }
+#[cfg(feature = "keystore")]
+impl Keypair {
+    /// Read a keypair from an EIP-2335 keystore encrypted with `password`
+    pub fn read_keystore<R: Read>(
+        reader: &mut R,
+        password: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let keystore: Keystore = serde_json::from_reader(reader)?;
+        Ok(keystore.decrypt(password)?)
+    }
 
-        let mut f = {
-            #[cfg(not(unix))]
-            {
-                OpenOptions::new()
-            }
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::OpenOptionsExt;
-                OpenOptions::new().mode(0o600)
-            }
-        }
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(outfile)?;
+    /// Read a keypair from an EIP-2335 keystore file encrypted with
+    /// `password`
+    pub fn read_keystore_file<F: AsRef<Path>>(
+        path: F,
+        password: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let mut file = File::open(path.as_ref())?;
+        Self::read_keystore(&mut file, password)
+    }
 
-        self.write_json(&mut f)
+    /// Write the keypair as an EIP-2335 keystore encrypted with `password`
+    pub fn write_keystore<W: Write>(
+        &self,
+        writer: &mut W,
+        password: &str,
+        kdf: &KeystoreKdf,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let keystore = Keystore::encrypt(self, password, kdf)?;
+        let json = serde_json::to_string(&keystore)?;
+        writer.write_all(json.as_bytes())?;
+        Ok(json)
+    }
+
+    /// Write the keypair as an EIP-2335 keystore file encrypted with
+    /// `password`
+    pub fn write_keystore_file<F: AsRef<Path>>(
+        &self,
+        outfile: F,
+        password: &str,
+        kdf: &KeystoreKdf,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let mut f = create_key_file(outfile.as_ref())?;
+        self.write_keystore(&mut f, password, kdf)
     }
 }
 
+/// Create or truncate a file that only the owner can read
+#[cfg(feature = "std")]
+fn create_key_file(outfile: &Path) -> std::io::Result<File> {
+    if let Some(outdir) = outfile.parent() {
+        fs::create_dir_all(outdir)?;
+    }
+
+    {
+        #[cfg(not(unix))]
+        {
+            OpenOptions::new()
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            OpenOptions::new().mode(0o600)
+        }
+    }
+    .write(true)
+    .truncate(true)
+    .create(true)
+    .open(outfile)
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, tempfile::NamedTempFile};
@@ -180,4 +260,52 @@ mod tests {
         let read_keypair = Keypair::read_json_file(&temp_keypair_file).unwrap();
         assert_eq!(original_keypair, read_keypair);
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_keypair_json_is_zeroizing() {
+        let keypair = Keypair::new();
+        let mut written = Vec::new();
+        let mut json = keypair.write_json(&mut written).unwrap();
+        assert_eq!(json.as_bytes(), written);
+        assert_eq!(
+            serde_json::from_str::<Vec<u8>>(&json).unwrap(),
+            *keypair.to_zeroizing_bytes()
+        );
+
+        let (ptr, capacity) = (json.as_ptr(), json.capacity());
+        zeroize::Zeroize::zeroize(&mut json);
+        // SAFETY: zeroizing a `String` overwrites its whole buffer with zeros
+        // and empties it without freeing the buffer
+        let buffer = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(buffer.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_keypair_bytes_are_zeroizing() {
+        let keypair = Keypair::new();
+        let mut bytes = keypair.to_zeroizing_bytes();
+        assert_eq!(*bytes, <[u8; BLS_KEYPAIR_SIZE]>::from(&keypair));
+        assert_eq!(Keypair::try_from(bytes.as_slice()).unwrap(), keypair);
+        zeroize::Zeroize::zeroize(&mut bytes);
+        assert_eq!(*bytes, [0; BLS_KEYPAIR_SIZE]);
+    }
+
+    #[test]
+    #[cfg(feature = "keystore")]
+    fn test_keystore_file() {
+        let temp_keystore_file = NamedTempFile::new().unwrap();
+        let original_keypair = Keypair::new();
+        let kdf = KeystoreKdf::Scrypt {
+            log_n: 4,
+            r: 8,
+            p: 1,
+        };
+        original_keypair
+            .write_keystore_file(&temp_keystore_file, "password", &kdf)
+            .unwrap();
+        let read_keypair = Keypair::read_keystore_file(&temp_keystore_file, "password").unwrap();
+        assert_eq!(original_keypair, read_keypair);
+        assert!(Keypair::read_keystore_file(&temp_keystore_file, "wrong").is_err());
+    }
 }
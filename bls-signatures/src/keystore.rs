@@ -0,0 +1,612 @@
+//! Password-encrypted BLS keystores.
+//!
+//! [`Keypair::write_json`] stores the secret key in plaintext. A [`Keystore`]
+//! instead stores it encrypted with a key derived from a password, in the
+//! JSON format of [EIP-2335], so that keys can be moved between tools that
+//! implement the standard:
+//!
+//! ```json
+//! {
+//!   "crypto": {
+//!     "kdf": {
+//!       "function": "scrypt",
+//!       "params": { "dklen": 32, "n": 262144, "p": 1, "r": 8, "salt": "<hex>" },
+//!       "message": ""
+//!     },
+//!     "checksum": { "function": "sha256", "params": {}, "message": "<hex>" },
+//!     "cipher": {
+//!       "function": "aes-128-ctr",
+//!       "params": { "iv": "<hex>" },
+//!       "message": "<hex>"
+//!     }
+//!   },
+//!   "description": "",
+//!   "pubkey": "<hex compressed pubkey>",
+//!   "path": "m/12381/501/0/0/0",
+//!   "uuid": "<uuid>",
+//!   "version": 4
+//! }
+//! ```
+//!
+//! [`Keypair::read_keystore`] and [`Keypair::write_keystore`] read and write
+//! keystores next to the plaintext [`Keypair::read_json`] and
+//! [`Keypair::write_json`].
+//!
+//! [EIP-2335]: https://eips.ethereum.org/EIPS/eip-2335
+
+use {
+    crate::{
+        derivation::DerivationPath,
+        keypair::Keypair,
+        pubkey::{PubkeyCompressed, PubkeyProjective, BLS_PUBLIC_KEY_COMPRESSED_SIZE},
+        secret_key::{SecretKey, BLS_SECRET_KEY_SIZE},
+    },
+    aes::{
+        cipher::{KeyIvInit, StreamCipher},
+        Aes128,
+    },
+    blstrs::G1Affine,
+    hmac::Hmac,
+    rand::{rngs::OsRng, RngCore},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    },
+    thiserror::Error,
+    unicode_normalization::UnicodeNormalization,
+    zeroize::Zeroizing,
+};
+
+/// The version of the keystore format defined by EIP-2335
+pub const KEYSTORE_VERSION: u32 = 4;
+
+const SALT_LENGTH: usize = 32;
+const IV_LENGTH: usize = 16;
+const DECRYPTION_KEY_LENGTH: usize = 32;
+/// Upper bound on the memory used by scrypt when reading a keystore, so that
+/// a malicious keystore can't exhaust memory
+const MAX_SCRYPT_MEMORY: u64 = 1024 * 1024 * 1024;
+const MAX_SCRYPT_P: u32 = 16;
+/// Upper bound on the PBKDF2 iterations when reading a keystore, so that a
+/// malicious keystore can't take forever to decrypt
+const MAX_PBKDF2_ITERATIONS: u32 = 1 << 24;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+/// Reasons that a keystore can't be created or decrypted
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+pub enum KeystoreError {
+    #[error("unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("unsupported or too expensive key derivation parameters")]
+    InvalidKdfParams,
+    #[error("invalid keystore encoding")]
+    InvalidEncoding,
+    #[error("wrong password or corrupted keystore")]
+    InvalidPassword,
+    #[error("decrypted secret key is invalid")]
+    InvalidSecretKey,
+    #[error("decrypted secret key does not match the keystore pubkey")]
+    PubkeyMismatch,
+}
+
+/// The key derivation function that turns a password into a decryption key
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeystoreKdf {
+    /// scrypt with a CPU/memory cost of `2^log_n`
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// PBKDF2 with HMAC-SHA256 and `c` iterations
+    Pbkdf2 { c: u32 },
+}
+
+impl Default for KeystoreKdf {
+    /// The scrypt parameters of the EIP-2335 test vectors, which take 256MiB
+    /// of memory
+    fn default() -> Self {
+        Self::Scrypt {
+            log_n: 18,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params", rename_all = "lowercase")]
+enum Kdf {
+    Scrypt {
+        dklen: usize,
+        n: u64,
+        p: u32,
+        r: u32,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+    Pbkdf2 {
+        dklen: usize,
+        c: u32,
+        prf: String,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct EmptyParams {}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params", rename_all = "lowercase")]
+enum Checksum {
+    Sha256(EmptyParams),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params")]
+enum Cipher {
+    #[serde(rename = "aes-128-ctr")]
+    Aes128Ctr {
+        #[serde(with = "hex_bytes")]
+        iv: Vec<u8>,
+    },
+}
+
+/// A module of the `crypto` section: a function, its parameters and its
+/// output
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Module<T> {
+    #[serde(flatten)]
+    function: T,
+    #[serde(with = "hex_bytes")]
+    message: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Crypto {
+    kdf: Module<Kdf>,
+    checksum: Module<Checksum>,
+    cipher: Module<Cipher>,
+}
+
+/// A BLS keypair encrypted with a password, in the format of EIP-2335
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    crypto: Crypto,
+    #[serde(default)]
+    description: String,
+    #[serde(with = "hex_pubkey")]
+    pubkey: PubkeyCompressed,
+    path: String,
+    uuid: String,
+    version: u32,
+}
+
+impl Keystore {
+    /// Encrypt `keypair` with a key derived from `password`
+    pub fn encrypt(
+        keypair: &Keypair,
+        password: &str,
+        kdf: &KeystoreKdf,
+    ) -> Result<Self, KeystoreError> {
+        let mut salt = vec![0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let mut iv = vec![0u8; IV_LENGTH];
+        OsRng.fill_bytes(&mut iv);
+        let kdf = match *kdf {
+            KeystoreKdf::Scrypt { log_n, r, p } => Kdf::Scrypt {
+                dklen: DECRYPTION_KEY_LENGTH,
+                n: 1u64
+                    .checked_shl(u32::from(log_n))
+                    .ok_or(KeystoreError::InvalidKdfParams)?,
+                p,
+                r,
+                salt,
+            },
+            KeystoreKdf::Pbkdf2 { c } => Kdf::Pbkdf2 {
+                dklen: DECRYPTION_KEY_LENGTH,
+                c,
+                prf: "hmac-sha256".to_string(),
+                salt,
+            },
+        };
+        let decryption_key = derive_decryption_key(&kdf, password)?;
+
+        // EIP-2335 stores the secret key in big-endian byte order
+        let mut ciphertext = Zeroizing::new(keypair.secret.to_zeroizing_bytes().to_vec());
+        ciphertext.reverse();
+        Aes128Ctr::new_from_slices(&decryption_key[..16], &iv)
+            .map_err(|_| KeystoreError::InvalidEncoding)?
+            .apply_keystream(&mut ciphertext);
+        let checksum = checksum(&decryption_key, &ciphertext);
+
+        Ok(Self {
+            crypto: Crypto {
+                kdf: Module {
+                    function: kdf,
+                    message: Vec::new(),
+                },
+                checksum: Module {
+                    function: Checksum::Sha256(EmptyParams {}),
+                    message: checksum,
+                },
+                cipher: Module {
+                    function: Cipher::Aes128Ctr { iv },
+                    message: ciphertext.to_vec(),
+                },
+            },
+            description: String::new(),
+            pubkey: compress(&keypair.public),
+            path: String::new(),
+            uuid: new_uuid(),
+            version: KEYSTORE_VERSION,
+        })
+    }
+
+    /// Record the path at which the keypair was derived
+    pub fn with_path(mut self, path: &DerivationPath) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Attach a description for humans
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Decrypt the keypair with a key derived from `password`
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        let decryption_key = derive_decryption_key(&self.crypto.kdf.function, password)?;
+        let ciphertext = &self.crypto.cipher.message;
+        let Checksum::Sha256(_) = &self.crypto.checksum.function;
+        if checksum(&decryption_key, ciphertext) != self.crypto.checksum.message {
+            return Err(KeystoreError::InvalidPassword);
+        }
+
+        let Cipher::Aes128Ctr { iv } = &self.crypto.cipher.function;
+        if ciphertext.len() != BLS_SECRET_KEY_SIZE {
+            return Err(KeystoreError::InvalidSecretKey);
+        }
+        let mut secret = Zeroizing::new(ciphertext.clone());
+        Aes128Ctr::new_from_slices(&decryption_key[..16], iv)
+            .map_err(|_| KeystoreError::InvalidEncoding)?
+            .apply_keystream(&mut secret);
+        secret.reverse();
+        let secret =
+            SecretKey::try_from(secret.as_slice()).map_err(|_| KeystoreError::InvalidSecretKey)?;
+        let public = PubkeyProjective::from_secret(&secret);
+        if compress(&public) != self.pubkey {
+            return Err(KeystoreError::PubkeyMismatch);
+        }
+        Ok(Keypair { secret, public })
+    }
+
+    /// The pubkey of the encrypted keypair, which is readable without the
+    /// password
+    pub fn pubkey(&self) -> &PubkeyCompressed {
+        &self.pubkey
+    }
+
+    /// The path at which the keypair was derived, or an empty string if it's
+    /// unknown
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The description for humans
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The UUID that identifies the keystore
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+}
+
+/// Prepare a password as EIP-2335 requires: normalize it to NFKD and strip
+/// control codes
+fn normalize_password(password: &str) -> Zeroizing<String> {
+    Zeroizing::new(password.nfkd().filter(|c| !c.is_control()).collect())
+}
+
+fn derive_decryption_key(kdf: &Kdf, password: &str) -> Result<Zeroizing<Vec<u8>>, KeystoreError> {
+    let password = normalize_password(password);
+    match kdf {
+        Kdf::Scrypt {
+            dklen,
+            n,
+            p,
+            r,
+            salt,
+        } => {
+            if *dklen < DECRYPTION_KEY_LENGTH || !n.is_power_of_two() || *p > MAX_SCRYPT_P {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+            let memory = n
+                .checked_mul(u64::from(*r))
+                .and_then(|memory| memory.checked_mul(128));
+            if memory.is_none_or(|memory| memory > MAX_SCRYPT_MEMORY) {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+            let log_n = n.trailing_zeros() as u8;
+            let params = scrypt::Params::new(log_n, *r, *p, *dklen)
+                .map_err(|_| KeystoreError::InvalidKdfParams)?;
+            let mut key = Zeroizing::new(vec![0u8; *dklen]);
+            scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+                .map_err(|_| KeystoreError::InvalidKdfParams)?;
+            Ok(key)
+        }
+        Kdf::Pbkdf2 {
+            dklen,
+            c,
+            prf,
+            salt,
+        } => {
+            if *dklen < DECRYPTION_KEY_LENGTH
+                || prf != "hmac-sha256"
+                || *c == 0
+                || *c > MAX_PBKDF2_ITERATIONS
+            {
+                return Err(KeystoreError::InvalidKdfParams);
+            }
+            let mut key = Zeroizing::new(vec![0u8; *dklen]);
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, *c, &mut key);
+            Ok(key)
+        }
+    }
+}
+
+fn checksum(decryption_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(&decryption_key[16..DECRYPTION_KEY_LENGTH])
+        .chain_update(ciphertext)
+        .finalize()
+        .to_vec()
+}
+
+fn compress(pubkey: &PubkeyProjective) -> PubkeyCompressed {
+    PubkeyCompressed(G1Affine::from(pubkey.0).to_compressed())
+}
+
+/// A random version 4 UUID
+fn new_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+mod hex_bytes {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        std::{string::String, vec::Vec},
+    };
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex::decode(hex).map_err(serde::de::Error::custom)
+    }
+}
+
+mod hex_pubkey {
+    use {
+        super::{PubkeyCompressed, BLS_PUBLIC_KEY_COMPRESSED_SIZE},
+        serde::{Deserializer, Serializer},
+    };
+
+    pub fn serialize<S: Serializer>(
+        pubkey: &PubkeyCompressed,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::hex_bytes::serialize(&pubkey.0, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PubkeyCompressed, D::Error> {
+        let bytes = super::hex_bytes::deserialize(deserializer)?;
+        <[u8; BLS_PUBLIC_KEY_COMPRESSED_SIZE]>::try_from(bytes)
+            .map(PubkeyCompressed)
+            .map_err(|_| serde::de::Error::custom("invalid pubkey length"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The password and secret key of the EIP-2335 test vectors
+    const PASSWORD: &str = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+    const SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+    const PUBKEY: &str = "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07";
+
+    fn expected_secret() -> SecretKey {
+        let mut bytes = hex::decode(SECRET).unwrap();
+        bytes.reverse();
+        SecretKey::try_from(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_eip2335_scrypt_vector() {
+        let keystore: Keystore = serde_json::from_str(
+            r#"{
+                "crypto": {
+                    "kdf": {
+                        "function": "scrypt",
+                        "params": {
+                            "dklen": 32,
+                            "n": 262144,
+                            "p": 1,
+                            "r": 8,
+                            "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                        },
+                        "message": ""
+                    },
+                    "checksum": {
+                        "function": "sha256",
+                        "params": {},
+                        "message": "d2217fe5f3e9a1e34581ef8a78f7c9928e436d36dacc5e846690a5581e8ea484"
+                    },
+                    "cipher": {
+                        "function": "aes-128-ctr",
+                        "params": {
+                            "iv": "264daa3f303d7259501c93d997d84fe6"
+                        },
+                        "message": "06ae90d55fe0a6e9c5c3bc5b170827b2e5cce3929ed3f116c2811e6366dfe20f"
+                    }
+                },
+                "description": "This is a test keystore that uses scrypt to secure the secret.",
+                "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+                "path": "m/12381/60/3141592653/589793238",
+                "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+                "version": 4
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(hex::encode(keystore.pubkey().0), PUBKEY);
+        assert_eq!(keystore.path(), "m/12381/60/3141592653/589793238");
+        assert_eq!(keystore.uuid(), "1d85ae20-35c5-4611-98e8-aa14a633906f");
+        let keypair = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(keypair.secret, expected_secret());
+        assert_eq!(
+            keystore.decrypt("testpassword"),
+            Err(KeystoreError::InvalidPassword)
+        );
+    }
+
+    #[test]
+    fn test_eip2335_pbkdf2_vector() {
+        let keystore: Keystore = serde_json::from_str(
+            r#"{
+                "crypto": {
+                    "kdf": {
+                        "function": "pbkdf2",
+                        "params": {
+                            "dklen": 32,
+                            "c": 262144,
+                            "prf": "hmac-sha256",
+                            "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                        },
+                        "message": ""
+                    },
+                    "checksum": {
+                        "function": "sha256",
+                        "params": {},
+                        "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+                    },
+                    "cipher": {
+                        "function": "aes-128-ctr",
+                        "params": {
+                            "iv": "264daa3f303d7259501c93d997d84fe6"
+                        },
+                        "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+                    }
+                },
+                "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+                "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+                "path": "m/12381/60/0/0",
+                "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+                "version": 4
+            }"#,
+        )
+        .unwrap();
+        let keypair = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(keypair.secret, expected_secret());
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let keypair = Keypair::new();
+        let path = DerivationPath::new_eip2334_signing(0);
+        for kdf in [
+            KeystoreKdf::Scrypt {
+                log_n: 4,
+                r: 8,
+                p: 1,
+            },
+            KeystoreKdf::Pbkdf2 { c: 16 },
+        ] {
+            let keystore = Keystore::encrypt(&keypair, "password", &kdf)
+                .unwrap()
+                .with_path(&path)
+                .with_description("vote key");
+            assert_eq!(keystore.path(), "m/12381/501/0/0/0");
+            assert_eq!(keystore.description(), "vote key");
+            assert_eq!(keystore.uuid().len(), 36);
+            assert_eq!(&keystore.uuid()[14..15], "4");
+
+            let json = serde_json::to_string(&keystore).unwrap();
+            let keystore: Keystore = serde_json::from_str(&json).unwrap();
+            assert_eq!(keystore.decrypt("password").unwrap(), keypair);
+            // Control codes are stripped from passwords
+            assert_eq!(keystore.decrypt("pass\u{7f}word").unwrap(), keypair);
+            assert_eq!(
+                keystore.decrypt("Password"),
+                Err(KeystoreError::InvalidPassword)
+            );
+        }
+    }
+
+    #[test]
+    fn test_keystore_rejects_invalid() {
+        let keypair = Keypair::new();
+        let kdf = KeystoreKdf::Pbkdf2 { c: 16 };
+        let keystore = Keystore::encrypt(&keypair, "password", &kdf).unwrap();
+
+        let mut other = keystore.clone();
+        other.version = 3;
+        assert_eq!(
+            other.decrypt("password"),
+            Err(KeystoreError::UnsupportedVersion(3))
+        );
+
+        let mut other = keystore.clone();
+        other.pubkey = compress(&Keypair::new().public);
+        assert_eq!(
+            other.decrypt("password"),
+            Err(KeystoreError::PubkeyMismatch)
+        );
+
+        let mut other = keystore.clone();
+        if let Kdf::Pbkdf2 { c, .. } = &mut other.crypto.kdf.function {
+            *c = MAX_PBKDF2_ITERATIONS + 1;
+        }
+        assert_eq!(
+            other.decrypt("password"),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+
+        let mut other = keystore;
+        other.crypto.kdf.function = Kdf::Scrypt {
+            dklen: 32,
+            n: 1 << 30,
+            p: 1,
+            r: 8,
+            salt: vec![0; SALT_LENGTH],
+        };
+        assert_eq!(
+            other.decrypt("password"),
+            Err(KeystoreError::InvalidKdfParams)
+        );
+    }
+}
@@ -28,9 +28,17 @@ pub use crate::{
 // TODO: add conversion between compressed and uncompressed representation of
 // signatures, pubkeys, and proof of possessions
 
+#[cfg(all(feature = "std", not(target_os = "solana")))]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod certificate;
+#[cfg(all(feature = "std", not(target_os = "solana")))]
+pub mod derivation;
 pub mod error;
 #[cfg(not(target_os = "solana"))]
 pub mod keypair;
+#[cfg(all(feature = "keystore", not(target_os = "solana")))]
+pub mod keystore;
 #[macro_use]
 pub(crate) mod macros;
 #[cfg(not(target_os = "solana"))]
@@ -40,3 +48,5 @@ pub mod pubkey;
 #[cfg(not(target_os = "solana"))]
 pub mod secret_key;
 pub mod signature;
+#[cfg(all(feature = "std", not(target_os = "solana")))]
+pub mod threshold;
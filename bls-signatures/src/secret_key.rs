@@ -6,14 +6,19 @@ use {
         pubkey::PubkeyProjective,
         signature::SignatureProjective,
     },
-    blst::{blst_keygen, blst_scalar},
+    blst::{blst_derive_child_eip2333, blst_derive_master_eip2333, blst_keygen, blst_scalar},
     blstrs::Scalar,
-    core::ptr,
+    core::{ptr, sync::atomic},
     ff::Field,
     rand::rngs::OsRng,
+    zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing},
 };
 #[cfg(feature = "solana-signer-derive")]
-use {solana_signature::Signature, solana_signer::Signer, subtle::ConstantTimeEq};
+use {
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    solana_signer::Signer,
+    subtle::ConstantTimeEq,
+};
 
 /// Size of BLS secret key in bytes
 pub const BLS_SECRET_KEY_SIZE: usize = 32;
@@ -32,37 +37,71 @@ impl SecretKey {
 
     /// Derive a `BlsSecretKey` from a seed (input key material)
     pub fn derive(ikm: &[u8]) -> Result<Self, BlsError> {
-        let mut scalar = blst_scalar::default();
+        let mut scalar = Zeroizing::new(blst_scalar::default());
+        // SAFETY: `scalar` is valid for writes, `ikm` is valid for reads of
+        // `ikm.len()` bytes, and a null `info` of length 0 is allowed. blst
+        // zeroizes its scratch buffers before returning.
+        unsafe {
+            blst_keygen(&mut *scalar, ikm.as_ptr(), ikm.len(), ptr::null(), 0);
+        }
+        Self::from_blst_scalar(&scalar)
+    }
+
+    /// Derive the master `BlsSecretKey` of an EIP-2333 key tree from a seed of
+    /// at least 32 bytes
+    pub fn derive_master_eip2333(seed: &[u8]) -> Result<Self, BlsError> {
+        if seed.len() < 32 {
+            return Err(BlsError::KeyDerivation);
+        }
+        let mut scalar = Zeroizing::new(blst_scalar::default());
+        // SAFETY: `scalar` is valid for writes and `seed` is valid for reads of
+        // `seed.len()` bytes. blst zeroizes its lamport and IKM scratch buffers
+        // before returning.
         unsafe {
-            blst_keygen(
-                &mut scalar as *mut blst_scalar,
-                ikm.as_ptr(),
-                ikm.len(),
-                ptr::null(),
-                0,
-            );
+            blst_derive_master_eip2333(&mut *scalar, seed.as_ptr(), seed.len());
         }
-        scalar
-            .try_into()
+        Self::from_blst_scalar(&scalar)
+    }
+
+    /// Derive the child `BlsSecretKey` at `index` in an EIP-2333 key tree
+    pub fn derive_child_eip2333(&self, index: u32) -> Result<Self, BlsError> {
+        let parent: Zeroizing<blst_scalar> = Zeroizing::new(self.0.into());
+        let mut scalar = Zeroizing::new(blst_scalar::default());
+        // SAFETY: `scalar` is valid for writes and `parent` is valid for reads.
+        // blst zeroizes its lamport and IKM scratch buffers before returning.
+        unsafe {
+            blst_derive_child_eip2333(&mut *scalar, &*parent, index);
+        }
+        Self::from_blst_scalar(&scalar)
+    }
+
+    /// Convert a derived scalar without copying it out of `scalar`, which the
+    /// caller zeroizes
+    fn from_blst_scalar(scalar: &blst_scalar) -> Result<Self, BlsError> {
+        Option::<Scalar>::from(Scalar::from_bytes_le(&scalar.b))
             .map(Self)
-            .map_err(|_| BlsError::FieldDecode)
+            .ok_or(BlsError::FieldDecode)
     }
 
     /// Derive a `BlsSecretKey` from a Solana signer
     #[cfg(feature = "solana-signer-derive")]
     pub fn derive_from_signer(signer: &dyn Signer, public_seed: &[u8]) -> Result<Self, BlsError> {
         let message = [b"bls-key-derive-", public_seed].concat();
-        let signature = signer
-            .try_sign_message(&message)
-            .map_err(|_| BlsError::KeyDerivation)?;
+        // The signature is the key material, so it's zeroized once derived from
+        let signature: Zeroizing<[u8; SIGNATURE_BYTES]> = Zeroizing::new(
+            signer
+                .try_sign_message(&message)
+                .map_err(|_| BlsError::KeyDerivation)?
+                .into(),
+        );
 
         // Some `Signer` implementations return the default signature, which is not suitable for
         // use as key material
-        if bool::from(signature.as_ref().ct_eq(Signature::default().as_ref())) {
+        if bool::from(signature.as_slice().ct_eq(Signature::default().as_ref())) {
             return Err(BlsError::KeyDerivation);
         }
 
-        Self::derive(signature.as_ref())
+        Self::derive(signature.as_slice())
     }
 
     /// Generate a proof of possession for the corresponding pubkey
@@ -88,7 +127,8 @@ impl TryFrom<&[u8]> for SecretKey {
             return Err(BlsError::ParseFromBytes);
         }
         // unwrap safe due to the length check above
-        let scalar: Option<Scalar> = Scalar::from_bytes_le(bytes.try_into().unwrap()).into();
+        let bytes = Zeroizing::new(<[u8; BLS_SECRET_KEY_SIZE]>::try_from(bytes).unwrap());
+        let scalar: Option<Scalar> = Scalar::from_bytes_le(&bytes).into();
         scalar.ok_or(BlsError::FieldDecode).map(Self)
     }
 }
@@ -98,3 +138,63 @@ impl From<&SecretKey> for [u8; BLS_SECRET_KEY_SIZE] {
         secret_key.0.to_bytes_le()
     }
 }
+
+impl SecretKey {
+    /// Returns the secret key bytes in an array which is zeroized on drop
+    pub fn to_zeroizing_bytes(&self) -> Zeroizing<[u8; BLS_SECRET_KEY_SIZE]> {
+        Zeroizing::new(self.0.to_bytes_le())
+    }
+}
+
+impl Zeroize for SecretKey {
+    fn zeroize(&mut self) {
+        // `Scalar` doesn't implement `Zeroize`, so overwrite it the way the
+        // `zeroize` crate does, with a volatile write that can't be optimized
+        // away, followed by a fence
+        //
+        // SAFETY: `&mut self.0` is valid for writes and properly aligned, and
+        // `Scalar` is `Copy`, so overwriting it without dropping the old value
+        // doesn't leak anything
+        unsafe { ptr::write_volatile(&mut self.0, Scalar::ZERO) };
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_key_zeroize() {
+        let mut secret_key = SecretKey::new();
+        let bytes = secret_key.to_zeroizing_bytes();
+        assert_eq!(*bytes, <[u8; BLS_SECRET_KEY_SIZE]>::from(&secret_key));
+        assert_eq!(SecretKey::try_from(bytes.as_slice()).unwrap(), secret_key);
+
+        secret_key.zeroize();
+        assert_eq!(secret_key.0, Scalar::ZERO);
+    }
+
+    #[test]
+    fn test_secret_key_is_zeroized_on_drop() {
+        let mut secret_key = core::mem::MaybeUninit::new(SecretKey::new());
+        // SAFETY: the secret key is initialized and is not used after it's
+        // dropped, while its memory stays owned by the `MaybeUninit`
+        let bytes = unsafe {
+            secret_key.assume_init_drop();
+            core::slice::from_raw_parts(
+                secret_key.as_ptr() as *const u8,
+                core::mem::size_of::<SecretKey>(),
+            )
+        };
+        assert_eq!(bytes, [0; BLS_SECRET_KEY_SIZE]);
+    }
+}
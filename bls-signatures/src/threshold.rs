@@ -0,0 +1,439 @@
+//! Threshold BLS signatures.
+//!
+//! A group secret key is split into `n` shares so that any `t` of them can
+//! produce a signature under the group pubkey, while fewer than `t` learn
+//! nothing about the group key. Each shareholder signs with its share to
+//! produce a [`PartialSignature`], and any `t` partial signatures combine
+//! into an ordinary signature that verifies against the group pubkey with
+//! [`PubkeyProjective::verify`].
+//!
+//! The group secret key is the constant term of a random polynomial of
+//! degree `t - 1` and the share with index `i` is the polynomial evaluated at
+//! `i`. Partial signatures are combined by Lagrange interpolation at zero in
+//! the exponent.
+//!
+//! [`split`] trusts the dealer to hand out consistent shares. [`split_vss`]
+//! implements Feldman's verifiable secret sharing: the dealer additionally
+//! publishes a [`FeldmanCommitment`] to the polynomial, with which every
+//! shareholder can check its share, and from which anyone can compute the
+//! group pubkey and the [`VerificationKey`] of every share.
+
+use {
+    crate::{
+        error::BlsError, pubkey::PubkeyProjective, secret_key::SecretKey,
+        signature::SignatureProjective,
+    },
+    blstrs::{G1Projective, G2Projective, Scalar},
+    ff::Field,
+    group::Group,
+    rand::rngs::OsRng,
+    std::{collections::BTreeSet, vec::Vec},
+};
+
+/// A share of a group secret key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecretKeyShare {
+    /// The index of the share, starting at 1
+    pub index: u32,
+    pub secret: SecretKey,
+}
+
+impl SecretKeyShare {
+    /// Sign a message with the share
+    pub fn sign(&self, message: &[u8]) -> PartialSignature {
+        PartialSignature {
+            index: self.index,
+            signature: self.secret.sign(message),
+        }
+    }
+
+    /// The key that partial signatures by this share verify against
+    pub fn verification_key(&self) -> VerificationKey {
+        VerificationKey {
+            index: self.index,
+            pubkey: PubkeyProjective::from_secret(&self.secret),
+        }
+    }
+}
+
+/// The public key of a share, which partial signatures by the share verify
+/// against
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VerificationKey {
+    /// The index of the share, starting at 1
+    pub index: u32,
+    pub pubkey: PubkeyProjective,
+}
+
+/// A signature by one share of a group secret key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PartialSignature {
+    /// The index of the share that signed, starting at 1
+    pub index: u32,
+    pub signature: SignatureProjective,
+}
+
+impl PartialSignature {
+    /// Verify the partial signature against the verification key of its
+    /// share
+    pub fn verify(&self, verification_key: &VerificationKey, message: &[u8]) -> bool {
+        self.index == verification_key.index
+            && verification_key.pubkey.verify(&self.signature, message)
+    }
+}
+
+/// The shares of a group secret key split by a trusted dealer
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DealtShares {
+    /// The pubkey that combined signatures verify against
+    pub group_pubkey: PubkeyProjective,
+    /// The shares, with indexes `1..=n`
+    pub shares: Vec<SecretKeyShare>,
+    /// The verification keys of the shares, in the same order
+    pub verification_keys: Vec<VerificationKey>,
+}
+
+/// Split `secret` into `num_shares` shares, any `threshold` of which can sign
+/// under the pubkey of `secret`
+pub fn split(
+    secret: &SecretKey,
+    threshold: usize,
+    num_shares: usize,
+) -> Result<DealtShares, BlsError> {
+    let (shares, commitment) = split_vss(secret, threshold, num_shares)?;
+    let verification_keys = shares
+        .iter()
+        .map(SecretKeyShare::verification_key)
+        .collect();
+    Ok(DealtShares {
+        group_pubkey: commitment.group_pubkey(),
+        shares,
+        verification_keys,
+    })
+}
+
+/// Commitments to the coefficients of the polynomial that a group secret key
+/// was split with, which let shareholders verify their shares
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeldmanCommitment(Vec<PubkeyProjective>);
+
+impl FeldmanCommitment {
+    /// The number of shares needed to sign
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The commitments to the coefficients, starting with the constant term
+    pub fn coefficients(&self) -> &[PubkeyProjective] {
+        &self.0
+    }
+
+    /// The pubkey that combined signatures verify against
+    pub fn group_pubkey(&self) -> PubkeyProjective {
+        // Commitments are never empty
+        self.0[0]
+    }
+
+    /// The verification key of the share with `index`, computed without the
+    /// share
+    #[allow(clippy::arithmetic_side_effects)]
+    pub fn verification_key(&self, index: u32) -> VerificationKey {
+        let x = Scalar::from(u64::from(index));
+        let pubkey = self
+            .0
+            .iter()
+            .rev()
+            .fold(G1Projective::identity(), |acc, coefficient| {
+                acc * x + coefficient.0
+            });
+        VerificationKey {
+            index,
+            pubkey: PubkeyProjective(pubkey),
+        }
+    }
+
+    /// Check that `share` is consistent with the committed polynomial
+    pub fn verify_share(&self, share: &SecretKeyShare) -> bool {
+        share.index != 0 && self.verification_key(share.index) == share.verification_key()
+    }
+}
+
+impl TryFrom<Vec<PubkeyProjective>> for FeldmanCommitment {
+    type Error = BlsError;
+
+    /// Reconstruct a published commitment from its coefficients
+    fn try_from(coefficients: Vec<PubkeyProjective>) -> Result<Self, Self::Error> {
+        if coefficients.is_empty() {
+            return Err(BlsError::InvalidThreshold);
+        }
+        Ok(Self(coefficients))
+    }
+}
+
+/// Split `secret` into `num_shares` shares, any `threshold` of which can sign
+/// under the pubkey of `secret`, along with a Feldman commitment that every
+/// shareholder can verify its share against
+#[allow(clippy::arithmetic_side_effects)]
+pub fn split_vss(
+    secret: &SecretKey,
+    threshold: usize,
+    num_shares: usize,
+) -> Result<(Vec<SecretKeyShare>, FeldmanCommitment), BlsError> {
+    if threshold == 0 || threshold > num_shares {
+        return Err(BlsError::InvalidThreshold);
+    }
+    let num_shares = u32::try_from(num_shares).map_err(|_| BlsError::InvalidThreshold)?;
+    // The coefficients are secret, and `SecretKey` zeroizes them on drop
+    let coefficients: Vec<SecretKey> = core::iter::once(secret.clone())
+        .chain((1..threshold).map(|_| SecretKey(Scalar::random(OsRng))))
+        .collect();
+    let shares = (1..=num_shares)
+        .map(|index| {
+            let x = Scalar::from(u64::from(index));
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient.0);
+            SecretKeyShare {
+                index,
+                secret: SecretKey(y),
+            }
+        })
+        .collect();
+    let commitment = coefficients
+        .iter()
+        .map(PubkeyProjective::from_secret)
+        .collect();
+    Ok((shares, FeldmanCommitment(commitment)))
+}
+
+/// The Lagrange coefficients at zero of `indexes`
+#[allow(clippy::arithmetic_side_effects)]
+fn lagrange_coefficients(indexes: &[u32]) -> Result<Vec<Scalar>, BlsError> {
+    let xs: Vec<Scalar> = indexes
+        .iter()
+        .map(|index| Scalar::from(u64::from(*index)))
+        .collect();
+    xs.iter()
+        .enumerate()
+        .map(|(i, x_i)| {
+            let (numerator, denominator) = xs.iter().enumerate().filter(|(j, _)| *j != i).fold(
+                (Scalar::ONE, Scalar::ONE),
+                |(numerator, denominator), (_, x_j)| (numerator * x_j, denominator * (x_j - x_i)),
+            );
+            Option::from(denominator.invert())
+                .map(|inverse: Scalar| numerator * inverse)
+                .ok_or(BlsError::InvalidShareIndex)
+        })
+        .collect()
+}
+
+/// Combine `threshold` partial signatures by distinct shares into a
+/// signature under the group pubkey. Extra partial signatures are ignored.
+///
+/// The partial signatures aren't verified, so an invalid one results in an
+/// invalid signature. Use [`combine_verified`] when partial signatures come
+/// from untrusted shareholders.
+#[allow(clippy::arithmetic_side_effects)]
+pub fn combine<'a, I>(threshold: usize, partials: I) -> Result<SignatureProjective, BlsError>
+where
+    I: IntoIterator<Item = &'a PartialSignature>,
+{
+    if threshold == 0 {
+        return Err(BlsError::InvalidThreshold);
+    }
+    let mut seen = BTreeSet::new();
+    let mut selected = Vec::with_capacity(threshold);
+    for partial in partials {
+        if partial.index == 0 {
+            return Err(BlsError::InvalidShareIndex);
+        }
+        if !seen.insert(partial.index) {
+            return Err(BlsError::DuplicateShareIndex(partial.index));
+        }
+        if selected.len() < threshold {
+            selected.push(partial);
+        }
+    }
+    if selected.len() < threshold {
+        return Err(BlsError::NotEnoughShares {
+            threshold,
+            provided: selected.len(),
+        });
+    }
+
+    let indexes: Vec<u32> = selected.iter().map(|partial| partial.index).collect();
+    let coefficients = lagrange_coefficients(&indexes)?;
+    let signature = selected
+        .iter()
+        .zip(coefficients)
+        .fold(G2Projective::identity(), |acc, (partial, coefficient)| {
+            acc + partial.signature.0 * coefficient
+        });
+    Ok(SignatureProjective(signature))
+}
+
+/// Verify partial signatures of `message` against the verification keys of
+/// their shares, and combine `threshold` valid ones into a signature under
+/// the group pubkey. Invalid partial signatures, and those by shares without
+/// a verification key, are skipped.
+pub fn combine_verified<'a, I>(
+    threshold: usize,
+    partials: I,
+    verification_keys: &[VerificationKey],
+    message: &[u8],
+) -> Result<SignatureProjective, BlsError>
+where
+    I: IntoIterator<Item = &'a PartialSignature>,
+{
+    let mut valid = Vec::with_capacity(threshold);
+    let mut seen = BTreeSet::new();
+    for partial in partials {
+        if valid.len() == threshold {
+            break;
+        }
+        let is_valid = verification_keys
+            .iter()
+            .find(|key| key.index == partial.index)
+            .is_some_and(|key| partial.verify(key, message));
+        if is_valid && seen.insert(partial.index) {
+            valid.push(partial);
+        }
+    }
+    combine(threshold, valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{keypair::Keypair, pubkey::Pubkey, signature::Signature},
+    };
+
+    #[test]
+    fn test_threshold_sign_and_combine() {
+        let group = Keypair::new();
+        let dealt = split(&group.secret, 3, 5).unwrap();
+        assert_eq!(dealt.group_pubkey, group.public);
+        assert_eq!(dealt.shares.len(), 5);
+
+        let message = b"randomness beacon round 7";
+        let partials: Vec<PartialSignature> = dealt
+            .shares
+            .iter()
+            .map(|share| share.sign(message))
+            .collect();
+        for (partial, key) in partials.iter().zip(&dealt.verification_keys) {
+            assert!(partial.verify(key, message));
+            assert!(!partial.verify(key, b"other message"));
+        }
+
+        // Every subset of three shares produces the same signature, which is
+        // the signature of the group secret key
+        let expected = group.sign(message);
+        for subset in [[0, 1, 2], [0, 2, 4], [4, 3, 1], [1, 3, 4]] {
+            let subset: Vec<&PartialSignature> = subset.iter().map(|i| &partials[*i]).collect();
+            let signature = combine(3, subset).unwrap();
+            assert_eq!(signature, expected);
+
+            let signature = Signature::from(&signature);
+            let group_pubkey = Pubkey::from(&dealt.group_pubkey);
+            let signature = SignatureProjective::try_from(&signature).unwrap();
+            let group_pubkey = PubkeyProjective::try_from(&group_pubkey).unwrap();
+            assert!(group_pubkey.verify(&signature, message));
+        }
+
+        // Two shares are not enough
+        assert_eq!(
+            combine(3, &partials[..2]),
+            Err(BlsError::NotEnoughShares {
+                threshold: 3,
+                provided: 2
+            })
+        );
+        // Two partial signatures of the same share don't count twice
+        assert_eq!(
+            combine(3, [&partials[0], &partials[1], &partials[0]]),
+            Err(BlsError::DuplicateShareIndex(1))
+        );
+    }
+
+    #[test]
+    fn test_combine_verified_skips_invalid() {
+        let group = Keypair::new();
+        let dealt = split(&group.secret, 2, 4).unwrap();
+        let message = b"bridge transfer";
+        let mut partials: Vec<PartialSignature> = dealt
+            .shares
+            .iter()
+            .map(|share| share.sign(message))
+            .collect();
+        // A malicious shareholder signs something else
+        partials[0] = dealt.shares[0].sign(b"other message");
+
+        // Unverified, the invalid partial signature corrupts the result
+        assert_ne!(combine(2, &partials).unwrap(), group.sign(message));
+        let signature = combine_verified(2, &partials, &dealt.verification_keys, message).unwrap();
+        assert!(dealt.group_pubkey.verify(&signature, message));
+
+        // Only one valid partial signature remains without the third share
+        assert_eq!(
+            combine_verified(2, &partials[..2], &dealt.verification_keys, message),
+            Err(BlsError::NotEnoughShares {
+                threshold: 2,
+                provided: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_feldman_vss() {
+        let group = Keypair::new();
+        let (shares, commitment) = split_vss(&group.secret, 3, 4).unwrap();
+        assert_eq!(commitment.threshold(), 3);
+        assert_eq!(commitment.group_pubkey(), group.public);
+        assert_eq!(
+            FeldmanCommitment::try_from(commitment.coefficients().to_vec()),
+            Ok(commitment.clone())
+        );
+        assert_eq!(
+            FeldmanCommitment::try_from(Vec::new()),
+            Err(BlsError::InvalidThreshold)
+        );
+        for share in &shares {
+            assert!(commitment.verify_share(share));
+            assert_eq!(
+                commitment.verification_key(share.index),
+                share.verification_key()
+            );
+        }
+
+        // A share that's inconsistent with the commitment is detected
+        let mut bad_share = shares[1].clone();
+        bad_share.secret = SecretKey::new();
+        assert!(!commitment.verify_share(&bad_share));
+        let mut moved_share = shares[1].clone();
+        moved_share.index = 3;
+        assert!(!commitment.verify_share(&moved_share));
+
+        let message = b"message";
+        let partials: Vec<PartialSignature> =
+            shares.iter().map(|share| share.sign(message)).collect();
+        let signature = combine(3, &partials[1..]).unwrap();
+        assert!(commitment.group_pubkey().verify(&signature, message));
+    }
+
+    #[test]
+    fn test_split_invalid_threshold() {
+        let secret = SecretKey::new();
+        for (threshold, num_shares) in [(0, 3), (4, 3), (1, 0)] {
+            assert_eq!(
+                split(&secret, threshold, num_shares),
+                Err(BlsError::InvalidThreshold)
+            );
+        }
+        // A threshold of one gives every shareholder the group secret key
+        let dealt = split(&secret, 1, 2).unwrap();
+        assert!(dealt.shares.iter().all(|share| share.secret == secret));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod prelude {
     pub use crate::compression::{
-        alt_bn128_compression_size::*, consts::*, target_arch::*, AltBn128CompressionError,
+        alt_bn128_compression_size::*, consts::*, convert_endianness, target_arch::*,
+        AltBn128CompressionError,
     };
 }
 
@@ -65,6 +66,24 @@ impl From<AltBn128CompressionError> for u64 {
     }
 }
 
+/// Reverse the byte order of each `CHUNK_SIZE`-byte chunk of `bytes`, which
+/// converts field elements between the big-endian encoding of the syscalls
+/// and the little-endian encoding of arkworks. `ARRAY_SIZE` must be a
+/// multiple of `CHUNK_SIZE`; trailing bytes of a partial chunk are zeroed.
+pub fn convert_endianness<const CHUNK_SIZE: usize, const ARRAY_SIZE: usize>(
+    bytes: &[u8; ARRAY_SIZE],
+) -> [u8; ARRAY_SIZE] {
+    let reversed: [_; ARRAY_SIZE] = bytes
+        .chunks_exact(CHUNK_SIZE)
+        .flat_map(|chunk| chunk.iter().rev().copied())
+        .enumerate()
+        .fold([0u8; ARRAY_SIZE], |mut acc, (i, v)| {
+            acc[i] = v;
+            acc
+        });
+    reversed
+}
+
 #[cfg(not(target_os = "solana"))]
 mod target_arch {
 
@@ -172,20 +191,6 @@ mod target_arch {
             .map_err(|_| AltBn128CompressionError::G2CompressionFailed)?;
         Ok(convert_endianness::<64, 64>(&g2_bytes))
     }
-
-    pub fn convert_endianness<const CHUNK_SIZE: usize, const ARRAY_SIZE: usize>(
-        bytes: &[u8; ARRAY_SIZE],
-    ) -> [u8; ARRAY_SIZE] {
-        let reversed: [_; ARRAY_SIZE] = bytes
-            .chunks_exact(CHUNK_SIZE)
-            .flat_map(|chunk| chunk.iter().rev().copied())
-            .enumerate()
-            .fold([0u8; ARRAY_SIZE], |mut acc, (i, v)| {
-                acc[i] = v;
-                acc
-            });
-        reversed
-    }
 }
 
 #[cfg(target_os = "solana")]
@@ -276,7 +281,7 @@ mod target_arch {
 mod tests {
     use {
         super::*,
-        crate::compression::target_arch::convert_endianness,
+        crate::compression::convert_endianness,
         ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate},
         std::ops::Neg,
         target_arch::{
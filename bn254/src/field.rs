@@ -0,0 +1,485 @@
+//! Arithmetic in the base field `Fq` and the scalar field `Fr` of BN254.
+//!
+//! Elements are kept in Montgomery form, as four little-endian 64-bit limbs,
+//! which is also the internal representation of the `ark-bn254` crate. Byte
+//! encodings are canonical, either big-endian as in EIP-197 and the syscalls,
+//! or little-endian as in the arkworks implementation. The arithmetic is
+//! plain Rust, so it runs on-chain as well as off-chain.
+
+use {
+    crate::compression::convert_endianness,
+    core::{
+        fmt,
+        ops::{Add, Mul, Neg, Sub},
+    },
+};
+
+const LIMBS: usize = 4;
+const FIELD_SIZE: usize = 32;
+
+type Limbs = [u64; LIMBS];
+
+/// `a + b + carry`, and the carry out
+const fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let sum = a as u128 + b as u128 + carry as u128;
+    (sum as u64, (sum >> 64) as u64)
+}
+
+/// `a - b - borrow`, and the borrow out
+const fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let difference = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (difference as u64, (difference >> 127) as u64)
+}
+
+/// `a + b * c + carry`, and the carry out
+const fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let sum = a as u128 + b as u128 * c as u128 + carry as u128;
+    (sum as u64, (sum >> 64) as u64)
+}
+
+fn is_less(a: &Limbs, b: &Limbs) -> bool {
+    for (a, b) in a.iter().zip(b).rev() {
+        if a != b {
+            return a < b;
+        }
+    }
+    false
+}
+
+fn add_limbs(a: &Limbs, b: &Limbs) -> (Limbs, u64) {
+    let mut sum = [0; LIMBS];
+    let mut carry = 0;
+    for ((sum, a), b) in sum.iter_mut().zip(a).zip(b) {
+        (*sum, carry) = adc(*a, *b, carry);
+    }
+    (sum, carry)
+}
+
+fn sub_limbs(a: &Limbs, b: &Limbs) -> (Limbs, u64) {
+    let mut difference = [0; LIMBS];
+    let mut borrow = 0;
+    for ((difference, a), b) in difference.iter_mut().zip(a).zip(b) {
+        (*difference, borrow) = sbb(*a, *b, borrow);
+    }
+    (difference, borrow)
+}
+
+/// `a + b mod modulus`, for `a` and `b` reduced
+fn add_mod(a: &Limbs, b: &Limbs, modulus: &Limbs) -> Limbs {
+    // Both moduli are below 2^255, so the sum can't overflow
+    let (sum, _) = add_limbs(a, b);
+    if is_less(&sum, modulus) {
+        sum
+    } else {
+        sub_limbs(&sum, modulus).0
+    }
+}
+
+/// `a - b mod modulus`, for `a` and `b` reduced
+fn sub_mod(a: &Limbs, b: &Limbs, modulus: &Limbs) -> Limbs {
+    let (difference, borrow) = sub_limbs(a, b);
+    if borrow == 0 {
+        difference
+    } else {
+        add_limbs(&difference, modulus).0
+    }
+}
+
+/// `a * b / 2^256 mod modulus`, for `a` and `b` reduced, where `inv` is
+/// `-modulus^-1 mod 2^64`
+fn montgomery_mul(a: &Limbs, b: &Limbs, modulus: &Limbs, inv: u64) -> Limbs {
+    let mut t = [0u64; LIMBS + 1];
+    for b in b {
+        let mut carry = 0;
+        for (t, a) in t.iter_mut().zip(a) {
+            (*t, carry) = mac(*t, *a, *b, carry);
+        }
+        let (high, overflow) = adc(t[LIMBS], carry, 0);
+
+        let m = t[0].wrapping_mul(inv);
+        let (_, mut carry) = mac(t[0], m, modulus[0], 0);
+        for j in 1..LIMBS {
+            (t[j - 1], carry) = mac(t[j], m, modulus[j], carry);
+        }
+        (t[LIMBS - 1], carry) = adc(high, carry, 0);
+        t[LIMBS] = overflow + carry;
+    }
+
+    let mut result = [0; LIMBS];
+    result.copy_from_slice(&t[..LIMBS]);
+    if t[LIMBS] != 0 || !is_less(&result, modulus) {
+        result = sub_limbs(&result, modulus).0;
+    }
+    result
+}
+
+fn limbs_from_le_bytes(bytes: &[u8; FIELD_SIZE]) -> Limbs {
+    let mut limbs = [0; LIMBS];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    limbs
+}
+
+fn limbs_to_le_bytes(limbs: &Limbs) -> [u8; FIELD_SIZE] {
+    let mut bytes = [0; FIELD_SIZE];
+    for (chunk, limb) in bytes.chunks_exact_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+macro_rules! field_element {
+    (
+        $(#[$meta:meta])*
+        $name:ident,
+        modulus: $modulus:expr,
+        r: $r:expr,
+        r2: $r2:expr,
+        inv: $inv:expr,
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $name(Limbs);
+
+        impl $name {
+            /// The modulus of the field, as little-endian limbs
+            pub const MODULUS: [u64; 4] = $modulus;
+            /// `2^512 mod MODULUS`, to convert into Montgomery form
+            const R2: Limbs = $r2;
+            /// `-MODULUS^-1 mod 2^64`
+            const INV: u64 = $inv;
+
+            pub const ZERO: Self = Self([0; LIMBS]);
+            pub const ONE: Self = Self($r);
+
+            fn from_canonical_limbs(limbs: Limbs) -> Option<Self> {
+                is_less(&limbs, &Self::MODULUS).then(|| {
+                    Self(montgomery_mul(
+                        &limbs,
+                        &Self::R2,
+                        &Self::MODULUS,
+                        Self::INV,
+                    ))
+                })
+            }
+
+            fn to_canonical_limbs(self) -> Limbs {
+                montgomery_mul(&self.0, &[1, 0, 0, 0], &Self::MODULUS, Self::INV)
+            }
+
+            pub fn from_u64(value: u64) -> Self {
+                // Any u64 is smaller than the modulus
+                Self::from_canonical_limbs([value, 0, 0, 0]).unwrap()
+            }
+
+            /// Decode a little-endian encoding, or `None` if it isn't
+            /// smaller than the modulus
+            pub fn from_le_bytes(bytes: &[u8; FIELD_SIZE]) -> Option<Self> {
+                Self::from_canonical_limbs(limbs_from_le_bytes(bytes))
+            }
+
+            /// Decode a big-endian encoding, or `None` if it isn't smaller
+            /// than the modulus
+            pub fn from_be_bytes(bytes: &[u8; FIELD_SIZE]) -> Option<Self> {
+                Self::from_le_bytes(&convert_endianness::<FIELD_SIZE, FIELD_SIZE>(bytes))
+            }
+
+            /// Decode a big-endian encoding of any 256-bit integer, reduced
+            /// modulo the modulus
+            pub fn from_be_bytes_mod_order(bytes: &[u8; FIELD_SIZE]) -> Self {
+                let mut limbs =
+                    limbs_from_le_bytes(&convert_endianness::<FIELD_SIZE, FIELD_SIZE>(bytes));
+                while !is_less(&limbs, &Self::MODULUS) {
+                    limbs = sub_limbs(&limbs, &Self::MODULUS).0;
+                }
+                Self::from_canonical_limbs(limbs).unwrap()
+            }
+
+            pub fn to_le_bytes(self) -> [u8; FIELD_SIZE] {
+                limbs_to_le_bytes(&self.to_canonical_limbs())
+            }
+
+            pub fn to_be_bytes(self) -> [u8; FIELD_SIZE] {
+                convert_endianness::<FIELD_SIZE, FIELD_SIZE>(&self.to_le_bytes())
+            }
+
+            /// Construct an element from its Montgomery form, as used by
+            /// `ark_ff::Fp::new_unchecked`, or `None` if it isn't reduced
+            pub fn from_montgomery_limbs(limbs: [u64; 4]) -> Option<Self> {
+                is_less(&limbs, &Self::MODULUS).then_some(Self(limbs))
+            }
+
+            /// The Montgomery form of the element, `self * 2^256 mod MODULUS`
+            pub fn to_montgomery_limbs(self) -> [u64; 4] {
+                self.0
+            }
+
+            pub fn is_zero(&self) -> bool {
+                *self == Self::ZERO
+            }
+
+            pub fn square(&self) -> Self {
+                *self * *self
+            }
+
+            /// `self` to the power of `exponent`, given as little-endian limbs
+            pub fn pow(&self, exponent: &[u64; 4]) -> Self {
+                let mut result = Self::ONE;
+                for limb in exponent.iter().rev() {
+                    for bit in (0..64).rev() {
+                        result = result.square();
+                        if (limb >> bit) & 1 == 1 {
+                            result = result * *self;
+                        }
+                    }
+                }
+                result
+            }
+
+            /// The multiplicative inverse, or `None` for zero
+            pub fn inverse(&self) -> Option<Self> {
+                if self.is_zero() {
+                    return None;
+                }
+                // By Fermat's little theorem. The low limb of both moduli is
+                // larger than 2.
+                let mut exponent = Self::MODULUS;
+                exponent[0] = exponent[0].wrapping_sub(2);
+                Some(self.pow(&exponent))
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self(add_mod(&self.0, &other.0, &Self::MODULUS))
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self(sub_mod(&self.0, &other.0, &Self::MODULUS))
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            fn mul(self, other: Self) -> Self {
+                Self(montgomery_mul(&self.0, &other.0, &Self::MODULUS, Self::INV))
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(sub_mod(&[0; LIMBS], &self.0, &Self::MODULUS))
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(value: u64) -> Self {
+                Self::from_u64(value)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}(0x", stringify!($name))?;
+                for byte in self.to_be_bytes() {
+                    write!(f, "{byte:02x}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    };
+}
+
+field_element!(
+    /// An element of the base field of BN254, over which the curve points
+    /// are defined
+    Fq,
+    modulus: [
+        0x3c208c16d87cfd47,
+        0x97816a916871ca8d,
+        0xb85045b68181585d,
+        0x30644e72e131a029,
+    ],
+    r: [
+        0xd35d438dc58f0d9d,
+        0x0a78eb28f5c70b3d,
+        0x666ea36f7879462c,
+        0x0e0a77c19a07df2f,
+    ],
+    r2: [
+        0xf32cfc5b538afa89,
+        0xb5e71911d44501fb,
+        0x47ab1eff0a417ff6,
+        0x06d89f71cab8351f,
+    ],
+    inv: 0x87d20782e4866389,
+);
+
+field_element!(
+    /// An element of the scalar field of BN254, the integers modulo the
+    /// order of the groups
+    Fr,
+    modulus: [
+        0x43e1f593f0000001,
+        0x2833e84879b97091,
+        0xb85045b68181585d,
+        0x30644e72e131a029,
+    ],
+    r: [
+        0xac96341c4ffffffb,
+        0x36fc76959f60cd29,
+        0x666ea36f7879462e,
+        0x0e0a77c19a07df2f,
+    ],
+    r2: [
+        0x1bb8e645ae216da7,
+        0x53fe3ab1e35c59e3,
+        0x8c49833d53bb8085,
+        0x0216d0b17f4e44a5,
+    ],
+    inv: 0xc2e1f593efffffff,
+);
+
+impl Fq {
+    /// `(MODULUS + 1) / 4`, since the modulus is 3 mod 4
+    const SQRT_EXPONENT: Limbs = [
+        0x4f082305b61f3f52,
+        0x65e05aa45a1c72a3,
+        0x6e14116da0605617,
+        0x0c19139cb84c680a,
+    ];
+
+    /// A square root, or `None` if the element isn't a square. The other
+    /// root is the negation of the returned one.
+    pub fn sqrt(&self) -> Option<Self> {
+        let root = self.pow(&Self::SQRT_EXPONENT);
+        (root.square() == *self).then_some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        ark_ff::{BigInteger, Field, PrimeField},
+    };
+
+    fn hex_to_bytes(hex: &str) -> [u8; 32] {
+        array_bytes::hex2array_unchecked(hex)
+    }
+
+    #[test]
+    fn test_known_answers() {
+        // (p + 1) / 2 is the inverse of 2
+        assert_eq!(
+            Fq::from(2).inverse().unwrap().to_be_bytes(),
+            hex_to_bytes("183227397098d014dc2822db40c0ac2ecbc0b548b438e5469e10460b6c3e7ea4")
+        );
+        assert_eq!(
+            Fr::from(2).inverse().unwrap().to_be_bytes(),
+            hex_to_bytes("183227397098d014dc2822db40c0ac2e9419f4243cdcb848a1f0fac9f8000001")
+        );
+        // p - 1 is -1
+        assert_eq!(
+            (-Fq::ONE).to_be_bytes(),
+            hex_to_bytes("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd46")
+        );
+        assert_eq!(Fq::ONE.to_montgomery_limbs(), Fq::from(1).0);
+        assert_eq!(Fr::ZERO.inverse(), None);
+
+        // The y coordinate of the generator of G1 is 2 = sqrt(1 + 3)
+        let root = Fq::from(4).sqrt().unwrap();
+        assert!(root == Fq::from(2) || root == -Fq::from(2));
+        // -1 isn't a square when p is 3 mod 4
+        assert_eq!((-Fq::ONE).sqrt(), None);
+
+        let modulus =
+            hex_to_bytes("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+        assert_eq!(Fr::from_be_bytes(&modulus), None);
+        assert_eq!(Fr::from_be_bytes_mod_order(&modulus), Fr::ZERO);
+        assert_eq!(Fr::from_be_bytes_mod_order(&[0xff; 32]).to_be_bytes(), {
+            // 2^256 - 1 - 5r
+            hex_to_bytes("0e0a77c19a07df2f666ea36f7879462e36fc76959f60cd29ac96341c4ffffffa")
+        });
+        assert_eq!(Fr::from_montgomery_limbs(Fr::MODULUS), None);
+    }
+
+    #[test]
+    #[allow(clippy::arithmetic_side_effects)]
+    fn test_arkworks_compatibility() {
+        let values = [
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef",
+            "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+            "2a1c84bc8fa1a6ae2de8f1c5e2e98e3e43b8ad3bfc4f2ea59d4fda9f1e4bd8a1",
+        ];
+        for a in values {
+            for b in values {
+                let (a, b) = (hex_to_bytes(a), hex_to_bytes(b));
+
+                let (fq_a, fq_b) = (
+                    Fq::from_be_bytes(&a).unwrap(),
+                    Fq::from_be_bytes(&b).unwrap(),
+                );
+                let ark_a = ark_bn254::Fq::from_be_bytes_mod_order(&a);
+                let ark_b = ark_bn254::Fq::from_be_bytes_mod_order(&b);
+                assert_eq!(fq_a.to_montgomery_limbs(), ark_a.0 .0);
+                for (result, expected) in [
+                    (fq_a + fq_b, ark_a + ark_b),
+                    (fq_a - fq_b, ark_a - ark_b),
+                    (fq_a * fq_b, ark_a * ark_b),
+                    (-fq_a, -ark_a),
+                    (fq_a.square(), ark_a.square()),
+                ] {
+                    assert_eq!(
+                        result.to_be_bytes()[..],
+                        expected.into_bigint().to_bytes_be()
+                    );
+                }
+                assert_eq!(
+                    fq_a.inverse().map(|inverse| inverse.to_le_bytes().to_vec()),
+                    ark_a
+                        .inverse()
+                        .map(|inverse| inverse.into_bigint().to_bytes_le())
+                );
+
+                let (fr_a, fr_b) = (
+                    Fr::from_be_bytes(&a).unwrap(),
+                    Fr::from_be_bytes(&b).unwrap(),
+                );
+                let ark_a = ark_bn254::Fr::from_be_bytes_mod_order(&a);
+                let ark_b = ark_bn254::Fr::from_be_bytes_mod_order(&b);
+                assert_eq!(fr_a.to_montgomery_limbs(), ark_a.0 .0);
+                for (result, expected) in [
+                    (fr_a + fr_b, ark_a + ark_b),
+                    (fr_a - fr_b, ark_a - ark_b),
+                    (fr_a * fr_b, ark_a * ark_b),
+                    (-fr_a, -ark_a),
+                ] {
+                    assert_eq!(
+                        result.to_be_bytes()[..],
+                        expected.into_bigint().to_bytes_be()
+                    );
+                }
+                assert_eq!(
+                    fr_a.inverse().map(|inverse| inverse.to_be_bytes().to_vec()),
+                    ark_a
+                        .inverse()
+                        .map(|inverse| inverse.into_bigint().to_bytes_be())
+                );
+            }
+        }
+    }
+}
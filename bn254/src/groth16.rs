@@ -0,0 +1,712 @@
+//! Groth16 proof verification on the alt_bn128 syscalls.
+//!
+//! Verifying keys and proofs are kept in the EIP-197 (big-endian) encoding
+//! expected by the syscalls, so that they can be stored in accounts and
+//! instruction data as is. Verification prepares the public inputs with
+//! [`alt_bn128_multiplication`] and [`alt_bn128_addition`], then checks
+//!
+//! ```text
+//! e(-A, B) * e(alpha, beta) * e(inputs, gamma) * e(C, delta) == 1
+//! ```
+//!
+//! with a single call to [`alt_bn128_pairing`]. The same code runs on-chain
+//! through the syscalls and off-chain through arkworks.
+//!
+//! Off-chain, verifying keys and proofs can be imported from the canonical
+//! serialization of `ark-groth16`, and with the `snarkjs` feature from the
+//! JSON files written by snarkjs.
+
+use {
+    crate::{
+        prelude::{
+            alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing, ALT_BN128_FIELD_SIZE,
+            ALT_BN128_PAIRING_ELEMENT_LEN, ALT_BN128_PAIRING_OUTPUT_LEN, ALT_BN128_POINT_SIZE,
+        },
+        AltBn128Error, G2_POINT_SIZE,
+    },
+    thiserror::Error,
+};
+#[cfg(feature = "serde")]
+use {
+    serde_derive::{Deserialize, Serialize},
+    serde_with::{serde_as, Bytes},
+};
+
+/// The modulus of the base field of BN254, big-endian
+const BASE_FIELD_MODULUS: [u8; ALT_BN128_FIELD_SIZE] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// The modulus of the scalar field of BN254, big-endian. Public inputs must
+/// be smaller.
+const SCALAR_FIELD_MODULUS: [u8; ALT_BN128_FIELD_SIZE] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum Groth16Error {
+    #[error("Expected {expected} public inputs, got {actual}")]
+    InvalidPublicInputsLength { expected: usize, actual: usize },
+    #[error("Public input is not smaller than the scalar field modulus")]
+    PublicInputGreaterThanFieldSize,
+    #[error("The verifying key has no input commitments")]
+    InvalidVerifyingKey,
+    #[error("Invalid group element")]
+    InvalidPoint,
+    #[error("Invalid field element")]
+    InvalidFieldElement,
+    #[error("Invalid serialization: {0}")]
+    InvalidSerialization(String),
+    #[error("Proof verification failed")]
+    ProofVerificationFailed,
+    #[error(transparent)]
+    AltBn128(#[from] AltBn128Error),
+}
+
+/// A Groth16 proof, with its points in the EIP-197 encoding
+#[cfg_attr(feature = "serde", cfg_eval::cfg_eval, serde_as)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Proof {
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub a: [u8; ALT_BN128_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub b: [u8; G2_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub c: [u8; ALT_BN128_POINT_SIZE],
+}
+
+/// A Groth16 verifying key, with its points in the EIP-197 encoding.
+///
+/// `ic` holds one point for the constant term followed by one point for each
+/// public input.
+#[cfg_attr(feature = "serde", cfg_eval::cfg_eval, serde_as)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKey {
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub alpha_g1: [u8; ALT_BN128_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub beta_g2: [u8; G2_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub gamma_g2: [u8; G2_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Bytes"))]
+    pub delta_g2: [u8; G2_POINT_SIZE],
+    #[cfg_attr(feature = "serde", serde_as(as = "Vec<Bytes>"))]
+    pub ic: Vec<[u8; ALT_BN128_POINT_SIZE]>,
+}
+
+impl VerifyingKey {
+    /// The number of public inputs of the circuit
+    pub fn num_public_inputs(&self) -> usize {
+        self.ic.len().saturating_sub(1)
+    }
+
+    /// Combine the public inputs, 32-byte big-endian scalars, into the G1
+    /// point that is paired with `gamma`
+    pub fn prepare_inputs(
+        &self,
+        public_inputs: &[[u8; ALT_BN128_FIELD_SIZE]],
+    ) -> Result<[u8; ALT_BN128_POINT_SIZE], Groth16Error> {
+        let (constant, input_points) = self
+            .ic
+            .split_first()
+            .ok_or(Groth16Error::InvalidVerifyingKey)?;
+        if public_inputs.len() != input_points.len() {
+            return Err(Groth16Error::InvalidPublicInputsLength {
+                expected: input_points.len(),
+                actual: public_inputs.len(),
+            });
+        }
+
+        let mut prepared = *constant;
+        for (input, point) in public_inputs.iter().zip(input_points) {
+            // The syscall doesn't reduce scalars, so larger inputs would let
+            // the same proof verify for several inputs
+            if *input >= SCALAR_FIELD_MODULUS {
+                return Err(Groth16Error::PublicInputGreaterThanFieldSize);
+            }
+            if input.iter().all(|byte| *byte == 0) {
+                continue;
+            }
+            let product = alt_bn128_multiplication(&[&point[..], &input[..]].concat())?;
+            let sum = alt_bn128_addition(&[&prepared[..], &product[..]].concat())?;
+            prepared = sum.try_into().map_err(AltBn128Error::TryIntoVecError)?;
+        }
+        Ok(prepared)
+    }
+
+    /// Verify `proof` for `public_inputs`, 32-byte big-endian scalars
+    pub fn verify(
+        &self,
+        proof: &Proof,
+        public_inputs: &[[u8; ALT_BN128_FIELD_SIZE]],
+    ) -> Result<(), Groth16Error> {
+        let prepared_inputs = self.prepare_inputs(public_inputs)?;
+        self.verify_prepared(proof, &prepared_inputs)
+    }
+
+    /// Verify `proof` for inputs already combined with
+    /// [`prepare_inputs`](Self::prepare_inputs)
+    pub fn verify_prepared(
+        &self,
+        proof: &Proof,
+        prepared_inputs: &[u8; ALT_BN128_POINT_SIZE],
+    ) -> Result<(), Groth16Error> {
+        let mut input = Vec::with_capacity(ALT_BN128_PAIRING_ELEMENT_LEN.saturating_mul(4));
+        for (g1, g2) in [
+            (&negate_g1(&proof.a)?, &proof.b),
+            (&self.alpha_g1, &self.beta_g2),
+            (prepared_inputs, &self.gamma_g2),
+            (&proof.c, &self.delta_g2),
+        ] {
+            input.extend_from_slice(g1);
+            input.extend_from_slice(g2);
+        }
+
+        let result = alt_bn128_pairing(&input)?;
+        let mut expected = [0u8; ALT_BN128_PAIRING_OUTPUT_LEN];
+        expected[ALT_BN128_PAIRING_OUTPUT_LEN.saturating_sub(1)] = 1;
+        if result == expected {
+            Ok(())
+        } else {
+            Err(Groth16Error::ProofVerificationFailed)
+        }
+    }
+}
+
+/// Negate a G1 point, that is replace `y` with `p - y`
+fn negate_g1(
+    point: &[u8; ALT_BN128_POINT_SIZE],
+) -> Result<[u8; ALT_BN128_POINT_SIZE], Groth16Error> {
+    if point.iter().all(|byte| *byte == 0) {
+        return Ok(*point);
+    }
+    let (x, y) = point.split_at(ALT_BN128_FIELD_SIZE);
+    if *y >= BASE_FIELD_MODULUS[..] {
+        return Err(Groth16Error::InvalidPoint);
+    }
+
+    let mut negated = [0u8; ALT_BN128_POINT_SIZE];
+    negated[..ALT_BN128_FIELD_SIZE].copy_from_slice(x);
+    let mut borrow = false;
+    for ((result, modulus), y) in negated[ALT_BN128_FIELD_SIZE..]
+        .iter_mut()
+        .zip(&BASE_FIELD_MODULUS)
+        .zip(y)
+        .rev()
+    {
+        let (difference, borrow_y) = modulus.overflowing_sub(*y);
+        let (difference, borrow_carry) = difference.overflowing_sub(u8::from(borrow));
+        *result = difference;
+        borrow = borrow_y || borrow_carry;
+    }
+    Ok(negated)
+}
+
+#[cfg(not(target_os = "solana"))]
+mod arkworks {
+    use {
+        super::*,
+        ark_bn254::{Fq, G1Affine, G2Affine},
+        ark_ec::AffineRepr,
+        ark_ff::{BigInteger, PrimeField},
+        ark_serialize::{CanonicalDeserialize, Compress, Validate},
+    };
+
+    fn fq_to_be_bytes(element: &Fq) -> [u8; ALT_BN128_FIELD_SIZE] {
+        let mut bytes = [0u8; ALT_BN128_FIELD_SIZE];
+        bytes.copy_from_slice(&element.into_bigint().to_bytes_be());
+        bytes
+    }
+
+    pub(super) fn g1_to_be_bytes(point: &G1Affine) -> [u8; ALT_BN128_POINT_SIZE] {
+        let mut bytes = [0u8; ALT_BN128_POINT_SIZE];
+        if let Some((x, y)) = point.xy() {
+            bytes[..32].copy_from_slice(&fq_to_be_bytes(x));
+            bytes[32..].copy_from_slice(&fq_to_be_bytes(y));
+        }
+        bytes
+    }
+
+    pub(super) fn g2_to_be_bytes(point: &G2Affine) -> [u8; G2_POINT_SIZE] {
+        let mut bytes = [0u8; G2_POINT_SIZE];
+        if let Some((x, y)) = point.xy() {
+            // note the cross order
+            bytes[..32].copy_from_slice(&fq_to_be_bytes(&x.c1));
+            bytes[32..64].copy_from_slice(&fq_to_be_bytes(&x.c0));
+            bytes[64..96].copy_from_slice(&fq_to_be_bytes(&y.c1));
+            bytes[96..].copy_from_slice(&fq_to_be_bytes(&y.c0));
+        }
+        bytes
+    }
+
+    fn deserialize<T: CanonicalDeserialize>(
+        reader: &mut &[u8],
+        compressed: bool,
+    ) -> Result<T, Groth16Error> {
+        let compress = if compressed {
+            Compress::Yes
+        } else {
+            Compress::No
+        };
+        T::deserialize_with_mode(reader, compress, Validate::Yes)
+            .map_err(|err| Groth16Error::InvalidSerialization(err.to_string()))
+    }
+
+    fn check_consumed(reader: &[u8]) -> Result<(), Groth16Error> {
+        if reader.is_empty() {
+            Ok(())
+        } else {
+            Err(Groth16Error::InvalidSerialization(
+                "trailing bytes".to_string(),
+            ))
+        }
+    }
+
+    impl Proof {
+        /// Import a proof from the canonical serialization of an
+        /// `ark_groth16::Proof<Bn254>`, compressed or not
+        pub fn from_arkworks_bytes(bytes: &[u8], compressed: bool) -> Result<Self, Groth16Error> {
+            let mut reader = bytes;
+            let a: G1Affine = deserialize(&mut reader, compressed)?;
+            let b: G2Affine = deserialize(&mut reader, compressed)?;
+            let c: G1Affine = deserialize(&mut reader, compressed)?;
+            check_consumed(reader)?;
+            Ok(Self {
+                a: g1_to_be_bytes(&a),
+                b: g2_to_be_bytes(&b),
+                c: g1_to_be_bytes(&c),
+            })
+        }
+    }
+
+    impl VerifyingKey {
+        /// Import a verifying key from the canonical serialization of an
+        /// `ark_groth16::VerifyingKey<Bn254>`, compressed or not
+        pub fn from_arkworks_bytes(bytes: &[u8], compressed: bool) -> Result<Self, Groth16Error> {
+            let mut reader = bytes;
+            let alpha_g1: G1Affine = deserialize(&mut reader, compressed)?;
+            let beta_g2: G2Affine = deserialize(&mut reader, compressed)?;
+            let gamma_g2: G2Affine = deserialize(&mut reader, compressed)?;
+            let delta_g2: G2Affine = deserialize(&mut reader, compressed)?;
+            let ic: Vec<G1Affine> = deserialize(&mut reader, compressed)?;
+            check_consumed(reader)?;
+            if ic.is_empty() {
+                return Err(Groth16Error::InvalidVerifyingKey);
+            }
+            Ok(Self {
+                alpha_g1: g1_to_be_bytes(&alpha_g1),
+                beta_g2: g2_to_be_bytes(&beta_g2),
+                gamma_g2: g2_to_be_bytes(&gamma_g2),
+                delta_g2: g2_to_be_bytes(&delta_g2),
+                ic: ic.iter().map(g1_to_be_bytes).collect(),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "snarkjs")]
+mod snarkjs {
+    use {super::*, serde_derive::Deserialize};
+
+    #[derive(Deserialize)]
+    struct SnarkjsVerifyingKey {
+        protocol: Option<String>,
+        curve: Option<String>,
+        #[serde(rename = "nPublic")]
+        n_public: Option<usize>,
+        vk_alpha_1: Vec<String>,
+        vk_beta_2: Vec<Vec<String>>,
+        vk_gamma_2: Vec<Vec<String>>,
+        vk_delta_2: Vec<Vec<String>>,
+        #[serde(rename = "IC")]
+        ic: Vec<Vec<String>>,
+    }
+
+    #[derive(Deserialize)]
+    struct SnarkjsProof {
+        protocol: Option<String>,
+        curve: Option<String>,
+        pi_a: Vec<String>,
+        pi_b: Vec<Vec<String>>,
+        pi_c: Vec<String>,
+    }
+
+    fn parse_json<'a, T: serde::Deserialize<'a>>(json: &'a str) -> Result<T, Groth16Error> {
+        serde_json::from_str(json)
+            .map_err(|err| Groth16Error::InvalidSerialization(err.to_string()))
+    }
+
+    fn check_header(protocol: Option<&str>, curve: Option<&str>) -> Result<(), Groth16Error> {
+        if protocol.is_some_and(|protocol| protocol != "groth16") {
+            return Err(Groth16Error::InvalidSerialization(
+                "protocol is not groth16".to_string(),
+            ));
+        }
+        if curve.is_some_and(|curve| curve != "bn128" && curve != "bn254") {
+            return Err(Groth16Error::InvalidSerialization(
+                "curve is not bn128".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse a decimal string into a big-endian field element smaller than
+    /// `modulus`
+    fn parse_decimal(
+        decimal: &str,
+        modulus: &[u8; ALT_BN128_FIELD_SIZE],
+    ) -> Result<[u8; ALT_BN128_FIELD_SIZE], Groth16Error> {
+        if decimal.is_empty() {
+            return Err(Groth16Error::InvalidFieldElement);
+        }
+        let mut bytes = [0u8; ALT_BN128_FIELD_SIZE];
+        for digit in decimal.bytes() {
+            if !digit.is_ascii_digit() {
+                return Err(Groth16Error::InvalidFieldElement);
+            }
+            let mut carry = u16::from(digit.saturating_sub(b'0'));
+            for byte in bytes.iter_mut().rev() {
+                let value = u16::from(*byte).saturating_mul(10).saturating_add(carry);
+                *byte = (value & 0xff) as u8;
+                carry = value >> 8;
+            }
+            if carry != 0 {
+                return Err(Groth16Error::InvalidFieldElement);
+            }
+        }
+        if bytes >= *modulus {
+            return Err(Groth16Error::InvalidFieldElement);
+        }
+        Ok(bytes)
+    }
+
+    fn parse_g1(coordinates: &[String]) -> Result<[u8; ALT_BN128_POINT_SIZE], Groth16Error> {
+        let [x, y, z] = coordinates else {
+            return Err(Groth16Error::InvalidPoint);
+        };
+        let mut bytes = [0u8; ALT_BN128_POINT_SIZE];
+        match z.as_str() {
+            "0" => {}
+            "1" => {
+                bytes[..32].copy_from_slice(&parse_decimal(x, &BASE_FIELD_MODULUS)?);
+                bytes[32..].copy_from_slice(&parse_decimal(y, &BASE_FIELD_MODULUS)?);
+            }
+            _ => return Err(Groth16Error::InvalidPoint),
+        }
+        Ok(bytes)
+    }
+
+    fn parse_g2(coordinates: &[Vec<String>]) -> Result<[u8; G2_POINT_SIZE], Groth16Error> {
+        let [x, y, z] = coordinates else {
+            return Err(Groth16Error::InvalidPoint);
+        };
+        let ([x0, x1], [y0, y1], [z0, z1]) = (&x[..], &y[..], &z[..]) else {
+            return Err(Groth16Error::InvalidPoint);
+        };
+        let mut bytes = [0u8; G2_POINT_SIZE];
+        match (z0.as_str(), z1.as_str()) {
+            ("0", "0") => {}
+            ("1", "0") => {
+                // snarkjs lists the coefficients as [c0, c1], EIP-197 expects
+                // [c1, c0]
+                bytes[..32].copy_from_slice(&parse_decimal(x1, &BASE_FIELD_MODULUS)?);
+                bytes[32..64].copy_from_slice(&parse_decimal(x0, &BASE_FIELD_MODULUS)?);
+                bytes[64..96].copy_from_slice(&parse_decimal(y1, &BASE_FIELD_MODULUS)?);
+                bytes[96..].copy_from_slice(&parse_decimal(y0, &BASE_FIELD_MODULUS)?);
+            }
+            _ => return Err(Groth16Error::InvalidPoint),
+        }
+        Ok(bytes)
+    }
+
+    impl Proof {
+        /// Import a proof from the `proof.json` written by snarkjs
+        pub fn from_snarkjs_json(json: &str) -> Result<Self, Groth16Error> {
+            let proof: SnarkjsProof = parse_json(json)?;
+            check_header(proof.protocol.as_deref(), proof.curve.as_deref())?;
+            Ok(Self {
+                a: parse_g1(&proof.pi_a)?,
+                b: parse_g2(&proof.pi_b)?,
+                c: parse_g1(&proof.pi_c)?,
+            })
+        }
+    }
+
+    impl VerifyingKey {
+        /// Import a verifying key from the `verification_key.json` written by
+        /// snarkjs
+        pub fn from_snarkjs_json(json: &str) -> Result<Self, Groth16Error> {
+            let key: SnarkjsVerifyingKey = parse_json(json)?;
+            check_header(key.protocol.as_deref(), key.curve.as_deref())?;
+            if key.ic.is_empty() {
+                return Err(Groth16Error::InvalidVerifyingKey);
+            }
+            if key
+                .n_public
+                .is_some_and(|n_public| n_public.saturating_add(1) != key.ic.len())
+            {
+                return Err(Groth16Error::InvalidVerifyingKey);
+            }
+            Ok(Self {
+                alpha_g1: parse_g1(&key.vk_alpha_1)?,
+                beta_g2: parse_g2(&key.vk_beta_2)?,
+                gamma_g2: parse_g2(&key.vk_gamma_2)?,
+                delta_g2: parse_g2(&key.vk_delta_2)?,
+                ic: key
+                    .ic
+                    .iter()
+                    .map(|point| parse_g1(point))
+                    .collect::<Result<_, _>>()?,
+            })
+        }
+    }
+
+    /// Import public inputs from the `public.json` written by snarkjs
+    pub fn public_inputs_from_snarkjs_json(
+        json: &str,
+    ) -> Result<Vec<[u8; ALT_BN128_FIELD_SIZE]>, Groth16Error> {
+        let inputs: Vec<String> = parse_json(json)?;
+        inputs
+            .iter()
+            .map(|input| {
+                parse_decimal(input, &SCALAR_FIELD_MODULUS)
+                    .map_err(|_| Groth16Error::PublicInputGreaterThanFieldSize)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "snarkjs")]
+pub use snarkjs::public_inputs_from_snarkjs_json;
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{arkworks::*, *},
+        ark_bn254::{Fr, G1Affine, G2Affine},
+        ark_ec::{AffineRepr, CurveGroup},
+        ark_ff::{BigInteger, Field, PrimeField},
+        ark_serialize::{CanonicalSerialize, Compress},
+    };
+
+    fn fr_to_be_bytes(scalar: &Fr) -> [u8; 32] {
+        scalar.into_bigint().to_bytes_be().try_into().unwrap()
+    }
+
+    /// A verifying key for two public inputs and a valid proof for them,
+    /// built from known discrete logs rather than from a circuit
+    #[allow(clippy::arithmetic_side_effects)]
+    fn test_proof() -> (
+        G1Affine,
+        [G2Affine; 3],
+        Vec<G1Affine>,
+        [G1Affine; 2],
+        G2Affine,
+        Vec<Fr>,
+    ) {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let [alpha, beta, gamma, delta] = [11u64, 13, 17, 19].map(Fr::from);
+        let ic_scalars = [23u64, 29, 31].map(Fr::from);
+        let inputs = vec![Fr::from(1234u64), -Fr::from(5u64)];
+        let (a, b) = (Fr::from(37u64), Fr::from(41u64));
+
+        let prepared = ic_scalars[0] + inputs[0] * ic_scalars[1] + inputs[1] * ic_scalars[2];
+        let c = (a * b - alpha * beta - prepared * gamma) * delta.inverse().unwrap();
+
+        (
+            (g1 * alpha).into_affine(),
+            [beta, gamma, delta].map(|scalar| (g2 * scalar).into_affine()),
+            ic_scalars
+                .iter()
+                .map(|scalar| (g1 * scalar).into_affine())
+                .collect(),
+            [(g1 * a).into_affine(), (g1 * c).into_affine()],
+            (g2 * b).into_affine(),
+            inputs,
+        )
+    }
+
+    fn test_key_and_proof() -> (VerifyingKey, Proof, Vec<[u8; 32]>) {
+        let (alpha, [beta, gamma, delta], ic, [a, c], b, inputs) = test_proof();
+        let key = VerifyingKey {
+            alpha_g1: g1_to_be_bytes(&alpha),
+            beta_g2: g2_to_be_bytes(&beta),
+            gamma_g2: g2_to_be_bytes(&gamma),
+            delta_g2: g2_to_be_bytes(&delta),
+            ic: ic.iter().map(g1_to_be_bytes).collect(),
+        };
+        let proof = Proof {
+            a: g1_to_be_bytes(&a),
+            b: g2_to_be_bytes(&b),
+            c: g1_to_be_bytes(&c),
+        };
+        (key, proof, inputs.iter().map(fr_to_be_bytes).collect())
+    }
+
+    #[test]
+    fn test_verify() {
+        let (key, proof, inputs) = test_key_and_proof();
+        assert_eq!(key.num_public_inputs(), 2);
+        assert_eq!(key.verify(&proof, &inputs), Ok(()));
+
+        let prepared = key.prepare_inputs(&inputs).unwrap();
+        assert_eq!(key.verify_prepared(&proof, &prepared), Ok(()));
+
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs[0][31] ^= 1;
+        assert_eq!(
+            key.verify(&proof, &wrong_inputs),
+            Err(Groth16Error::ProofVerificationFailed)
+        );
+
+        let mut wrong_proof = proof;
+        wrong_proof.c = proof.a;
+        assert_eq!(
+            key.verify(&wrong_proof, &inputs),
+            Err(Groth16Error::ProofVerificationFailed)
+        );
+
+        assert_eq!(
+            key.verify(&proof, &inputs[..1]),
+            Err(Groth16Error::InvalidPublicInputsLength {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        // The same input plus the modulus must not be accepted
+        let mut unreduced = inputs.clone();
+        unreduced[1] = SCALAR_FIELD_MODULUS;
+        assert_eq!(
+            key.verify(&proof, &unreduced),
+            Err(Groth16Error::PublicInputGreaterThanFieldSize)
+        );
+
+        let empty_key = VerifyingKey {
+            ic: vec![],
+            ..key.clone()
+        };
+        assert_eq!(
+            empty_key.verify(&proof, &[]),
+            Err(Groth16Error::InvalidVerifyingKey)
+        );
+    }
+
+    #[test]
+    fn test_negate_g1() {
+        let point = G1Affine::generator();
+        assert_eq!(
+            negate_g1(&g1_to_be_bytes(&point)).unwrap(),
+            g1_to_be_bytes(&-point)
+        );
+        assert_eq!(negate_g1(&[0; 64]).unwrap(), [0; 64]);
+
+        let mut invalid = [0; 64];
+        invalid[32..].copy_from_slice(&BASE_FIELD_MODULUS);
+        assert_eq!(negate_g1(&invalid), Err(Groth16Error::InvalidPoint));
+    }
+
+    #[test]
+    fn test_from_arkworks_bytes() {
+        let (key, proof, _) = test_key_and_proof();
+        let (alpha, [beta, gamma, delta], ic, [a, c], b, _) = test_proof();
+
+        for compress in [Compress::Yes, Compress::No] {
+            let compressed = compress == Compress::Yes;
+            let mut key_bytes = vec![];
+            alpha.serialize_with_mode(&mut key_bytes, compress).unwrap();
+            for point in [beta, gamma, delta] {
+                point.serialize_with_mode(&mut key_bytes, compress).unwrap();
+            }
+            ic.serialize_with_mode(&mut key_bytes, compress).unwrap();
+            assert_eq!(
+                VerifyingKey::from_arkworks_bytes(&key_bytes, compressed).unwrap(),
+                key
+            );
+
+            let mut proof_bytes = vec![];
+            a.serialize_with_mode(&mut proof_bytes, compress).unwrap();
+            b.serialize_with_mode(&mut proof_bytes, compress).unwrap();
+            c.serialize_with_mode(&mut proof_bytes, compress).unwrap();
+            assert_eq!(
+                Proof::from_arkworks_bytes(&proof_bytes, compressed).unwrap(),
+                proof
+            );
+
+            proof_bytes.push(0);
+            assert!(Proof::from_arkworks_bytes(&proof_bytes, compressed).is_err());
+            assert!(Proof::from_arkworks_bytes(&proof_bytes[..10], compressed).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "snarkjs")]
+    fn test_from_snarkjs_json() {
+        let (key, proof, inputs) = test_key_and_proof();
+        let (alpha, [beta, gamma, delta], ic, [a, c], b, public) = test_proof();
+
+        let g1_json = |point: &G1Affine| {
+            let (x, y) = point.xy().unwrap();
+            format!(r#"["{x}", "{y}", "1"]"#)
+        };
+        let g2_json = |point: &G2Affine| {
+            let (x, y) = point.xy().unwrap();
+            format!(
+                r#"[["{}", "{}"], ["{}", "{}"], ["1", "0"]]"#,
+                x.c0, x.c1, y.c0, y.c1
+            )
+        };
+
+        let key_json = format!(
+            r#"{{
+                "protocol": "groth16",
+                "curve": "bn128",
+                "nPublic": 2,
+                "vk_alpha_1": {},
+                "vk_beta_2": {},
+                "vk_gamma_2": {},
+                "vk_delta_2": {},
+                "IC": [{}]
+            }}"#,
+            g1_json(&alpha),
+            g2_json(&beta),
+            g2_json(&gamma),
+            g2_json(&delta),
+            ic.iter().map(g1_json).collect::<Vec<_>>().join(", "),
+        );
+        assert_eq!(VerifyingKey::from_snarkjs_json(&key_json).unwrap(), key);
+
+        let proof_json = format!(
+            r#"{{"pi_a": {}, "pi_b": {}, "pi_c": {}, "protocol": "groth16", "curve": "bn128"}}"#,
+            g1_json(&a),
+            g2_json(&b),
+            g1_json(&c),
+        );
+        assert_eq!(Proof::from_snarkjs_json(&proof_json).unwrap(), proof);
+
+        let public_json = format!(r#"["{}", "{}"]"#, public[0], public[1]);
+        assert_eq!(
+            public_inputs_from_snarkjs_json(&public_json).unwrap(),
+            inputs
+        );
+
+        let plonk_json = proof_json.replace("groth16", "plonk");
+        assert!(Proof::from_snarkjs_json(&plonk_json).is_err());
+        let invalid_json = proof_json.replace(r#""1"]"#, r#""2"]"#);
+        assert_eq!(
+            Proof::from_snarkjs_json(&invalid_json),
+            Err(Groth16Error::InvalidPoint)
+        );
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        assert_eq!(
+            public_inputs_from_snarkjs_json(&format!(r#"["{modulus}"]"#)),
+            Err(Groth16Error::PublicInputGreaterThanFieldSize)
+        );
+    }
+}
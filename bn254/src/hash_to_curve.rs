@@ -0,0 +1,94 @@
+//! Hashing to G1 by try-and-increment.
+//!
+//! The candidate x coordinates are `SHA-256(DST || len(DST) || msg || ctr)`
+//! reduced modulo the base field, for the counters `ctr` from 0 to 255, where
+//! `DST` is a domain separation tag of at most 255 bytes. The first one on
+//! the curve `y^2 = x^3 + 3` wins, with the smaller of its two y coordinates.
+//! About half of the candidates are on the curve, and since G1 has cofactor
+//! 1 every such point is in G1.
+//!
+//! This is not the hash-to-curve of RFC 9380: its output doesn't match any
+//! `BN254G1_XMD:SHA-256_SVDW_RO_` implementation, or any other library, so
+//! use it only where both sides run this function. Reducing a 256-bit hash
+//! modulo the base field is also slightly biased towards small x
+//! coordinates.
+//!
+//! The number of attempts depends on the message, so this isn't constant
+//! time and must not be used on secret messages. It uses the `sol_sha256`
+//! syscall on-chain.
+
+use {
+    crate::{field::Fq, AltBn128Error, PodG1},
+    solana_sha256_hasher::hashv,
+};
+
+/// The maximum length of a domain separation tag
+pub const MAX_DST_LEN: usize = 255;
+
+/// The coefficient `b` of the curve equation `y^2 = x^3 + b`
+const CURVE_B: u64 = 3;
+
+/// Hash `message` to a point of G1 by try-and-increment, under the domain
+/// separation tag `dst`. See the [module documentation](self) for why this
+/// isn't interoperable with other hash-to-curve implementations.
+pub fn hash_to_g1_try_and_increment(dst: &[u8], message: &[u8]) -> Result<PodG1, AltBn128Error> {
+    let dst_len = u8::try_from(dst.len()).map_err(|_| AltBn128Error::InvalidInputData)?;
+    for counter in 0..=u8::MAX {
+        let hash = hashv(&[dst, &[dst_len], message, &[counter]]).to_bytes();
+        let x = Fq::from_be_bytes_mod_order(&hash);
+        let Some(y) = (x.square() * x + Fq::from(CURVE_B)).sqrt() else {
+            continue;
+        };
+        let negated_y = -y;
+        let y = if negated_y.to_be_bytes() < y.to_be_bytes() {
+            negated_y
+        } else {
+            y
+        };
+
+        let mut point = [0u8; 64];
+        point[..32].copy_from_slice(&x.to_le_bytes());
+        point[32..].copy_from_slice(&y.to_le_bytes());
+        return Ok(PodG1(point));
+    }
+    // The probability of getting here is 2^-256
+    Err(AltBn128Error::UnexpectedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::prelude::alt_bn128_addition};
+
+    #[test]
+    fn test_hash_to_g1_try_and_increment() {
+        let dst = b"solana-bn254-test";
+        for (message, expected) in [
+            (
+                &b""[..],
+                "0254ac1eac79d3c0157d1c9dbe63dfb0cfa9fbc5b3a3bdb29d851215f5662b440a350b3705c3492164662ea424c09319aaea7b07eba7645532c9368df309f71b",
+            ),
+            (
+                &b"hello"[..],
+                "2ab359410811497b5f5e72e822d339ac479c9198df9f31e641cec26b4903332c120ea56d3b862fb004d4c0739b405f34463e86b5614c41503ca0d7bf94176da6",
+            ),
+        ] {
+            let point = hash_to_g1_try_and_increment(dst, message).unwrap();
+            assert_eq!(
+                point.to_be_bytes().to_vec(),
+                array_bytes::hex2bytes_unchecked(expected)
+            );
+            // The syscall accepts the point, so it is on the curve
+            assert!(alt_bn128_addition(&point.to_be_bytes()).is_ok());
+        }
+
+        assert_ne!(
+            hash_to_g1_try_and_increment(b"other-dst", b"hello").unwrap(),
+            hash_to_g1_try_and_increment(dst, b"hello").unwrap()
+        );
+        assert!(hash_to_g1_try_and_increment(&[0; MAX_DST_LEN], b"hello").is_ok());
+        assert_eq!(
+            hash_to_g1_try_and_increment(&[0; MAX_DST_LEN + 1], b"hello"),
+            Err(AltBn128Error::InvalidInputData)
+        );
+    }
+}
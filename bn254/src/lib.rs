@@ -1,10 +1,17 @@
 pub mod compression;
+#[allow(clippy::arithmetic_side_effects)]
+pub mod field;
+pub mod groth16;
+#[allow(clippy::arithmetic_side_effects)]
+pub mod hash_to_curve;
+pub mod msm;
 pub mod prelude {
     pub use crate::{consts::*, target_arch::*, AltBn128Error};
 }
 
 use {
     bytemuck::{Pod, Zeroable},
+    compression::prelude::convert_endianness,
     consts::*,
     thiserror::Error,
 };
@@ -127,6 +134,56 @@ const G2_POINT_SIZE: usize = FIELD_SIZE * 4;
 #[repr(transparent)]
 pub struct PodG2(pub [u8; G2_POINT_SIZE]);
 
+impl PodG1 {
+    /// Takes in an EIP-197 (big-endian) byte encoding of a group element in G1 and constructs a
+    /// `PodG1` struct that encodes the same bytes in little-endian.
+    pub fn from_be_bytes(be_bytes: &[u8]) -> Result<Self, AltBn128Error> {
+        let be_bytes: &[u8; G1_POINT_SIZE] = be_bytes
+            .try_into()
+            .map_err(|_| AltBn128Error::SliceOutOfBounds)?;
+        Ok(Self(convert_endianness::<FIELD_SIZE, G1_POINT_SIZE>(
+            be_bytes,
+        )))
+    }
+
+    /// The EIP-197 (big-endian) byte encoding of the group element, as
+    /// expected by the syscalls.
+    pub fn to_be_bytes(&self) -> [u8; G1_POINT_SIZE] {
+        convert_endianness::<FIELD_SIZE, G1_POINT_SIZE>(&self.0)
+    }
+}
+
+impl PodG2 {
+    /// Takes in an EIP-197 (big-endian) byte encoding of a group element in G2
+    /// and constructs a `PodG2` struct that encodes the same bytes in
+    /// little-endian.
+    pub fn from_be_bytes(be_bytes: &[u8]) -> Result<Self, AltBn128Error> {
+        let be_bytes: &[u8; G2_POINT_SIZE] = be_bytes
+            .try_into()
+            .map_err(|_| AltBn128Error::SliceOutOfBounds)?;
+        Ok(Self(swap_g2_coefficients(convert_endianness::<
+            FIELD_SIZE,
+            G2_POINT_SIZE,
+        >(be_bytes))))
+    }
+
+    /// The EIP-197 (big-endian) byte encoding of the group element, as
+    /// expected by the syscalls.
+    pub fn to_be_bytes(&self) -> [u8; G2_POINT_SIZE] {
+        convert_endianness::<FIELD_SIZE, G2_POINT_SIZE>(&swap_g2_coefficients(self.0))
+    }
+}
+
+/// Swaps the coefficients `c0` and `c1` of both coordinates of a G2 element,
+/// which are in the opposite order in the EIP-197 and arkworks encodings.
+fn swap_g2_coefficients(mut bytes: [u8; G2_POINT_SIZE]) -> [u8; G2_POINT_SIZE] {
+    for coordinate in bytes.chunks_exact_mut(FIELD_SIZE.saturating_mul(2)) {
+        let (c0, c1) = coordinate.split_at_mut(FIELD_SIZE);
+        c0.swap_with_slice(c1);
+    }
+    bytes
+}
+
 #[cfg(not(target_os = "solana"))]
 mod target_arch {
     use {
@@ -140,60 +197,6 @@ mod target_arch {
     type G1 = ark_bn254::g1::G1Affine;
     type G2 = ark_bn254::g2::G2Affine;
 
-    impl PodG1 {
-        /// Takes in an EIP-197 (big-endian) byte encoding of a group element in G1 and constructs a
-        /// `PodG1` struct that encodes the same bytes in little-endian.
-        fn from_be_bytes(be_bytes: &[u8]) -> Result<Self, AltBn128Error> {
-            if be_bytes.len() != G1_POINT_SIZE {
-                return Err(AltBn128Error::SliceOutOfBounds);
-            }
-            let mut pod_bytes = [0u8; G1_POINT_SIZE];
-            reverse_copy(&be_bytes[..FIELD_SIZE], &mut pod_bytes[..FIELD_SIZE])?;
-            reverse_copy(&be_bytes[FIELD_SIZE..], &mut pod_bytes[FIELD_SIZE..])?;
-            Ok(Self(pod_bytes))
-        }
-    }
-
-    impl PodG2 {
-        /// Takes in an EIP-197 (big-endian) byte encoding of a group element in G2
-        /// and constructs a `PodG2` struct that encodes the same bytes in
-        /// little-endian.
-        fn from_be_bytes(be_bytes: &[u8]) -> Result<Self, AltBn128Error> {
-            if be_bytes.len() != G2_POINT_SIZE {
-                return Err(AltBn128Error::SliceOutOfBounds);
-            }
-            // note the cross order
-            const SOURCE_X1_INDEX: usize = 0;
-            const SOURCE_X0_INDEX: usize = SOURCE_X1_INDEX.saturating_add(FIELD_SIZE);
-            const SOURCE_Y1_INDEX: usize = SOURCE_X0_INDEX.saturating_add(FIELD_SIZE);
-            const SOURCE_Y0_INDEX: usize = SOURCE_Y1_INDEX.saturating_add(FIELD_SIZE);
-
-            const TARGET_X0_INDEX: usize = 0;
-            const TARGET_X1_INDEX: usize = TARGET_X0_INDEX.saturating_add(FIELD_SIZE);
-            const TARGET_Y0_INDEX: usize = TARGET_X1_INDEX.saturating_add(FIELD_SIZE);
-            const TARGET_Y1_INDEX: usize = TARGET_Y0_INDEX.saturating_add(FIELD_SIZE);
-
-            let mut pod_bytes = [0u8; G2_POINT_SIZE];
-            reverse_copy(
-                &be_bytes[SOURCE_X1_INDEX..SOURCE_X1_INDEX.saturating_add(FIELD_SIZE)],
-                &mut pod_bytes[TARGET_X1_INDEX..TARGET_X1_INDEX.saturating_add(FIELD_SIZE)],
-            )?;
-            reverse_copy(
-                &be_bytes[SOURCE_X0_INDEX..SOURCE_X0_INDEX.saturating_add(FIELD_SIZE)],
-                &mut pod_bytes[TARGET_X0_INDEX..TARGET_X0_INDEX.saturating_add(FIELD_SIZE)],
-            )?;
-            reverse_copy(
-                &be_bytes[SOURCE_Y1_INDEX..SOURCE_Y1_INDEX.saturating_add(FIELD_SIZE)],
-                &mut pod_bytes[TARGET_Y1_INDEX..TARGET_Y1_INDEX.saturating_add(FIELD_SIZE)],
-            )?;
-            reverse_copy(
-                &be_bytes[SOURCE_Y0_INDEX..SOURCE_Y0_INDEX.saturating_add(FIELD_SIZE)],
-                &mut pod_bytes[TARGET_Y0_INDEX..TARGET_Y0_INDEX.saturating_add(FIELD_SIZE)],
-            )?;
-            Ok(Self(pod_bytes))
-        }
-    }
-
     impl TryFrom<PodG1> for G1 {
         type Error = AltBn128Error;
 
@@ -443,12 +446,42 @@ mod target_arch {
 #[cfg(test)]
 mod tests {
     use {
-        crate::{prelude::*, PodG1},
+        crate::{prelude::*, PodG1, PodG2},
         ark_bn254::g1::G1Affine,
         ark_ec::AffineRepr,
         ark_serialize::{CanonicalSerialize, Compress},
     };
 
+    #[test]
+    fn pod_be_bytes_test() {
+        use ark_bn254::g2::G2Affine;
+
+        let mut g1_bytes = [0u8; 64];
+        G1Affine::generator()
+            .serialize_with_mode(&mut g1_bytes[..], Compress::No)
+            .unwrap();
+        let g1 = PodG1(g1_bytes);
+        let mut g1_be_bytes = [0u8; 64];
+        g1_be_bytes[31] = 1;
+        g1_be_bytes[63] = 2;
+        assert_eq!(g1.to_be_bytes(), g1_be_bytes);
+        assert_eq!(PodG1::from_be_bytes(&g1_be_bytes), Ok(g1));
+
+        let mut g2_bytes = [0u8; 128];
+        G2Affine::generator()
+            .serialize_with_mode(&mut g2_bytes[..], Compress::No)
+            .unwrap();
+        let g2 = PodG2(g2_bytes);
+        let g2_be_bytes = array_bytes::hex2bytes_unchecked("198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c21800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa");
+        assert_eq!(g2.to_be_bytes().to_vec(), g2_be_bytes);
+        assert_eq!(PodG2::from_be_bytes(&g2_be_bytes), Ok(g2));
+
+        assert_eq!(
+            PodG1::from_be_bytes(&g1_be_bytes[1..]),
+            Err(AltBn128Error::SliceOutOfBounds)
+        );
+    }
+
     #[test]
     fn zero_serialization_test() {
         let zero = G1Affine::zero();
@@ -0,0 +1,153 @@
+//! Multi-scalar multiplication in G1.
+//!
+//! Off-chain, [`alt_bn128_g1_msm`] runs the Pippenger algorithm of arkworks.
+//! On-chain there is no syscall for it, so it multiplies and adds the points
+//! one by one with the `alt_bn128` syscalls, which costs one multiplication
+//! and one addition per point.
+
+use crate::{field::Fr, AltBn128Error, PodG1};
+
+/// `sum(scalars[i] * points[i])`. Fails if the lengths of `points` and
+/// `scalars` differ or if a point isn't in G1. The sum of no points is the
+/// point at infinity.
+pub fn alt_bn128_g1_msm(points: &[PodG1], scalars: &[Fr]) -> Result<PodG1, AltBn128Error> {
+    if points.len() != scalars.len() {
+        return Err(AltBn128Error::InvalidInputData);
+    }
+    target_arch::alt_bn128_g1_msm(points, scalars)
+}
+
+#[cfg(not(target_os = "solana"))]
+mod target_arch {
+    use {
+        super::*,
+        ark_bn254::{G1Affine, G1Projective},
+        ark_ec::{CurveGroup, VariableBaseMSM},
+        ark_ff::{BigInt, Fp},
+        ark_serialize::{CanonicalSerialize, Compress},
+    };
+
+    pub(super) fn alt_bn128_g1_msm(
+        points: &[PodG1],
+        scalars: &[Fr],
+    ) -> Result<PodG1, AltBn128Error> {
+        let bases = points
+            .iter()
+            .map(|point| G1Affine::try_from(*point))
+            .collect::<Result<Vec<_>, _>>()?;
+        let scalars: Vec<ark_bn254::Fr> = scalars
+            .iter()
+            .map(|scalar| Fp::new_unchecked(BigInt(scalar.to_montgomery_limbs())))
+            .collect();
+        let result = G1Projective::msm_unchecked(&bases, &scalars).into_affine();
+
+        let mut result_bytes = [0u8; 64];
+        result
+            .x
+            .serialize_with_mode(&mut result_bytes[..32], Compress::No)
+            .map_err(|_| AltBn128Error::InvalidInputData)?;
+        result
+            .y
+            .serialize_with_mode(&mut result_bytes[32..], Compress::No)
+            .map_err(|_| AltBn128Error::InvalidInputData)?;
+        Ok(PodG1(result_bytes))
+    }
+}
+
+#[cfg(target_os = "solana")]
+mod target_arch {
+    use {
+        super::*,
+        crate::prelude::{alt_bn128_addition, alt_bn128_multiplication},
+    };
+
+    pub(super) fn alt_bn128_g1_msm(
+        points: &[PodG1],
+        scalars: &[Fr],
+    ) -> Result<PodG1, AltBn128Error> {
+        let mut sum = [0u8; 64];
+        for (point, scalar) in points.iter().zip(scalars) {
+            if scalar.is_zero() {
+                continue;
+            }
+            let product = alt_bn128_multiplication(
+                &[&point.to_be_bytes()[..], &scalar.to_be_bytes()].concat(),
+            )?;
+            let addition = alt_bn128_addition(&[&sum[..], &product[..]].concat())?;
+            sum.copy_from_slice(&addition);
+        }
+        PodG1::from_be_bytes(&sum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::prelude::{alt_bn128_addition, alt_bn128_multiplication},
+    };
+
+    fn g(be_hex: &str) -> PodG1 {
+        PodG1::from_be_bytes(&array_bytes::hex2bytes_unchecked(be_hex)).unwrap()
+    }
+
+    const G: &str = "00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002";
+    const G_TIMES_2: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+    const G_TIMES_3: &str = "0769bf9ac56bea3ff40232bcb1b6bd159315d84715b8e679f2d355961915abf02ab799bee0489429554fdb7c8d086475319e63b40b9c5b57cdf1ff3dd9fe2261";
+    const G_TIMES_19: &str = "15514de6a136158ef7b2bc22bed59866743bc401edd63ae857d44f4c71edc28d095e28f5ba5d73440c0e504b624afabfedb9387320817b62e9168b6868d8952e";
+
+    #[test]
+    fn test_alt_bn128_g1_msm() {
+        assert_eq!(
+            alt_bn128_g1_msm(&[g(G), g(G_TIMES_2)], &[Fr::ONE, Fr::ONE]),
+            Ok(g(G_TIMES_3))
+        );
+        // 5 * G + 7 * 2G
+        assert_eq!(
+            alt_bn128_g1_msm(&[g(G), g(G_TIMES_2)], &[Fr::from(5), Fr::from(7)]),
+            Ok(g(G_TIMES_19))
+        );
+        // 2G - 2 * G
+        assert_eq!(
+            alt_bn128_g1_msm(&[g(G_TIMES_2), g(G)], &[Fr::ONE, -Fr::from(2)]),
+            Ok(PodG1([0; 64]))
+        );
+        assert_eq!(alt_bn128_g1_msm(&[], &[]), Ok(PodG1([0; 64])));
+
+        assert_eq!(
+            alt_bn128_g1_msm(&[g(G)], &[]),
+            Err(AltBn128Error::InvalidInputData)
+        );
+        let mut not_on_curve = g(G);
+        not_on_curve.0[32] = 3;
+        assert!(alt_bn128_g1_msm(&[not_on_curve], &[Fr::ONE]).is_err());
+    }
+
+    #[test]
+    fn test_alt_bn128_g1_msm_matches_syscalls() {
+        let points: Vec<PodG1> = [G, G_TIMES_2, G_TIMES_3, G_TIMES_19]
+            .into_iter()
+            .map(g)
+            .collect();
+        let scalars = [
+            Fr::from(u64::MAX),
+            -Fr::from(12345),
+            Fr::from(3).inverse().unwrap(),
+            Fr::ZERO,
+        ];
+
+        let mut sum = [0u8; 64];
+        for (point, scalar) in points.iter().zip(&scalars) {
+            let product = alt_bn128_multiplication(
+                &[&point.to_be_bytes()[..], &scalar.to_be_bytes()].concat(),
+            )
+            .unwrap();
+            let addition = alt_bn128_addition(&[&sum[..], &product[..]].concat()).unwrap();
+            sum.copy_from_slice(&addition);
+        }
+        assert_eq!(
+            alt_bn128_g1_msm(&points, &scalars).unwrap().to_be_bytes(),
+            sum
+        );
+    }
+}
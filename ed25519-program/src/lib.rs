@@ -2,10 +2,15 @@
 //!
 //! [np]: https://docs.solanalabs.com/runtime/programs#ed25519-program
 
+pub use solana_instructions_sysvar::precompile::DataRef;
 use {
     bytemuck::bytes_of,
     bytemuck_derive::{Pod, Zeroable},
+    solana_account_info::AccountInfo,
     solana_instruction::Instruction,
+    solana_instructions_sysvar::precompile,
+    solana_precompile_error::PrecompileError,
+    solana_program_error::ProgramError,
 };
 
 pub const PUBKEY_SERIALIZED_SIZE: usize = 32;
@@ -106,3 +111,645 @@ pub fn new_ed25519_instruction_with_signature(
         data: instruction_data,
     }
 }
+
+/// Builder of an ed25519 instruction verifying any number of signatures.
+///
+/// Each signature, public key and message is either appended to the
+/// instruction or found in another instruction of the same transaction, and
+/// [`Ed25519InstructionBuilder::build`] fills in the [`Ed25519SignatureOffsets`]
+/// of every signature. Identical inline data, like a message signed by
+/// several keys, is only appended once.
+///
+/// The builder can't check references to other instructions against their
+/// data, [`verify`] the built instruction with the data of every instruction
+/// of the transaction for that.
+#[derive(Debug, Default, Clone)]
+pub struct Ed25519InstructionBuilder<'a> {
+    // signature, public key and message of each signature
+    signatures: Vec<[DataRef<'a>; 3]>,
+}
+
+impl<'a> Ed25519InstructionBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a signature to verify, with its data appended to the instruction
+    pub fn add_signature(
+        &mut self,
+        message: &'a [u8],
+        signature: &'a [u8; SIGNATURE_SERIALIZED_SIZE],
+        pubkey: &'a [u8; PUBKEY_SERIALIZED_SIZE],
+    ) -> &mut Self {
+        self.add_signature_ref(
+            DataRef::Inline(message),
+            DataRef::Inline(signature),
+            DataRef::Inline(pubkey),
+        )
+    }
+
+    /// Add a signature to verify, with its data wherever `message`,
+    /// `signature` and `pubkey` refer to
+    pub fn add_signature_ref(
+        &mut self,
+        message: DataRef<'a>,
+        signature: DataRef<'a>,
+        pubkey: DataRef<'a>,
+    ) -> &mut Self {
+        self.signatures.push([signature, pubkey, message]);
+        self
+    }
+
+    /// Build the instruction, checking that the offsets and sizes of all
+    /// data fit the instruction format
+    pub fn build(&self) -> Result<Instruction, PrecompileError> {
+        Ok(Instruction {
+            program_id: badchain_sdk_ids::ed25519_program::id(),
+            accounts: vec![],
+            data: precompile::build_instruction_data(
+                &self.signatures,
+                SIGNATURE_SERIALIZED_SIZE,
+                PUBKEY_SERIALIZED_SIZE,
+            )?,
+        })
+    }
+}
+
+/// A signature verified by an ed25519 instruction, with its public key and
+/// message
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedSignature {
+    pub signature: [u8; SIGNATURE_SERIALIZED_SIZE],
+    pub pubkey: [u8; PUBKEY_SERIALIZED_SIZE],
+    pub message: Vec<u8>,
+}
+
+/// Load the signatures verified by the ed25519 instruction at `index` in the
+/// current transaction, from the instructions sysvar.
+///
+/// A transaction only executes if all of its ed25519 instructions succeed,
+/// so a program can trust the returned signatures. Data referenced in other
+/// instructions is read from those instructions.
+///
+/// # Errors
+///
+/// Returns [`ProgramError::IncorrectProgramId`] if the instruction at
+/// `index` isn't an ed25519 instruction, and the errors of
+/// [`load_instruction_at_checked`] if the sysvar can't be read.
+///
+/// [`load_instruction_at_checked`]: solana_instructions_sysvar::load_instruction_at_checked
+pub fn load_verified_signatures(
+    index: usize,
+    instructions_sysvar: &AccountInfo,
+) -> Result<Vec<VerifiedSignature>, ProgramError> {
+    let verified = precompile::load_verified_signatures(
+        index,
+        instructions_sysvar,
+        &badchain_sdk_ids::ed25519_program::id(),
+        SIGNATURE_SERIALIZED_SIZE,
+        PUBKEY_SERIALIZED_SIZE,
+    )?;
+    Ok(verified
+        .into_iter()
+        .map(|verified| VerifiedSignature {
+            signature: verified.signature.try_into().unwrap(),
+            pubkey: verified.pubkey.try_into().unwrap(),
+            message: verified.message,
+        })
+        .collect())
+}
+
+/// Verify the signatures of an ed25519 instruction, as the runtime does.
+///
+/// `data` is the instruction data of the ed25519 instruction and
+/// `instruction_datas` the instruction data of every instruction of the
+/// transaction, that the offsets can refer to by index. An index of
+/// `u16::MAX` refers to `data` itself.
+#[cfg(not(target_os = "solana"))]
+pub fn verify(data: &[u8], instruction_datas: &[&[u8]]) -> Result<(), PrecompileError> {
+    if data.len() < SIGNATURE_OFFSETS_START {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let num_signatures = data[0] as usize;
+    if num_signatures == 0 && data.len() > SIGNATURE_OFFSETS_START {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let expected_data_size = num_signatures
+        .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .saturating_add(SIGNATURE_OFFSETS_START);
+    // We do not check or use the byte at data[1]
+    if data.len() < expected_data_size {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    for i in 0..num_signatures {
+        let start = i
+            .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .saturating_add(SIGNATURE_OFFSETS_START);
+        let end = start.saturating_add(SIGNATURE_OFFSETS_SERIALIZED_SIZE);
+        let offsets: Ed25519SignatureOffsets = bytemuck::pod_read_unaligned(&data[start..end]);
+
+        // Parse out signature
+        let signature = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.signature_instruction_index,
+            offsets.signature_offset,
+            SIGNATURE_SERIALIZED_SIZE,
+        )?;
+        let signature = ed25519_dalek::Signature::from_bytes(signature)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+
+        // Parse out pubkey
+        let pubkey = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.public_key_instruction_index,
+            offsets.public_key_offset,
+            PUBKEY_SERIALIZED_SIZE,
+        )?;
+        let publickey = ed25519_dalek::PublicKey::from_bytes(pubkey)
+            .map_err(|_| PrecompileError::InvalidPublicKey)?;
+
+        // Parse out message
+        let message = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.message_instruction_index,
+            offsets.message_data_offset,
+            offsets.message_data_size as usize,
+        )?;
+
+        publickey
+            .verify_strict(message, &signature)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "solana"))]
+fn get_data_slice<'a>(
+    data: &'a [u8],
+    instruction_datas: &'a [&[u8]],
+    instruction_index: u16,
+    offset_start: u16,
+    size: usize,
+) -> Result<&'a [u8], PrecompileError> {
+    let instruction = if instruction_index == u16::MAX {
+        data
+    } else {
+        let signature_index = instruction_index as usize;
+        if signature_index >= instruction_datas.len() {
+            return Err(PrecompileError::InvalidDataOffsets);
+        }
+        instruction_datas[signature_index]
+    };
+
+    let start = offset_start as usize;
+    let end = start.saturating_add(size);
+    if end > instruction.len() {
+        return Err(PrecompileError::InvalidDataOffsets);
+    }
+
+    Ok(&instruction[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ed25519_dalek::Signer, solana_instruction::BorrowedInstruction};
+
+    // Test 2 of RFC 8032
+    const SECRET_KEY: [u8; 32] = [
+        0x4c, 0xcd, 0x08, 0x9b, 0x28, 0xff, 0x96, 0xda, 0x9d, 0xb6, 0xc3, 0x46, 0xec, 0x11, 0x4e,
+        0x0f, 0x5b, 0x8a, 0x31, 0x9f, 0x35, 0xab, 0xa6, 0x24, 0xda, 0x8c, 0xf6, 0xed, 0x4f, 0xb8,
+        0xa6, 0xfb,
+    ];
+    const PUBLIC_KEY: [u8; 32] = [
+        0x3d, 0x40, 0x17, 0xc3, 0xe8, 0x43, 0x89, 0x5a, 0x92, 0xb7, 0x0a, 0xa7, 0x4d, 0x1b, 0x7e,
+        0xbc, 0x9c, 0x98, 0x2c, 0xcf, 0x2e, 0xc4, 0x96, 0x8c, 0xc0, 0xcd, 0x55, 0xf1, 0x2a, 0xf4,
+        0x66, 0x0c,
+    ];
+    const MESSAGE: [u8; 1] = [0x72];
+    const SIGNATURE: [u8; 64] = [
+        0x92, 0xa0, 0x09, 0xa9, 0xf0, 0xd4, 0xca, 0xb8, 0x72, 0x0e, 0x82, 0x0b, 0x5f, 0x64, 0x25,
+        0x40, 0xa2, 0xb2, 0x7b, 0x54, 0x16, 0x50, 0x3f, 0x8f, 0xb3, 0x76, 0x22, 0x23, 0xeb, 0xdb,
+        0x69, 0xda, 0x08, 0x5a, 0xc1, 0xe4, 0x3e, 0x15, 0x99, 0x6e, 0x45, 0x8f, 0x36, 0x13, 0xd0,
+        0xf1, 0x1d, 0x8c, 0x38, 0x7b, 0x2e, 0xae, 0xb4, 0x30, 0x2a, 0xee, 0xb0, 0x0d, 0x29, 0x16,
+        0x12, 0xbb, 0x0c, 0x00,
+    ];
+
+    fn test_case(
+        num_signatures: u16,
+        offsets: &Ed25519SignatureOffsets,
+    ) -> Result<(), PrecompileError> {
+        assert_eq!(
+            bytemuck::bytes_of(offsets).len(),
+            SIGNATURE_OFFSETS_SERIALIZED_SIZE
+        );
+
+        let mut instruction_data = vec![0u8; DATA_START];
+        instruction_data[0..SIGNATURE_OFFSETS_START].copy_from_slice(bytes_of(&num_signatures));
+        instruction_data[SIGNATURE_OFFSETS_START..DATA_START].copy_from_slice(bytes_of(offsets));
+
+        verify(&instruction_data, &[&[0u8; 100]])
+    }
+
+    #[test]
+    fn test_verify_vector() {
+        let keypair = ed25519_dalek::Keypair {
+            secret: ed25519_dalek::SecretKey::from_bytes(&SECRET_KEY).unwrap(),
+            public: ed25519_dalek::PublicKey::from_bytes(&PUBLIC_KEY).unwrap(),
+        };
+        assert_eq!(keypair.sign(&MESSAGE).to_bytes(), SIGNATURE);
+
+        let instruction = new_ed25519_instruction_with_signature(&MESSAGE, &SIGNATURE, &PUBLIC_KEY);
+        assert_eq!(verify(&instruction.data, &[&instruction.data]), Ok(()));
+        // Offsets of `u16::MAX` refer to the instruction itself, whatever the
+        // other instructions are
+        assert_eq!(verify(&instruction.data, &[]), Ok(()));
+
+        let mut invalid = instruction.data.clone();
+        *invalid.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify(&invalid, &[]),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        // The same signature, with its data in other instructions
+        let pubkey_and_message = [&PUBLIC_KEY[..], &MESSAGE].concat();
+        let offsets = Ed25519SignatureOffsets {
+            signature_offset: 0,
+            signature_instruction_index: 2,
+            public_key_offset: 0,
+            public_key_instruction_index: 1,
+            message_data_offset: PUBKEY_SERIALIZED_SIZE as u16,
+            message_data_size: MESSAGE.len() as u16,
+            message_instruction_index: 1,
+        };
+        let instruction = offsets_to_ed25519_instruction(&[offsets]);
+        assert_eq!(
+            verify(
+                &instruction.data,
+                &[&instruction.data, &pubkey_and_message, &SIGNATURE]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&instruction.data, &[&instruction.data, &pubkey_and_message]),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+    }
+
+    #[test]
+    fn test_invalid_offsets() {
+        let mut instruction_data = vec![0u8; DATA_START];
+        let offsets = Ed25519SignatureOffsets::default();
+        instruction_data[0..SIGNATURE_OFFSETS_START].copy_from_slice(bytes_of(&1u16));
+        instruction_data[SIGNATURE_OFFSETS_START..DATA_START].copy_from_slice(bytes_of(&offsets));
+        instruction_data.truncate(instruction_data.len() - 1);
+        assert_eq!(
+            verify(&instruction_data, &[&[0u8; 100]]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            signature_instruction_index: 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            message_instruction_index: 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            public_key_instruction_index: 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        // No signatures, with trailing data
+        assert_eq!(
+            verify(&[0u8; DATA_START], &[]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+        assert_eq!(verify(&[0, 0], &[]), Ok(()));
+        assert_eq!(
+            verify(&[1], &[]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+    }
+
+    #[test]
+    fn test_message_data_offsets() {
+        let offsets = Ed25519SignatureOffsets {
+            message_data_offset: 99,
+            message_data_size: 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            message_data_offset: 100,
+            message_data_size: 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            message_data_offset: 100,
+            message_data_size: 1000,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            message_data_offset: u16::MAX,
+            message_data_size: u16::MAX,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+    }
+
+    #[test]
+    fn test_pubkey_and_signature_offsets() {
+        let offsets = Ed25519SignatureOffsets {
+            public_key_offset: u16::MAX,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            public_key_offset: 100 - PUBKEY_SERIALIZED_SIZE as u16 + 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            signature_offset: u16::MAX,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = Ed25519SignatureOffsets {
+            signature_offset: 100 - SIGNATURE_SERIALIZED_SIZE as u16 + 1,
+            ..Ed25519SignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+    }
+
+    fn keypair(seed: u8) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    #[test]
+    fn test_builder_inline() {
+        let keypairs: Vec<_> = (0..3).map(keypair).collect();
+        let pubkeys: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.public.to_bytes())
+            .collect();
+        let proposal = b"proposal";
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.sign(proposal).to_bytes())
+            .collect();
+        let other_message = b"other message";
+        let other_signature = keypairs[0].sign(other_message).to_bytes();
+
+        let mut builder = Ed25519InstructionBuilder::new();
+        for (signature, pubkey) in signatures.iter().zip(&pubkeys) {
+            builder.add_signature(proposal, signature, pubkey);
+        }
+        builder.add_signature(other_message, &other_signature, &pubkeys[0]);
+        let instruction = builder.build().unwrap();
+        assert_eq!(verify(&instruction.data, &[]), Ok(()));
+        // The message and first public key are only appended once
+        assert_eq!(
+            instruction.data.len(),
+            SIGNATURE_OFFSETS_START
+                + 4 * SIGNATURE_OFFSETS_SERIALIZED_SIZE
+                + 4 * SIGNATURE_SERIALIZED_SIZE
+                + 3 * PUBKEY_SERIALIZED_SIZE
+                + proposal.len()
+                + other_message.len()
+        );
+
+        // A single signature builds the same as the existing function, up to
+        // the order of the data
+        let mut builder = Ed25519InstructionBuilder::new();
+        builder.add_signature(&MESSAGE, &SIGNATURE, &PUBLIC_KEY);
+        let instruction = builder.build().unwrap();
+        assert_eq!(verify(&instruction.data, &[]), Ok(()));
+        assert_eq!(
+            instruction.data.len(),
+            new_ed25519_instruction_with_signature(&MESSAGE, &SIGNATURE, &PUBLIC_KEY)
+                .data
+                .len()
+        );
+
+        let mut builder = Ed25519InstructionBuilder::new();
+        for _ in 0..=u8::MAX as usize {
+            builder.add_signature(&MESSAGE, &SIGNATURE, &PUBLIC_KEY);
+        }
+        assert_eq!(
+            builder.build(),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+    }
+
+    #[test]
+    fn test_builder_references() {
+        let keypair = keypair(1);
+        let message = b"transfer 100 to alice";
+        let signature = keypair.sign(message).to_bytes();
+        let pubkey = keypair.public.to_bytes();
+
+        // Message in instruction 0, signature and public key in the ed25519
+        // instruction at index 1
+        let other_instruction_data = [&[0xff; 10][..], message].concat();
+        let mut builder = Ed25519InstructionBuilder::new();
+        builder.add_signature_ref(
+            DataRef::Instruction {
+                instruction_index: 0,
+                offset: 10,
+                size: message.len() as u16,
+            },
+            DataRef::Inline(&signature),
+            DataRef::Inline(&pubkey),
+        );
+        let instruction = builder.build().unwrap();
+        assert_eq!(
+            verify(
+                &instruction.data,
+                &[&other_instruction_data, &instruction.data]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&instruction.data, &[&instruction.data]),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        for (data_ref, error) in [
+            (
+                DataRef::Inline(&signature[1..]),
+                PrecompileError::InvalidSignature,
+            ),
+            (
+                DataRef::Instruction {
+                    instruction_index: 0,
+                    offset: 0,
+                    size: 32,
+                },
+                PrecompileError::InvalidSignature,
+            ),
+            (
+                DataRef::Instruction {
+                    instruction_index: u16::MAX,
+                    offset: 0,
+                    size: 64,
+                },
+                PrecompileError::InvalidDataOffsets,
+            ),
+            (
+                DataRef::Instruction {
+                    instruction_index: 0,
+                    offset: u16::MAX - 10,
+                    size: 64,
+                },
+                PrecompileError::InvalidDataOffsets,
+            ),
+        ] {
+            let mut builder = Ed25519InstructionBuilder::new();
+            builder.add_signature_ref(DataRef::Inline(message), data_ref, DataRef::Inline(&pubkey));
+            assert_eq!(builder.build(), Err(error));
+        }
+        let mut builder = Ed25519InstructionBuilder::new();
+        builder.add_signature_ref(
+            DataRef::Inline(message),
+            DataRef::Inline(&signature),
+            DataRef::Inline(&pubkey[1..]),
+        );
+        assert_eq!(builder.build(), Err(PrecompileError::InvalidPublicKey));
+    }
+
+    #[test]
+    fn test_load_verified_signatures() {
+        let keypairs: Vec<_> = (0..2).map(keypair).collect();
+        let message = b"vote yes";
+        let signatures: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.sign(message).to_bytes())
+            .collect();
+        let pubkeys: Vec<_> = keypairs
+            .iter()
+            .map(|keypair| keypair.public.to_bytes())
+            .collect();
+
+        // The message is in the program instruction, at index 1
+        let program_id = badchain_sdk_ids::system_program::id();
+        let program_data = [&[7][..], message].concat();
+        let mut builder = Ed25519InstructionBuilder::new();
+        for (signature, pubkey) in signatures.iter().zip(&pubkeys) {
+            builder.add_signature_ref(
+                DataRef::Instruction {
+                    instruction_index: 1,
+                    offset: 1,
+                    size: message.len() as u16,
+                },
+                DataRef::Inline(signature),
+                DataRef::Inline(pubkey),
+            );
+        }
+        let instruction = builder.build().unwrap();
+        assert_eq!(
+            verify(&instruction.data, &[&instruction.data, &program_data]),
+            Ok(())
+        );
+
+        let key = solana_instructions_sysvar::id();
+        let owner = badchain_sdk_ids::sysvar::id();
+        let mut lamports = 0;
+        let mut data = solana_instructions_sysvar::construct_instructions_data(&[
+            BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: vec![],
+                data: &instruction.data,
+            },
+            BorrowedInstruction {
+                program_id: &program_id,
+                accounts: vec![],
+                data: &program_data,
+            },
+        ]);
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        let verified = load_verified_signatures(0, &account_info).unwrap();
+        assert_eq!(
+            verified,
+            signatures
+                .iter()
+                .zip(&pubkeys)
+                .map(|(signature, pubkey)| VerifiedSignature {
+                    signature: *signature,
+                    pubkey: *pubkey,
+                    message: message.to_vec(),
+                })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            load_verified_signatures(1, &account_info),
+            Err(ProgramError::IncorrectProgramId)
+        );
+        assert_eq!(
+            load_verified_signatures(2, &account_info),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+}
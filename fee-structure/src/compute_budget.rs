@@ -0,0 +1,540 @@
+//! Offline fee estimation from compute-budget instructions.
+//!
+//! The runtime derives a transaction's compute budget from the
+//! `ComputeBudgetInstruction`s in its message before charging fees. The
+//! functions in this module repeat that processing, including the errors
+//! returned for malformed or duplicate instructions, so that a wallet can
+//! quote the exact fee of a message without asking a validator.
+
+use {
+    crate::{FeeBudgetLimits, FeeDetails, FeeStructure},
+    badchain_sdk_ids::{ed25519_program, secp256k1_program, secp256r1_program},
+    solana_compute_budget_interface::{self as compute_budget, ComputeBudgetInstruction},
+    solana_instruction::error::InstructionError,
+    solana_message::{
+        compiled_instruction::CompiledInstruction, SanitizedMessage, TransactionSignatureDetails,
+        VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+    solana_transaction_error::{TransactionError, TransactionResult as Result},
+    std::num::NonZeroU32,
+};
+
+/// Compute units allotted to each instruction when the message doesn't set
+/// a compute unit limit
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Maximum compute units a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Smallest heap frame, which is also the default heap frame size
+pub const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+/// Largest heap frame a transaction may request
+pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+/// Maximum number of account data bytes a transaction may load
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: NonZeroU32 = match NonZeroU32::new(64 * 1024 * 1024)
+{
+    Some(bytes) => bytes,
+    None => panic!("zero loaded accounts data size limit"),
+};
+/// Compute unit cost of each 32KiB heap page. A page takes roughly 0.5us to
+/// process and the runtime budgets about 15 compute units per microsecond,
+/// which rounds to 8 compute units. Fees charge the same cost for each
+/// [`ACCOUNT_DATA_COST_PAGE_SIZE`] page of the loaded accounts data size limit.
+///
+/// [`ACCOUNT_DATA_COST_PAGE_SIZE`]: crate::ACCOUNT_DATA_COST_PAGE_SIZE
+pub const DEFAULT_HEAP_COST: u64 = 8;
+/// Number of micro-lamports in a lamport, the unit of the compute unit price
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// The compute budget requested by a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetLimits {
+    /// Heap frame size in bytes
+    pub updated_heap_bytes: u32,
+    /// Compute units the transaction may consume
+    pub compute_unit_limit: u32,
+    /// Price of each compute unit in micro-lamports
+    pub compute_unit_price: u64,
+    /// Maximum number of account data bytes the transaction may load
+    pub loaded_accounts_bytes: NonZeroU32,
+}
+
+impl Default for ComputeBudgetLimits {
+    fn default() -> Self {
+        Self {
+            updated_heap_bytes: MIN_HEAP_FRAME_BYTES,
+            compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+            compute_unit_price: 0,
+            loaded_accounts_bytes: MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+        }
+    }
+}
+
+impl ComputeBudgetLimits {
+    /// Process the compute-budget instructions among `instructions`, which
+    /// yields each instruction with its program id.
+    ///
+    /// Fails on the first compute-budget instruction that can't be decoded or
+    /// that repeats an earlier request, just like the runtime.
+    pub fn try_from_instructions<'a>(
+        instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+    ) -> Result<Self> {
+        let mut requested_compute_unit_limit = None;
+        let mut requested_compute_unit_price = None;
+        let mut requested_heap_size = None;
+        let mut requested_loaded_accounts_data_size_limit = None;
+        let mut num_non_compute_budget_instructions: u32 = 0;
+
+        for (index, (program_id, instruction)) in instructions.enumerate() {
+            if !compute_budget::check_id(program_id) {
+                num_non_compute_budget_instructions =
+                    num_non_compute_budget_instructions.saturating_add(1);
+                continue;
+            }
+
+            // The runtime reports instruction indexes as a u8
+            let index = index as u8;
+            let invalid_instruction_data_error =
+                TransactionError::InstructionError(index, InstructionError::InvalidInstructionData);
+            let duplicate_instruction_error = TransactionError::DuplicateInstruction(index);
+
+            match solana_borsh::v1::try_from_slice_unchecked(&instruction.data) {
+                Ok(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    if requested_heap_size.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    requested_heap_size = Some((index, bytes));
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    if requested_compute_unit_limit.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    requested_compute_unit_limit = Some(units);
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    if requested_compute_unit_price.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    requested_compute_unit_price = Some(micro_lamports);
+                }
+                Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                    if requested_loaded_accounts_data_size_limit.is_some() {
+                        return Err(duplicate_instruction_error);
+                    }
+                    requested_loaded_accounts_data_size_limit = Some(bytes);
+                }
+                Ok(ComputeBudgetInstruction::Unused) | Err(_) => {
+                    return Err(invalid_instruction_data_error);
+                }
+            }
+        }
+
+        // The heap size is checked after all instructions were decoded, so
+        // that duplicate instructions are reported first
+        let updated_heap_bytes = match requested_heap_size {
+            Some((_, bytes)) if sanitize_requested_heap_size(bytes) => bytes,
+            Some((index, _)) => {
+                return Err(TransactionError::InstructionError(
+                    index,
+                    InstructionError::InvalidInstructionData,
+                ))
+            }
+            None => MIN_HEAP_FRAME_BYTES,
+        };
+
+        let compute_unit_limit = requested_compute_unit_limit
+            .unwrap_or_else(|| {
+                num_non_compute_budget_instructions
+                    .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+            })
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+
+        let loaded_accounts_bytes = match requested_loaded_accounts_data_size_limit {
+            Some(bytes) => NonZeroU32::new(bytes)
+                .ok_or(TransactionError::InvalidLoadedAccountsDataSizeLimit)?,
+            None => MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+        }
+        .min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES);
+
+        Ok(Self {
+            updated_heap_bytes,
+            compute_unit_limit,
+            compute_unit_price: requested_compute_unit_price.unwrap_or_default(),
+            loaded_accounts_bytes,
+        })
+    }
+
+    /// Process the compute-budget instructions of a sanitized message
+    pub fn try_from_sanitized_message(message: &SanitizedMessage) -> Result<Self> {
+        Self::try_from_instructions(message.program_instructions_iter())
+    }
+
+    /// Process the compute-budget instructions of a versioned message.
+    ///
+    /// The message is sanitized first, because program ids can only be
+    /// looked up in a well-formed message.
+    pub fn try_from_versioned_message(message: &VersionedMessage) -> Result<Self> {
+        message.sanitize()?;
+        Self::try_from_instructions(versioned_program_instructions_iter(message))
+    }
+
+    /// The prioritization fee in lamports, which is the compute unit price
+    /// times the compute unit limit, rounded up to a whole lamport
+    pub fn prioritization_fee(&self) -> u64 {
+        let micro_lamport_fee =
+            u128::from(self.compute_unit_price).saturating_mul(u128::from(self.compute_unit_limit));
+        let fee = micro_lamport_fee
+            .saturating_add(u128::from(MICRO_LAMPORTS_PER_LAMPORT.saturating_sub(1)))
+            .checked_div(u128::from(MICRO_LAMPORTS_PER_LAMPORT))
+            .unwrap_or_default();
+        u64::try_from(fee).unwrap_or(u64::MAX)
+    }
+}
+
+impl From<ComputeBudgetLimits> for FeeBudgetLimits {
+    fn from(limits: ComputeBudgetLimits) -> Self {
+        FeeBudgetLimits {
+            loaded_accounts_data_size_limit: limits.loaded_accounts_bytes,
+            heap_cost: DEFAULT_HEAP_COST,
+            compute_unit_limit: u64::from(limits.compute_unit_limit),
+            prioritization_fee: limits.prioritization_fee(),
+        }
+    }
+}
+
+fn sanitize_requested_heap_size(bytes: u32) -> bool {
+    (MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes) && bytes % 1024 == 0
+}
+
+fn versioned_program_instructions_iter(
+    message: &VersionedMessage,
+) -> impl Iterator<Item = (&Pubkey, &CompiledInstruction)> {
+    let account_keys = message.static_account_keys();
+    message.instructions().iter().filter_map(|instruction| {
+        account_keys
+            .get(usize::from(instruction.program_id_index))
+            .map(|program_id| (program_id, instruction))
+    })
+}
+
+fn versioned_signature_details(message: &VersionedMessage) -> TransactionSignatureDetails {
+    let mut num_secp256k1_instruction_signatures: u64 = 0;
+    let mut num_ed25519_instruction_signatures: u64 = 0;
+    let mut num_secp256r1_instruction_signatures: u64 = 0;
+    for (program_id, instruction) in versioned_program_instructions_iter(message) {
+        let num_verifies = u64::from(instruction.data.first().copied().unwrap_or_default());
+        if secp256k1_program::check_id(program_id) {
+            num_secp256k1_instruction_signatures =
+                num_secp256k1_instruction_signatures.saturating_add(num_verifies);
+        } else if ed25519_program::check_id(program_id) {
+            num_ed25519_instruction_signatures =
+                num_ed25519_instruction_signatures.saturating_add(num_verifies);
+        } else if secp256r1_program::check_id(program_id) {
+            num_secp256r1_instruction_signatures =
+                num_secp256r1_instruction_signatures.saturating_add(num_verifies);
+        }
+    }
+    TransactionSignatureDetails::new(
+        u64::from(message.header().num_required_signatures),
+        num_secp256k1_instruction_signatures,
+        num_ed25519_instruction_signatures,
+        num_secp256r1_instruction_signatures,
+    )
+}
+
+/// Count the write locks requested by a versioned message.
+///
+/// Like [`SanitizedMessage::num_write_locks`], this ignores write-lock
+/// demotion of reserved accounts and program ids, so it's an upper bound on
+/// the accounts the runtime actually write-locks.
+fn versioned_num_write_locks(message: &VersionedMessage) -> u64 {
+    let header = message.header();
+    let num_static_write_locks = message
+        .static_account_keys()
+        .len()
+        .saturating_sub(usize::from(header.num_readonly_signed_accounts))
+        .saturating_sub(usize::from(header.num_readonly_unsigned_accounts));
+    let num_loaded_write_locks: usize = message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| lookup.writable_indexes.len())
+        .sum();
+    num_static_write_locks.saturating_add(num_loaded_write_locks) as u64
+}
+
+impl FeeStructure {
+    /// Estimate the fee details of a sanitized message from its
+    /// compute-budget instructions
+    pub fn estimate_fee_details(
+        &self,
+        message: &SanitizedMessage,
+        include_loaded_account_data_size_in_fee: bool,
+    ) -> Result<FeeDetails> {
+        let budget_limits =
+            FeeBudgetLimits::from(ComputeBudgetLimits::try_from_sanitized_message(message)?);
+        Ok(self.fee_details(
+            message.num_total_signatures(),
+            message.num_write_locks(),
+            &budget_limits,
+            include_loaded_account_data_size_in_fee,
+        ))
+    }
+
+    /// Estimate the fee details of a versioned message from its
+    /// compute-budget instructions.
+    ///
+    /// Accounts loaded from address lookup tables are counted towards the
+    /// write-lock fee without resolving the tables. Writable accounts are
+    /// counted before write-lock demotion, which makes the count an upper
+    /// bound, as it is for [`SanitizedMessage::num_write_locks`].
+    pub fn estimate_versioned_fee_details(
+        &self,
+        message: &VersionedMessage,
+        include_loaded_account_data_size_in_fee: bool,
+    ) -> Result<FeeDetails> {
+        let budget_limits =
+            FeeBudgetLimits::from(ComputeBudgetLimits::try_from_versioned_message(message)?);
+        Ok(self.fee_details(
+            versioned_signature_details(message).total_signatures(),
+            versioned_num_write_locks(message),
+            &budget_limits,
+            include_loaded_account_data_size_in_fee,
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::arithmetic_side_effects)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::Instruction,
+        solana_message::{v0, Message, MessageHeader},
+        std::collections::HashSet,
+    };
+
+    fn message(instructions: &[Instruction]) -> Message {
+        Message::new(instructions, Some(&Pubkey::new_unique()))
+    }
+
+    fn sanitized(message: Message) -> SanitizedMessage {
+        SanitizedMessage::try_from_legacy_message(message, &HashSet::default()).unwrap()
+    }
+
+    fn limits(instructions: &[Instruction]) -> Result<ComputeBudgetLimits> {
+        let message = message(instructions);
+        let limits = ComputeBudgetLimits::try_from_versioned_message(&VersionedMessage::Legacy(
+            message.clone(),
+        ));
+        assert_eq!(
+            limits,
+            ComputeBudgetLimits::try_from_sanitized_message(&sanitized(message))
+        );
+        limits
+    }
+
+    fn transfer() -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![])
+    }
+
+    #[test]
+    fn test_compute_budget_limits() {
+        assert_eq!(
+            limits(&[]),
+            Ok(ComputeBudgetLimits {
+                compute_unit_limit: 0,
+                ..ComputeBudgetLimits::default()
+            })
+        );
+        assert_eq!(
+            limits(&[transfer(), transfer()]),
+            Ok(ComputeBudgetLimits {
+                compute_unit_limit: 2 * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+                ..ComputeBudgetLimits::default()
+            })
+        );
+        assert_eq!(
+            limits(&vec![transfer(); 8]).unwrap().compute_unit_limit,
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(
+            limits(&[
+                ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX),
+                ComputeBudgetInstruction::set_compute_unit_price(7),
+                ComputeBudgetInstruction::request_heap_frame(MAX_HEAP_FRAME_BYTES),
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(1024),
+                transfer(),
+            ]),
+            Ok(ComputeBudgetLimits {
+                updated_heap_bytes: MAX_HEAP_FRAME_BYTES,
+                compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+                compute_unit_price: 7,
+                loaded_accounts_bytes: NonZeroU32::new(1024).unwrap(),
+            })
+        );
+        assert_eq!(
+            limits(&[ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(u32::MAX)])
+                .unwrap()
+                .loaded_accounts_bytes,
+            MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+        );
+    }
+
+    #[test]
+    fn test_compute_budget_limits_errors() {
+        assert_eq!(
+            limits(&[
+                transfer(),
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+            ]),
+            Err(TransactionError::DuplicateInstruction(2))
+        );
+        // Duplicates are reported before an invalid heap size
+        assert_eq!(
+            limits(&[
+                ComputeBudgetInstruction::request_heap_frame(1),
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+                ComputeBudgetInstruction::set_compute_unit_limit(1),
+            ]),
+            Err(TransactionError::DuplicateInstruction(2))
+        );
+        for bytes in [
+            MIN_HEAP_FRAME_BYTES - 1024,
+            MAX_HEAP_FRAME_BYTES + 1024,
+            40_000,
+        ] {
+            assert_eq!(
+                limits(&[
+                    transfer(),
+                    ComputeBudgetInstruction::request_heap_frame(bytes)
+                ]),
+                Err(TransactionError::InstructionError(
+                    1,
+                    InstructionError::InvalidInstructionData
+                ))
+            );
+        }
+        assert_eq!(
+            limits(&[ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(0)]),
+            Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+        );
+        for data in [vec![], vec![0], vec![2, 1], vec![5, 0, 0, 0, 0]] {
+            assert_eq!(
+                limits(&[Instruction::new_with_bytes(
+                    compute_budget::id(),
+                    &data,
+                    vec![]
+                )]),
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::InvalidInstructionData
+                ))
+            );
+        }
+        // Trailing bytes are ignored, like in the runtime
+        assert_eq!(
+            limits(&[Instruction::new_with_bytes(
+                compute_budget::id(),
+                &[2, 1, 0, 0, 0, 9],
+                vec![]
+            )])
+            .unwrap()
+            .compute_unit_limit,
+            1
+        );
+    }
+
+    #[test]
+    fn test_prioritization_fee() {
+        let fee = |compute_unit_price, compute_unit_limit| {
+            ComputeBudgetLimits {
+                compute_unit_price,
+                compute_unit_limit,
+                ..ComputeBudgetLimits::default()
+            }
+            .prioritization_fee()
+        };
+        assert_eq!(fee(0, MAX_COMPUTE_UNIT_LIMIT), 0);
+        assert_eq!(fee(1, 1), 1);
+        assert_eq!(fee(1_000_000, 1), 1);
+        assert_eq!(fee(1_000_001, 1), 2);
+        assert_eq!(fee(1, 1_000_000), 1);
+        assert_eq!(fee(3, 1_000_000), 3);
+        assert_eq!(fee(u64::MAX, MAX_COMPUTE_UNIT_LIMIT), u64::MAX);
+    }
+
+    #[test]
+    fn test_estimate_fee_details() {
+        let fee_structure = FeeStructure::default();
+        let message = message(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+            ComputeBudgetInstruction::set_compute_unit_price(25),
+            Instruction::new_with_bytes(ed25519_program::id(), &[2], vec![]),
+            transfer(),
+        ]);
+        let expected = FeeDetails::new(3 * fee_structure.lamports_per_signature, 3);
+        assert_eq!(
+            fee_structure
+                .estimate_versioned_fee_details(&VersionedMessage::Legacy(message.clone()), true),
+            Ok(expected)
+        );
+        assert_eq!(
+            fee_structure.estimate_fee_details(&sanitized(message), true),
+            Ok(expected)
+        );
+
+        let message = VersionedMessage::Legacy(self::message(&[
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+        ]));
+        assert_eq!(
+            fee_structure.estimate_versioned_fee_details(&message, true),
+            Err(TransactionError::DuplicateInstruction(1))
+        );
+    }
+
+    #[test]
+    fn test_estimate_versioned_fee_details_write_locks() {
+        let fee_structure = FeeStructure {
+            lamports_per_write_lock: 10,
+            ..FeeStructure::default()
+        };
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program_id],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![0])],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0, 1],
+                readonly_indexes: vec![2],
+            }],
+        });
+        // The payer and two loaded accounts are write-locked
+        assert_eq!(
+            fee_structure.estimate_versioned_fee_details(&message, false),
+            Ok(FeeDetails::new(
+                fee_structure.lamports_per_signature + 3 * 10,
+                0
+            ))
+        );
+
+        // Unsanitary messages are rejected
+        let mut message = message;
+        if let VersionedMessage::V0(message) = &mut message {
+            message.instructions[0].program_id_index = 5;
+        }
+        assert_eq!(
+            fee_structure.estimate_versioned_fee_details(&message, false),
+            Err(TransactionError::SanitizeFailure)
+        );
+    }
+}
@@ -2,6 +2,8 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
 
+#[cfg(all(feature = "compute-budget", not(target_os = "solana")))]
+pub mod compute_budget;
 #[cfg(not(target_os = "solana"))]
 use solana_message::SanitizedMessage;
 use std::num::NonZeroU32;
@@ -164,12 +166,23 @@ impl FeeStructure {
             return FeeDetails::default();
         }
 
-        let signature_fee = message
-            .num_total_signatures()
-            .saturating_mul(self.lamports_per_signature);
-        let write_lock_fee = message
-            .num_write_locks()
-            .saturating_mul(self.lamports_per_write_lock);
+        self.fee_details(
+            message.num_total_signatures(),
+            message.num_write_locks(),
+            budget_limits,
+            include_loaded_account_data_size_in_fee,
+        )
+    }
+
+    fn fee_details(
+        &self,
+        num_signatures: u64,
+        num_write_locks: u64,
+        budget_limits: &FeeBudgetLimits,
+        include_loaded_account_data_size_in_fee: bool,
+    ) -> FeeDetails {
+        let signature_fee = num_signatures.saturating_mul(self.lamports_per_signature);
+        let write_lock_fee = num_write_locks.saturating_mul(self.lamports_per_write_lock);
 
         // `compute_fee` covers costs for both requested_compute_units and
         // requested_loaded_account_data_size
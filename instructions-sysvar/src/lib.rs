@@ -31,6 +31,9 @@
 #![allow(clippy::arithmetic_side_effects)]
 
 pub use badchain_sdk_ids::sysvar::instructions::{check_id, id, ID};
+
+pub mod precompile;
+
 #[cfg(feature = "dev-context-only-utils")]
 use qualifier_attr::qualifiers;
 #[cfg(not(target_os = "solana"))]
@@ -0,0 +1,307 @@
+//! The signature offsets shared by the ed25519 and secp256r1 precompiles.
+//!
+//! Both precompiles start their instruction data with the number of
+//! signatures and a padding byte, followed by seven little-endian `u16`
+//! offsets per signature: the offset and instruction index of the signature,
+//! the offset and instruction index of the public key, and the offset, size
+//! and instruction index of the message. An instruction index of `u16::MAX`
+//! refers to the precompile instruction itself.
+//!
+//! [`build_instruction_data`] lays out the data of such an instruction and
+//! [`load_verified_signatures`] reads back what an instruction in the current
+//! transaction verified. The precompile crates wrap both with their own sizes
+//! and program ids.
+
+use {
+    crate::load_instruction_at_checked, solana_account_info::AccountInfo,
+    solana_precompile_error::PrecompileError, solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+};
+
+pub const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+pub const SIGNATURE_OFFSETS_START: usize = 2;
+
+/// Where a signature, public key or message verified by a precompile
+/// instruction is found
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DataRef<'a> {
+    /// Bytes appended to the data of the precompile instruction itself
+    Inline(&'a [u8]),
+    /// `size` bytes at `offset` in the data of the instruction at
+    /// `instruction_index` in the transaction
+    Instruction {
+        instruction_index: u16,
+        offset: u16,
+        size: u16,
+    },
+}
+
+/// A signature verified by a precompile instruction, with its public key and
+/// message
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedSignatureData {
+    pub signature: Vec<u8>,
+    pub pubkey: Vec<u8>,
+    pub message: Vec<u8>,
+}
+
+/// Build the data of a precompile instruction verifying `signatures`, each
+/// given as its signature, public key and message, checking that the offsets
+/// and sizes of all data fit the instruction format.
+///
+/// Identical inline data, like a message signed by several keys, is only
+/// appended once. References to other instructions can't be checked against
+/// their data.
+pub fn build_instruction_data(
+    signatures: &[[DataRef; 3]],
+    signature_size: usize,
+    pubkey_size: usize,
+) -> Result<Vec<u8>, PrecompileError> {
+    let num_signatures =
+        u8::try_from(signatures.len()).map_err(|_| PrecompileError::InvalidInstructionDataSize)?;
+    let data_start = SIGNATURE_OFFSETS_SERIALIZED_SIZE
+        .saturating_mul(signatures.len())
+        .saturating_add(SIGNATURE_OFFSETS_START);
+
+    let mut instruction_data = Vec::with_capacity(data_start);
+    // add padding byte so that offset structure is aligned
+    instruction_data.extend_from_slice(&[num_signatures, 0]);
+    let mut inline_data: Vec<u8> = Vec::new();
+    // start and end of every inline slice in `inline_data`, to share
+    // identical ones
+    let mut inline_slices: Vec<(usize, usize)> = Vec::new();
+    let mut locate = |data_ref: DataRef, expected_size: Option<usize>, error| {
+        let (instruction_index, offset, size) = match data_ref {
+            DataRef::Inline(bytes) => {
+                let start = inline_slices
+                    .iter()
+                    .find(|(start, end)| inline_data[*start..*end] == *bytes)
+                    .map(|(start, _)| *start)
+                    .unwrap_or_else(|| {
+                        let start = inline_data.len();
+                        inline_data.extend_from_slice(bytes);
+                        inline_slices.push((start, inline_data.len()));
+                        start
+                    });
+                let offset = u16::try_from(data_start.saturating_add(start))
+                    .map_err(|_| PrecompileError::InvalidInstructionDataSize)?;
+                let size = u16::try_from(bytes.len())
+                    .map_err(|_| PrecompileError::InvalidInstructionDataSize)?;
+                (u16::MAX, offset, size)
+            }
+            DataRef::Instruction {
+                instruction_index,
+                offset,
+                size,
+            } => {
+                // u16::MAX refers to the precompile instruction itself
+                if instruction_index == u16::MAX || offset.checked_add(size).is_none() {
+                    return Err(PrecompileError::InvalidDataOffsets);
+                }
+                (instruction_index, offset, size)
+            }
+        };
+        if expected_size.is_some_and(|expected_size| size as usize != expected_size) {
+            return Err(error);
+        }
+        Ok((instruction_index, offset, size))
+    };
+
+    for [signature, pubkey, message] in signatures {
+        let (signature_instruction_index, signature_offset, _) = locate(
+            *signature,
+            Some(signature_size),
+            PrecompileError::InvalidSignature,
+        )?;
+        let (public_key_instruction_index, public_key_offset, _) = locate(
+            *pubkey,
+            Some(pubkey_size),
+            PrecompileError::InvalidPublicKey,
+        )?;
+        let (message_instruction_index, message_data_offset, message_data_size) =
+            locate(*message, None, PrecompileError::InvalidDataOffsets)?;
+        for field in [
+            signature_offset,
+            signature_instruction_index,
+            public_key_offset,
+            public_key_instruction_index,
+            message_data_offset,
+            message_data_size,
+            message_instruction_index,
+        ] {
+            instruction_data.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+
+    debug_assert_eq!(instruction_data.len(), data_start);
+    instruction_data.extend_from_slice(&inline_data);
+    if instruction_data.len() > u16::MAX as usize {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    Ok(instruction_data)
+}
+
+/// Load the signatures verified by the precompile instruction at `index` in
+/// the current transaction, from the instructions sysvar.
+///
+/// A transaction only executes if all of its precompile instructions
+/// succeed, so a program can trust the returned signatures. Data referenced
+/// in other instructions is read from those instructions.
+///
+/// # Errors
+///
+/// Returns [`ProgramError::IncorrectProgramId`] if the instruction at
+/// `index` isn't owned by `program_id`, and the errors of
+/// [`load_instruction_at_checked`] if the sysvar can't be read.
+pub fn load_verified_signatures(
+    index: usize,
+    instructions_sysvar: &AccountInfo,
+    program_id: &Pubkey,
+    signature_size: usize,
+    pubkey_size: usize,
+) -> Result<Vec<VerifiedSignatureData>, ProgramError> {
+    let instruction = load_instruction_at_checked(index, instructions_sysvar)?;
+    if instruction.program_id != *program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = &instruction.data;
+    if data.len() < SIGNATURE_OFFSETS_START {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let num_signatures = data[0] as usize;
+    let data_start = num_signatures
+        .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .saturating_add(SIGNATURE_OFFSETS_START);
+    if data.len() < data_start {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // Instructions referenced by the offsets, loaded once each
+    let mut instruction_datas: Vec<(u16, Vec<u8>)> = Vec::new();
+    let mut load_slice = |instruction_index: u16, offset: u16, size: usize| {
+        let start = offset as usize;
+        let end = start.saturating_add(size);
+        let instruction_data = if instruction_index == u16::MAX {
+            data.as_slice()
+        } else {
+            let position = match instruction_datas
+                .iter()
+                .position(|(index, _)| *index == instruction_index)
+            {
+                Some(position) => position,
+                None => {
+                    let instruction = load_instruction_at_checked(
+                        instruction_index as usize,
+                        instructions_sysvar,
+                    )?;
+                    instruction_datas.push((instruction_index, instruction.data));
+                    instruction_datas.len().saturating_sub(1)
+                }
+            };
+            instruction_datas[position].1.as_slice()
+        };
+        instruction_data
+            .get(start..end)
+            .map(<[u8]>::to_vec)
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+
+    data[SIGNATURE_OFFSETS_START..data_start]
+        .chunks_exact(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .map(|offsets| {
+            let field = |i: usize| u16::from_le_bytes([offsets[2 * i], offsets[2 * i + 1]]);
+            Ok(VerifiedSignatureData {
+                signature: load_slice(field(1), field(0), signature_size)?,
+                pubkey: load_slice(field(3), field(2), pubkey_size)?,
+                message: load_slice(field(6), field(4), field(5) as usize)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_instruction_data() {
+        let message = b"message";
+        let signatures = [[1; 4], [2; 4]];
+        let pubkey = [3; 2];
+        let refs: Vec<_> = signatures
+            .iter()
+            .map(|signature| {
+                [
+                    DataRef::Inline(signature),
+                    DataRef::Inline(&pubkey),
+                    DataRef::Instruction {
+                        instruction_index: 1,
+                        offset: 2,
+                        size: message.len() as u16,
+                    },
+                ]
+            })
+            .collect();
+        let data = build_instruction_data(&refs, 4, 2).unwrap();
+
+        // The public key shared by both signatures is only appended once
+        let data_start = SIGNATURE_OFFSETS_START + 2 * SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+        assert_eq!(data[..2], [2, 0]);
+        assert_eq!(data[data_start..], [&[1; 4][..], &[3; 2], &[2; 4]].concat());
+        let offsets = |i: usize| -> Vec<u16> {
+            data[SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_SERIALIZED_SIZE..]
+                [..SIGNATURE_OFFSETS_SERIALIZED_SIZE]
+                .chunks(2)
+                .map(|field| u16::from_le_bytes([field[0], field[1]]))
+                .collect()
+        };
+        let (data_start, message_len) = (data_start as u16, message.len() as u16);
+        assert_eq!(
+            offsets(0),
+            [
+                data_start,
+                u16::MAX,
+                data_start + 4,
+                u16::MAX,
+                2,
+                message_len,
+                1
+            ]
+        );
+        assert_eq!(
+            offsets(1),
+            [
+                data_start + 6,
+                u16::MAX,
+                data_start + 4,
+                u16::MAX,
+                2,
+                message_len,
+                1
+            ]
+        );
+
+        // Sizes are checked, and the precompile instruction can't be
+        // referenced by index
+        let mut invalid = refs.clone();
+        invalid[0][0] = DataRef::Inline(&pubkey);
+        assert_eq!(
+            build_instruction_data(&invalid, 4, 2),
+            Err(PrecompileError::InvalidSignature)
+        );
+        assert_eq!(
+            build_instruction_data(&refs, 4, 3),
+            Err(PrecompileError::InvalidPublicKey)
+        );
+        invalid[0][0] = DataRef::Inline(&signatures[0]);
+        invalid[0][2] = DataRef::Instruction {
+            instruction_index: u16::MAX,
+            offset: 0,
+            size: 1,
+        };
+        assert_eq!(
+            build_instruction_data(&invalid, 4, 2),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+    }
+}
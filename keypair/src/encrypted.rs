@@ -0,0 +1,521 @@
+//! Password-encrypted keypair files.
+//!
+//! [`write_keypair_file`] stores the secret key as a plaintext JSON array. An
+//! [`EncryptedKeypair`] instead stores it encrypted with a key derived from a
+//! password, in a versioned JSON document:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "pubkey": "<base58 pubkey>",
+//!   "kdf": {
+//!     "function": "scrypt",
+//!     "log_n": 17,
+//!     "r": 8,
+//!     "p": 1,
+//!     "salt": "<32 hex-encoded bytes>"
+//!   },
+//!   "cipher": {
+//!     "function": "xchacha20-poly1305",
+//!     "nonce": "<24 hex-encoded bytes>",
+//!     "ciphertext": "<80 hex-encoded bytes>"
+//!   }
+//! }
+//! ```
+//!
+//! The pubkey is kept in clear so that tools can tell which key a file holds
+//! without the password. It is authenticated as associated data of the
+//! cipher, so it can't be changed without the decryption failing.
+//!
+//! [`Keypair::read_with_password`] and [`Keypair::read_from_file_with_password`]
+//! read both plaintext and encrypted keypairs, and only ask for a password
+//! when the keypair is encrypted. [`encrypt_keypair_file`] migrates an
+//! existing plaintext keypair file in place.
+//!
+//! [`write_keypair_file`]: crate::write_keypair_file
+//! [`Keypair::read_with_password`]: solana_signer::EncodableKey::read_with_password
+//! [`Keypair::read_from_file_with_password`]: solana_signer::EncodableKey::read_from_file_with_password
+
+use {
+    crate::{read_keypair, Keypair},
+    chacha20poly1305::{
+        aead::{Aead, Payload},
+        KeyInit, XChaCha20Poly1305, XNonce,
+    },
+    core::fmt,
+    rand0_7::{rngs::OsRng, RngCore},
+    serde_derive::{Deserialize, Serialize},
+    solana_pubkey::Pubkey,
+    solana_signer::{EncodableKey, EncodableKeypair, PasswordCallback, Signer},
+    std::{
+        error, fs,
+        io::{Read, Write},
+        path::Path,
+    },
+    zeroize::Zeroizing,
+};
+
+/// The version of the encrypted keypair format written by this crate
+pub const ENCRYPTED_KEYPAIR_VERSION: u32 = 1;
+
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const KEY_LENGTH: usize = 32;
+/// Upper bound on the memory used by scrypt when reading a file, so that a
+/// malicious file can't exhaust memory
+const MAX_SCRYPT_MEMORY: u64 = 1024 * 1024 * 1024;
+const MAX_SCRYPT_P: u32 = 16;
+
+/// Reasons that an encrypted keypair can't be read or decrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptedKeypairError {
+    /// The file format version is not supported
+    UnsupportedVersion(u32),
+    /// The key derivation parameters are invalid or too expensive
+    InvalidKdfParams,
+    /// A salt, nonce or ciphertext has the wrong length or encoding
+    InvalidEncoding,
+    /// The password is wrong or the file was modified
+    DecryptionFailed,
+    /// The decrypted keypair doesn't match the pubkey in the file
+    PubkeyMismatch,
+}
+
+impl error::Error for EncryptedKeypairError {}
+
+impl fmt::Display for EncryptedKeypairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported encrypted keypair version {version}")
+            }
+            Self::InvalidKdfParams => f.write_str("invalid key derivation parameters"),
+            Self::InvalidEncoding => f.write_str("invalid encrypted keypair encoding"),
+            Self::DecryptionFailed => {
+                f.write_str("failed to decrypt keypair: wrong password or corrupted file")
+            }
+            Self::PubkeyMismatch => f.write_str("decrypted keypair does not match its pubkey"),
+        }
+    }
+}
+
+/// Parameters of the scrypt key derivation function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    /// Base 2 logarithm of the CPU/memory cost
+    pub log_n: u8,
+    /// Block size
+    pub r: u32,
+    /// Parallelization
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// The parameters recommended by the scrypt authors for interactive use,
+    /// which take 128MiB of memory
+    fn default() -> Self {
+        Self {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl ScryptParams {
+    fn memory(&self) -> Option<u64> {
+        1u64.checked_shl(u32::from(self.log_n))?
+            .checked_mul(u64::from(self.r))?
+            .checked_mul(128)
+    }
+
+    fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+    ) -> Result<Zeroizing<[u8; KEY_LENGTH]>, EncryptedKeypairError> {
+        if self.p > MAX_SCRYPT_P
+            || self
+                .memory()
+                .is_none_or(|memory| memory > MAX_SCRYPT_MEMORY)
+        {
+            return Err(EncryptedKeypairError::InvalidKdfParams);
+        }
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, KEY_LENGTH)
+            .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        scrypt::scrypt(password, salt, &params, key.as_mut_slice())
+            .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "function", rename_all = "kebab-case")]
+enum Kdf {
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+        #[serde(with = "hex_bytes")]
+        salt: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "function", rename_all = "kebab-case")]
+enum Cipher {
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305 {
+        #[serde(with = "hex_bytes")]
+        nonce: Vec<u8>,
+        #[serde(with = "hex_bytes")]
+        ciphertext: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    #[serde(with = "pubkey_string")]
+    pubkey: Pubkey,
+    kdf: Kdf,
+    cipher: Cipher,
+}
+
+/// A keypair encrypted with a password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedKeypair(Document);
+
+impl EncryptedKeypair {
+    /// Encrypt `keypair` with a key derived from `password`
+    pub fn encrypt(
+        keypair: &Keypair,
+        password: &str,
+        params: &ScryptParams,
+    ) -> Result<Self, EncryptedKeypairError> {
+        let mut salt = vec![0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = vec![0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+
+        let pubkey = keypair.pubkey();
+        let key = params.derive_key(password.as_bytes(), &salt)?;
+        let ciphertext = XChaCha20Poly1305::new((&*key).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: keypair.to_zeroizing_bytes().as_slice(),
+                    aad: pubkey.as_ref(),
+                },
+            )
+            .map_err(|_| EncryptedKeypairError::InvalidEncoding)?;
+
+        Ok(Self(Document {
+            version: ENCRYPTED_KEYPAIR_VERSION,
+            pubkey,
+            kdf: Kdf::Scrypt {
+                log_n: params.log_n,
+                r: params.r,
+                p: params.p,
+                salt,
+            },
+            cipher: Cipher::XChaCha20Poly1305 { nonce, ciphertext },
+        }))
+    }
+
+    /// Decrypt the keypair with `password`
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, EncryptedKeypairError> {
+        let Document {
+            version,
+            pubkey,
+            kdf,
+            cipher,
+        } = &self.0;
+        if *version != ENCRYPTED_KEYPAIR_VERSION {
+            return Err(EncryptedKeypairError::UnsupportedVersion(*version));
+        }
+
+        let key = match kdf {
+            Kdf::Scrypt { log_n, r, p, salt } => {
+                if salt.len() != SALT_LENGTH {
+                    return Err(EncryptedKeypairError::InvalidEncoding);
+                }
+                let params = ScryptParams {
+                    log_n: *log_n,
+                    r: *r,
+                    p: *p,
+                };
+                params.derive_key(password.as_bytes(), salt)?
+            }
+        };
+
+        let keypair_bytes = Zeroizing::new(match cipher {
+            Cipher::XChaCha20Poly1305 { nonce, ciphertext } => {
+                if nonce.len() != NONCE_LENGTH {
+                    return Err(EncryptedKeypairError::InvalidEncoding);
+                }
+                XChaCha20Poly1305::new((&*key).into())
+                    .decrypt(
+                        XNonce::from_slice(nonce),
+                        Payload {
+                            msg: ciphertext,
+                            aad: pubkey.as_ref(),
+                        },
+                    )
+                    .map_err(|_| EncryptedKeypairError::DecryptionFailed)?
+            }
+        });
+
+        let keypair = Keypair::try_from(keypair_bytes.as_slice())
+            .map_err(|_| EncryptedKeypairError::InvalidEncoding)?;
+        if keypair.pubkey() != *pubkey {
+            return Err(EncryptedKeypairError::PubkeyMismatch);
+        }
+        Ok(keypair)
+    }
+
+    /// The pubkey of the encrypted keypair, which is stored in clear
+    pub fn pubkey(&self) -> Pubkey {
+        self.0.pubkey
+    }
+
+    /// The format version of the encrypted keypair
+    pub fn version(&self) -> u32 {
+        self.0.version
+    }
+}
+
+impl EncodableKey for EncryptedKeypair {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        let document: Document = serde_json::from_reader(reader)?;
+        if document.version != ENCRYPTED_KEYPAIR_VERSION {
+            return Err(EncryptedKeypairError::UnsupportedVersion(document.version).into());
+        }
+        Ok(Self(document))
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+        let json = serde_json::to_string_pretty(&self.0)?;
+        writer.write_all(json.as_bytes())?;
+        Ok(Zeroizing::new(json))
+    }
+}
+
+impl EncodableKeypair for EncryptedKeypair {
+    type Pubkey = Pubkey;
+
+    fn encodable_pubkey(&self) -> Self::Pubkey {
+        self.pubkey()
+    }
+}
+
+/// Returns true if `data` looks like an encrypted keypair rather than a
+/// plaintext JSON array
+pub fn is_encrypted_keypair(data: &[u8]) -> bool {
+    data.iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'{')
+}
+
+/// Read a plaintext or encrypted keypair, calling `password` only if the
+/// keypair is encrypted
+pub fn read_keypair_with_password<R: Read>(
+    reader: &mut R,
+    password: PasswordCallback,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    // Reserve more than a keypair file needs, so that reading a well-formed
+    // file doesn't grow the buffer and leave partial copies of the secret in
+    // freed memory. Larger inputs still grow it.
+    let mut buffer = Zeroizing::new(Vec::with_capacity(4096));
+    reader.read_to_end(&mut buffer)?;
+    if !is_encrypted_keypair(&buffer) {
+        return read_keypair(&mut buffer.as_slice());
+    }
+    let encrypted_keypair = EncryptedKeypair::read(&mut buffer.as_slice())?;
+    let password = Zeroizing::new(password()?);
+    Ok(encrypted_keypair.decrypt(&password)?)
+}
+
+/// Encrypt a plaintext keypair file in place and return its pubkey.
+///
+/// The encrypted keypair is written to a temporary file next to `path`, which
+/// then replaces the original, so the plaintext file is left untouched if
+/// anything fails. Files which are already encrypted are rejected.
+pub fn encrypt_keypair_file<F: AsRef<Path>>(
+    path: F,
+    password: &str,
+    params: &ScryptParams,
+) -> Result<Pubkey, Box<dyn error::Error>> {
+    let path = path.as_ref();
+    let data = Zeroizing::new(fs::read(path)?);
+    if is_encrypted_keypair(&data) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "keypair file is already encrypted",
+        )
+        .into());
+    }
+    let keypair = read_keypair(&mut data.as_slice())?;
+    let encrypted_keypair = EncryptedKeypair::encrypt(&keypair, password, params)?;
+    // Check that the keypair can be recovered before replacing the original
+    if encrypted_keypair.decrypt(password)?.pubkey() != keypair.pubkey() {
+        return Err(EncryptedKeypairError::PubkeyMismatch.into());
+    }
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".encrypting");
+    encrypted_keypair.write_to_file(&temp_path)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })?;
+    Ok(keypair.pubkey())
+}
+
+mod hex_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        hex::decode(string).map_err(D::Error::custom)
+    }
+}
+
+mod pubkey_string {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+        solana_pubkey::Pubkey,
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::write_keypair_file, std::cell::Cell};
+
+    // Cheap parameters to keep the tests fast
+    const TEST_PARAMS: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, "hunter2", &TEST_PARAMS).unwrap();
+        assert_eq!(encrypted.pubkey(), keypair.pubkey());
+        assert_eq!(encrypted.version(), ENCRYPTED_KEYPAIR_VERSION);
+        assert_eq!(
+            encrypted.decrypt("hunter2").unwrap().to_bytes(),
+            keypair.to_bytes()
+        );
+        assert_eq!(
+            encrypted.decrypt("hunter3").unwrap_err(),
+            EncryptedKeypairError::DecryptionFailed
+        );
+
+        // The clear pubkey is authenticated
+        let mut tampered = encrypted.clone();
+        tampered.0.pubkey = Pubkey::new_unique();
+        assert_eq!(
+            tampered.decrypt("hunter2").unwrap_err(),
+            EncryptedKeypairError::DecryptionFailed
+        );
+
+        let mut tampered = encrypted;
+        tampered.0.kdf = Kdf::Scrypt {
+            log_n: 40,
+            r: 8,
+            p: 1,
+            salt: vec![0; SALT_LENGTH],
+        };
+        assert_eq!(
+            tampered.decrypt("hunter2").unwrap_err(),
+            EncryptedKeypairError::InvalidKdfParams
+        );
+    }
+
+    #[test]
+    fn test_read_write_encrypted_keypair() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, "password", &TEST_PARAMS).unwrap();
+        let mut buffer = Vec::new();
+        let json = encrypted.write(&mut buffer).unwrap();
+        assert!(json.contains(&keypair.pubkey().to_string()));
+        assert_eq!(
+            EncryptedKeypair::read(&mut buffer.as_slice()).unwrap(),
+            encrypted
+        );
+
+        let calls = Cell::new(0);
+        let mut password = || {
+            calls.set(calls.get() + 1);
+            Ok("password".to_string())
+        };
+        let read_keypair =
+            Keypair::read_with_password(&mut buffer.as_slice(), &mut password).unwrap();
+        assert_eq!(read_keypair.pubkey(), keypair.pubkey());
+        assert_eq!(calls.get(), 1);
+
+        // Plaintext keypairs are read without asking for a password
+        let mut plaintext = Vec::new();
+        keypair.write(&mut plaintext).unwrap();
+        let read_keypair =
+            Keypair::read_with_password(&mut plaintext.as_slice(), &mut password).unwrap();
+        assert_eq!(read_keypair.pubkey(), keypair.pubkey());
+        assert_eq!(calls.get(), 1);
+
+        // Reading an encrypted keypair without a password fails
+        assert!(Keypair::read(&mut buffer.as_slice()).is_err());
+
+        let mut wrong_password = || Ok("wrong".to_string());
+        assert!(Keypair::read_with_password(&mut buffer.as_slice(), &mut wrong_password).is_err());
+
+        let document = String::from_utf8(buffer).unwrap().replace(
+            &format!("\"version\": {ENCRYPTED_KEYPAIR_VERSION}"),
+            "\"version\": 2",
+        );
+        assert!(EncryptedKeypair::read(&mut document.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_keypair_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("id.json");
+        let keypair = Keypair::new();
+        write_keypair_file(&keypair, &path).unwrap();
+
+        assert_eq!(
+            encrypt_keypair_file(&path, "password", &TEST_PARAMS).unwrap(),
+            keypair.pubkey()
+        );
+        assert!(is_encrypted_keypair(&fs::read(&path).unwrap()));
+        assert!(encrypt_keypair_file(&path, "password", &TEST_PARAMS).is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let read_keypair =
+            Keypair::read_from_file_with_password(&path, &mut || Ok("password".to_string()))
+                .unwrap();
+        assert_eq!(read_keypair.to_bytes(), keypair.to_bytes());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! Grinding for vanity addresses.
+//!
+//! A [`Grinder`] searches, on several threads, for a keypair or a
+//! [`Pubkey::create_with_seed`] seed whose base58 address matches one of a
+//! set of [`GrindPattern`]s. A pattern is a prefix, a suffix or both, and
+//! can ignore case.
+//!
+//! Every character of a pattern multiplies the expected number of attempts
+//! by about 58, or about 29 for letters when ignoring case, so
+//! [`Grinder::expected_attempts`] is worth checking before starting a
+//! search. The search reports its progress to an optional callback and stops
+//! early when its cancellation flag is set.
+
+use {
+    crate::Keypair,
+    core::fmt,
+    rand0_7::{
+        rngs::{OsRng, StdRng},
+        Rng, RngCore, SeedableRng,
+    },
+    solana_pubkey::{Pubkey, PubkeyError, MAX_SEED_LEN},
+    solana_signer::Signer,
+    std::{
+        error,
+        num::NonZeroUsize,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+    zeroize::Zeroizing,
+};
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// The length of the longest base58 encoded pubkey
+const MAX_PUBKEY_BASE58_LENGTH: usize = 44;
+/// The length of the random seeds tried by [`Grinder::grind_seed`]
+pub const GRIND_SEED_LENGTH: usize = 16;
+const _: () = assert!(GRIND_SEED_LENGTH <= MAX_SEED_LEN);
+/// Workers publish their attempt counts in batches of this size, to keep
+/// the shared counter from becoming a bottleneck
+const ATTEMPTS_BATCH: u64 = 256;
+/// How often the search checks for cancellation when there is no progress
+/// callback
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Shortest interval between progress reports, so that a zero interval
+/// doesn't turn the reporting loop into a busy loop
+pub const MIN_PROGRESS_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Reasons that a grind can't start or didn't finish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrindError {
+    /// The pattern has neither a prefix nor a suffix
+    EmptyPattern,
+    /// The prefix and suffix together are longer than any address
+    PatternTooLong(usize),
+    /// The character can't appear in a base58 address
+    InvalidCharacter(char),
+    /// The search was cancelled before a match was found
+    Cancelled,
+    /// The base and owner of a seed search can't derive addresses
+    Pubkey(PubkeyError),
+}
+
+impl error::Error for GrindError {}
+
+impl fmt::Display for GrindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyPattern => f.write_str("pattern has no prefix or suffix"),
+            Self::PatternTooLong(length) => {
+                write!(
+                    f,
+                    "pattern of {length} characters is longer than any address"
+                )
+            }
+            Self::InvalidCharacter(c) => write!(f, "'{c}' is not a base58 character"),
+            Self::Cancelled => f.write_str("grind was cancelled"),
+            Self::Pubkey(err) => write!(f, "cannot derive addresses: {err}"),
+        }
+    }
+}
+
+impl From<PubkeyError> for GrindError {
+    fn from(err: PubkeyError) -> Self {
+        Self::Pubkey(err)
+    }
+}
+
+/// A prefix and suffix that a base58 address must have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrindPattern {
+    prefix: String,
+    suffix: String,
+    ignore_case: bool,
+}
+
+impl GrindPattern {
+    /// A pattern of addresses starting with `prefix` and ending with
+    /// `suffix`, either of which may be empty
+    pub fn new(prefix: &str, suffix: &str, ignore_case: bool) -> Result<Self, GrindError> {
+        if prefix.is_empty() && suffix.is_empty() {
+            return Err(GrindError::EmptyPattern);
+        }
+        let length = prefix.len().saturating_add(suffix.len());
+        if length > MAX_PUBKEY_BASE58_LENGTH {
+            return Err(GrindError::PatternTooLong(length));
+        }
+        let pattern = Self {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            ignore_case,
+        };
+        if let Some(c) = pattern.chars().find(|c| pattern.alphabet_matches(*c) == 0) {
+            return Err(GrindError::InvalidCharacter(c));
+        }
+        Ok(pattern)
+    }
+
+    /// A pattern of addresses starting with `prefix`
+    pub fn prefix(prefix: &str, ignore_case: bool) -> Result<Self, GrindError> {
+        Self::new(prefix, "", ignore_case)
+    }
+
+    /// A pattern of addresses ending with `suffix`
+    pub fn suffix(suffix: &str, ignore_case: bool) -> Result<Self, GrindError> {
+        Self::new("", suffix, ignore_case)
+    }
+
+    /// Whether `address`, a base58 encoded pubkey, matches the pattern
+    pub fn matches(&self, address: &str) -> bool {
+        let address = address.as_bytes();
+        if address.len() < self.prefix.len().saturating_add(self.suffix.len()) {
+            return false;
+        }
+        let (start, end) = (
+            &address[..self.prefix.len()],
+            &address[address.len().saturating_sub(self.suffix.len())..],
+        );
+        if self.ignore_case {
+            start.eq_ignore_ascii_case(self.prefix.as_bytes())
+                && end.eq_ignore_ascii_case(self.suffix.as_bytes())
+        } else {
+            start == self.prefix.as_bytes() && end == self.suffix.as_bytes()
+        }
+    }
+
+    /// The probability that a random address matches the pattern, assuming
+    /// every base58 character is equally likely in every position. That's
+    /// exact for suffixes, but only an approximation for prefixes, since the
+    /// first character of an address is biased towards small digits.
+    pub fn probability(&self) -> f64 {
+        self.expected_attempts().recip()
+    }
+
+    /// The expected number of attempts to find a matching address
+    pub fn expected_attempts(&self) -> f64 {
+        self.chars()
+            .map(|c| BASE58_ALPHABET.len() as f64 / f64::from(self.alphabet_matches(c)))
+            .product()
+    }
+
+    fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.prefix.chars().chain(self.suffix.chars())
+    }
+
+    /// The number of base58 characters that `c` matches
+    fn alphabet_matches(&self, c: char) -> u32 {
+        BASE58_ALPHABET
+            .iter()
+            .filter(|&&a| {
+                if self.ignore_case {
+                    char::from(a).eq_ignore_ascii_case(&c)
+                } else {
+                    char::from(a) == c
+                }
+            })
+            .count() as u32
+    }
+}
+
+/// The progress of a grind, reported to the progress callback
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrindProgress {
+    /// The number of addresses tried so far
+    pub attempts: u64,
+    /// The time since the grind started
+    pub elapsed: Duration,
+    /// The expected number of attempts to find a match
+    pub expected_attempts: f64,
+}
+
+impl GrindProgress {
+    /// The number of addresses tried per second
+    pub fn attempts_per_second(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Receives the progress of a grind
+pub type GrindProgressCallback<'a> = &'a (dyn Fn(&GrindProgress) + Sync);
+
+/// A search for addresses matching any of a set of patterns
+pub struct Grinder<'a> {
+    patterns: Vec<GrindPattern>,
+    num_threads: NonZeroUsize,
+    progress: Option<(Duration, GrindProgressCallback<'a>)>,
+    cancel: Option<&'a AtomicBool>,
+}
+
+impl<'a> Grinder<'a> {
+    /// A search for addresses matching `pattern`, on as many threads as
+    /// there are CPUs
+    pub fn new(pattern: GrindPattern) -> Self {
+        Self {
+            patterns: vec![pattern],
+            num_threads: thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            progress: None,
+            cancel: None,
+        }
+    }
+
+    /// Also accept addresses matching `pattern`
+    pub fn pattern(mut self, pattern: GrindPattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Search on `num_threads` threads
+    pub fn num_threads(mut self, num_threads: NonZeroUsize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Call `callback` from the calling thread every `interval` until the
+    /// search ends. Intervals shorter than [`MIN_PROGRESS_INTERVAL`] are
+    /// raised to it.
+    pub fn on_progress(mut self, interval: Duration, callback: GrindProgressCallback<'a>) -> Self {
+        self.progress = Some((interval.max(MIN_PROGRESS_INTERVAL), callback));
+        self
+    }
+
+    /// Stop the search with [`GrindError::Cancelled`] once `cancel` is set
+    pub fn cancel_flag(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// The expected number of attempts to find an address matching any of
+    /// the patterns
+    pub fn expected_attempts(&self) -> f64 {
+        self.patterns
+            .iter()
+            .map(GrindPattern::probability)
+            .sum::<f64>()
+            .recip()
+    }
+
+    /// Search for a keypair whose pubkey matches
+    pub fn grind_keypair(&self) -> Result<Keypair, GrindError> {
+        self.grind(|rng| {
+            let mut secret_key = Zeroizing::new([0; Keypair::SECRET_KEY_LENGTH]);
+            rng.fill_bytes(secret_key.as_mut_slice());
+            let keypair = Keypair::new_from_array(*secret_key);
+            self.is_match(&keypair.pubkey()).then_some(keypair)
+        })
+    }
+
+    /// Search for a seed such that `Pubkey::create_with_seed(base, seed,
+    /// owner)` matches. Returns the seed, of [`GRIND_SEED_LENGTH`] base58
+    /// characters, and the address.
+    pub fn grind_seed(
+        &self,
+        base: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<(String, Pubkey), GrindError> {
+        // Fail early for owners that can't derive addresses
+        Pubkey::create_with_seed(base, "", owner)?;
+
+        self.grind(|rng| {
+            let seed: String = (0..GRIND_SEED_LENGTH)
+                .map(|_| char::from(BASE58_ALPHABET[rng.gen_range(0, BASE58_ALPHABET.len())]))
+                .collect();
+            let address = Pubkey::create_with_seed(base, &seed, owner).ok()?;
+            self.is_match(&address).then_some((seed, address))
+        })
+    }
+
+    fn is_match(&self, pubkey: &Pubkey) -> bool {
+        let address = pubkey.to_string();
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(&address))
+    }
+
+    /// Run `attempt` on every thread until one of them returns a match
+    fn grind<T: Send>(
+        &self,
+        attempt: impl Fn(&mut StdRng) -> Option<T> + Sync,
+    ) -> Result<T, GrindError> {
+        let start = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let done = AtomicBool::new(false);
+        let is_cancelled = || {
+            self.cancel
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        };
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..self.num_threads.get() {
+                let sender = sender.clone();
+                let (attempt, attempts, done) = (&attempt, &attempts, &done);
+                scope.spawn(move || {
+                    // StdRng is a CSPRNG, seeded from the OS once per thread
+                    // rather than reading from the OS for every attempt
+                    let Ok(mut rng) = StdRng::from_rng(OsRng) else {
+                        return;
+                    };
+                    let mut batch: u64 = 0;
+                    while !done.load(Ordering::Relaxed) && !is_cancelled() {
+                        let found = attempt(&mut rng);
+                        batch = batch.saturating_add(1);
+                        if found.is_some() || batch == ATTEMPTS_BATCH {
+                            attempts.fetch_add(batch, Ordering::Relaxed);
+                            batch = 0;
+                        }
+                        if let Some(found) = found {
+                            let _ = sender.send(found);
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            let interval = self
+                .progress
+                .map_or(POLL_INTERVAL, |(interval, _)| interval.min(POLL_INTERVAL));
+            let mut last_progress = start;
+            let result = loop {
+                match receiver.recv_timeout(interval) {
+                    Ok(found) => break Ok(found),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break Err(GrindError::Cancelled),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        if is_cancelled() {
+                            break Err(GrindError::Cancelled);
+                        }
+                        if let Some((interval, callback)) = self.progress {
+                            if last_progress.elapsed() >= interval {
+                                last_progress = Instant::now();
+                                callback(&GrindProgress {
+                                    attempts: attempts.load(Ordering::Relaxed),
+                                    elapsed: start.elapsed(),
+                                    expected_attempts: self.expected_attempts(),
+                                });
+                            }
+                        }
+                    }
+                }
+            };
+            done.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::Mutex};
+
+    #[test]
+    fn test_grind_pattern() {
+        assert_eq!(
+            GrindPattern::new("", "", false),
+            Err(GrindError::EmptyPattern)
+        );
+        assert_eq!(
+            GrindPattern::prefix(&"1".repeat(45), false),
+            Err(GrindError::PatternTooLong(45))
+        );
+        assert_eq!(
+            GrindPattern::prefix("Sun0", false),
+            Err(GrindError::InvalidCharacter('0'))
+        );
+        // 'l' is not in the alphabet, but 'L' is
+        assert_eq!(
+            GrindPattern::suffix("l", false),
+            Err(GrindError::InvalidCharacter('l'))
+        );
+        assert!(GrindPattern::suffix("l", true).is_ok());
+
+        let pattern = GrindPattern::new("Sun", "ana", false).unwrap();
+        assert!(pattern.matches("Sun1111ana"));
+        assert!(!pattern.matches("sun1111ana"));
+        assert!(!pattern.matches("Suna"));
+        assert!(!pattern.matches("Sun"));
+        let pattern = GrindPattern::new("Sun", "ana", true).unwrap();
+        assert!(pattern.matches("sUN1111AnA"));
+        assert!(!pattern.matches("sUN1111AnB"));
+    }
+
+    #[test]
+    fn test_expected_attempts() {
+        let exact = GrindPattern::prefix("A", false).unwrap();
+        assert_eq!(exact.expected_attempts(), 58.0);
+        let ignore_case = GrindPattern::prefix("a", true).unwrap();
+        assert_eq!(ignore_case.expected_attempts(), 29.0);
+        // Digits and letters with a single case match a single character
+        let digits = GrindPattern::suffix("1o", true).unwrap();
+        assert_eq!(digits.expected_attempts(), 58.0 * 58.0);
+        let pattern = GrindPattern::new("ab", "c", true).unwrap();
+        assert_eq!(pattern.expected_attempts(), 29.0 * 29.0 * 29.0);
+
+        let expected_attempts = Grinder::new(exact).pattern(ignore_case).expected_attempts();
+        assert!((expected_attempts - 58.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grind_keypair() {
+        let keypair = Grinder::new(GrindPattern::suffix("x", true).unwrap())
+            .pattern(GrindPattern::suffix("y", false).unwrap())
+            .num_threads(NonZeroUsize::new(2).unwrap())
+            .grind_keypair()
+            .unwrap();
+        let address = keypair.pubkey().to_string();
+        assert!(address.ends_with(['x', 'X', 'y']), "{address}");
+    }
+
+    #[test]
+    fn test_grind_seed() {
+        let base = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let pattern = GrindPattern::prefix("A", false).unwrap();
+        let (seed, address) = Grinder::new(pattern).grind_seed(&base, &owner).unwrap();
+        assert_eq!(seed.len(), GRIND_SEED_LENGTH);
+        assert_eq!(Pubkey::create_with_seed(&base, &seed, &owner), Ok(address));
+        assert!(address.to_string().starts_with('A'));
+
+        let mut pda_owner = [0; 32];
+        pda_owner[11..].copy_from_slice(b"ProgramDerivedAddress");
+        assert_eq!(
+            Grinder::new(GrindPattern::prefix("A", false).unwrap())
+                .grind_seed(&base, &Pubkey::from(pda_owner)),
+            Err(GrindError::Pubkey(PubkeyError::IllegalOwner))
+        );
+    }
+
+    #[test]
+    fn test_grind_cancel() {
+        let cancel = AtomicBool::new(false);
+        let progress = Mutex::new(Vec::new());
+        let callback = |p: &GrindProgress| {
+            progress.lock().unwrap().push(*p);
+            cancel.store(true, Ordering::Relaxed);
+        };
+        // Practically impossible to find
+        let pattern = GrindPattern::suffix("zzzzzzzzzzzz", false).unwrap();
+        assert_eq!(
+            Grinder::new(pattern)
+                .num_threads(NonZeroUsize::new(2).unwrap())
+                .on_progress(Duration::from_millis(10), &callback)
+                .cancel_flag(&cancel)
+                .grind_keypair()
+                .unwrap_err(),
+            GrindError::Cancelled
+        );
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 1);
+        assert!((progress[0].expected_attempts / 58f64.powi(12) - 1.0).abs() < 1e-9);
+
+        // A zero interval is raised to the minimum rather than busy looping
+        let cancel = AtomicBool::new(false);
+        let progress = Mutex::new(Vec::new());
+        let callback = |p: &GrindProgress| {
+            let mut progress = progress.lock().unwrap();
+            progress.push(p.elapsed);
+            if progress.len() == 3 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+        assert_eq!(
+            Grinder::new(GrindPattern::suffix("zzzzzzzzzzzz", false).unwrap())
+                .num_threads(NonZeroUsize::new(1).unwrap())
+                .on_progress(Duration::ZERO, &callback)
+                .cancel_flag(&cancel)
+                .grind_keypair()
+                .unwrap_err(),
+            GrindError::Cancelled
+        );
+        let progress = progress.into_inner().unwrap();
+        assert!(progress
+            .windows(2)
+            .all(|elapsed| elapsed[1] - elapsed[0] >= MIN_PROGRESS_INTERVAL / 2));
+
+        // A search that is cancelled before it starts
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            Grinder::new(GrindPattern::suffix("zzzzzzzzzzzz", false).unwrap())
+                .cancel_flag(&cancel)
+                .grind_seed(&Pubkey::new_unique(), &Pubkey::new_unique()),
+            Err(GrindError::Cancelled)
+        );
+    }
+}
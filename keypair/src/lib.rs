@@ -11,13 +11,21 @@ use {
     solana_signer::{EncodableKey, EncodableKeypair, Signer, SignerError},
     std::{
         error,
+        fmt::Write as _,
         io::{Read, Write},
         path::Path,
     },
+    zeroize::Zeroizing,
 };
 
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+#[cfg(feature = "grind")]
+pub mod grind;
 #[cfg(feature = "seed-derivable")]
 pub mod seed_derivable;
+#[cfg(feature = "shamir")]
+pub mod shamir;
 pub mod signable;
 
 /// A vanilla Ed25519 key pair
@@ -51,8 +59,9 @@ impl Keypair {
 
     /// Constructs a new `Keypair` using secret key bytes
     pub fn new_from_array(secret_key: [u8; 32]) -> Self {
+        let secret_key = Zeroizing::new(secret_key);
         // unwrap is safe because the only error condition is an incorrect length
-        let secret = ed25519_dalek::SecretKey::from_bytes(&secret_key).unwrap();
+        let secret = ed25519_dalek::SecretKey::from_bytes(secret_key.as_slice()).unwrap();
         let public = ed25519_dalek::PublicKey::from(&secret);
         Self(ed25519_dalek::Keypair { secret, public })
     }
@@ -68,18 +77,24 @@ impl Keypair {
         self.0.to_bytes()
     }
 
+    /// Returns this `Keypair` as a byte array which is zeroized on drop
+    pub fn to_zeroizing_bytes(&self) -> Zeroizing<[u8; KEYPAIR_LENGTH]> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+
     /// Recovers a `Keypair` from a base58-encoded string
     pub fn from_base58_string(s: &str) -> Self {
-        let mut buf = [0u8; ed25519_dalek::KEYPAIR_LENGTH];
+        let mut buf = Zeroizing::new([0u8; ed25519_dalek::KEYPAIR_LENGTH]);
         five8::decode_64(s, &mut buf).unwrap();
         Self::try_from(&buf[..]).unwrap()
     }
 
-    /// Returns this `Keypair` as a base58-encoded string
-    pub fn to_base58_string(&self) -> String {
-        let mut out = [0u8; five8::BASE58_ENCODED_64_MAX_LEN];
-        let len = five8::encode_64(&self.0.to_bytes(), &mut out);
-        unsafe { String::from_utf8_unchecked(out[..len as usize].to_vec()) }
+    /// Returns this `Keypair` as a base58-encoded string which is zeroized on
+    /// drop
+    pub fn to_base58_string(&self) -> Zeroizing<String> {
+        let mut out = Zeroizing::new([0u8; five8::BASE58_ENCODED_64_MAX_LEN]);
+        let len = five8::encode_64(&self.to_zeroizing_bytes(), &mut out);
+        Zeroizing::new(unsafe { String::from_utf8_unchecked(out[..len as usize].to_vec()) })
     }
 
     /// Gets this `Keypair`'s SecretKey
@@ -210,7 +225,15 @@ impl EncodableKey for Keypair {
         read_keypair(reader)
     }
 
-    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>> {
+    #[cfg(feature = "encryption")]
+    fn read_with_password<R: Read>(
+        reader: &mut R,
+        password: solana_signer::PasswordCallback,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        encrypted::read_keypair_with_password(reader, password)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
         write_keypair(self, writer)
     }
 }
@@ -227,7 +250,10 @@ impl EncodableKeypair for Keypair {
 
 /// Reads a JSON-encoded `Keypair` from a `Reader` implementor
 pub fn read_keypair<R: Read>(reader: &mut R) -> Result<Keypair, Box<dyn error::Error>> {
-    let mut buffer = String::new();
+    // Reserve more than a keypair file needs, so that reading a well-formed
+    // file doesn't grow the buffer and leave partial copies of the secret in
+    // freed memory. Larger inputs still grow it.
+    let mut buffer = Zeroizing::new(String::with_capacity(1024));
     reader.read_to_string(&mut buffer)?;
     let trimmed = buffer.trim();
     if !trimmed.starts_with('[') || !trimmed.ends_with(']') {
@@ -254,7 +280,7 @@ pub fn read_keypair<R: Read>(reader: &mut R) -> Result<Keypair, Box<dyn error::E
                 ),
             )
         })?;
-    let mut out = [0u8; ed25519_dalek::KEYPAIR_LENGTH];
+    let mut out = Zeroizing::new([0u8; ed25519_dalek::KEYPAIR_LENGTH]);
     for (idx, element) in elements.into_iter().enumerate() {
         let parsed: u8 = element.parse()?;
         out[idx] = parsed;
@@ -267,37 +293,38 @@ pub fn read_keypair_file<F: AsRef<Path>>(path: F) -> Result<Keypair, Box<dyn err
     Keypair::read_from_file(path)
 }
 
-/// Writes a `Keypair` to a `Write` implementor with JSON-encoding
+/// Writes a `Keypair` to a `Write` implementor with JSON-encoding, returning
+/// the JSON which is zeroized on drop
 pub fn write_keypair<W: Write>(
     keypair: &Keypair,
     writer: &mut W,
-) -> Result<String, Box<dyn error::Error>> {
-    let keypair_bytes = keypair.0.to_bytes();
-    let mut result = Vec::with_capacity(64 * 4 + 2); // Estimate capacity: 64 numbers * (up to 3 digits + 1 comma) + 2 brackets
+) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+    let keypair_bytes = keypair.to_zeroizing_bytes();
+    // Capacity: 64 numbers * (up to 3 digits + 1 comma) + 2 brackets, which is
+    // more than the encoding can need, so writing into it doesn't reallocate.
+    let mut result = Zeroizing::new(String::with_capacity(64 * 4 + 2));
 
-    result.push(b'['); // Opening bracket
+    result.push('['); // Opening bracket
 
     for (i, &num) in keypair_bytes.iter().enumerate() {
         if i > 0 {
-            result.push(b','); // Comma separator for all elements except the first
+            result.push(','); // Comma separator for all elements except the first
         }
 
-        // Convert number to string and then to bytes
-        let num_str = num.to_string();
-        result.extend_from_slice(num_str.as_bytes());
+        // Write the decimal digits directly, without an intermediate string
+        write!(*result, "{num}")?;
     }
 
-    result.push(b']'); // Closing bracket
-    writer.write_all(&result)?;
-    let as_string = String::from_utf8(result)?;
-    Ok(as_string)
+    result.push(']'); // Closing bracket
+    writer.write_all(result.as_bytes())?;
+    Ok(result)
 }
 
 /// Writes a `Keypair` to a file with JSON-encoding
 pub fn write_keypair_file<F: AsRef<Path>>(
     keypair: &Keypair,
     outfile: F,
-) -> Result<String, Box<dyn error::Error>> {
+) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
     keypair.write_to_file(outfile)
 }
 
@@ -323,6 +350,23 @@ pub fn keypair_from_seed_phrase_and_passphrase(
     ))
 }
 
+/// Constructs a `Keypair` from a BIP-39 seed phrase and passphrase, rejecting
+/// phrases with unknown words, the wrong number of words or a bad checksum
+#[cfg(feature = "bip39")]
+pub fn keypair_from_seed_phrase_and_passphrase_checked(
+    seed_phrase: &str,
+    passphrase: &str,
+    language: solana_seed_phrase::mnemonic::Language,
+) -> Result<Keypair, Box<dyn std::error::Error>> {
+    keypair_from_seed(
+        &solana_seed_phrase::mnemonic::generate_seed_from_seed_phrase_and_passphrase_checked(
+            seed_phrase,
+            passphrase,
+            language,
+        )?,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -490,4 +534,62 @@ mod tests {
             keypair_from_seed_phrase_and_passphrase(mnemonic.phrase(), passphrase).unwrap();
         assert_eq!(keypair.pubkey(), expected_keypair.pubkey());
     }
+
+    #[test]
+    fn test_keypair_is_zeroized_on_drop() {
+        let keypair = Keypair::new();
+        let secret = *keypair.secret_bytes();
+        let mut keypair = std::mem::MaybeUninit::new(keypair);
+        // SAFETY: the keypair is initialized and is not used after it's dropped,
+        // while its memory stays owned by the `MaybeUninit`
+        let bytes = unsafe {
+            keypair.assume_init_drop();
+            std::slice::from_raw_parts(
+                keypair.as_ptr() as *const u8,
+                std::mem::size_of::<Keypair>(),
+            )
+        };
+        assert!(!bytes.windows(secret.len()).any(|window| window == secret));
+    }
+
+    #[test]
+    fn test_keypair_bytes_are_zeroizing() {
+        let keypair = Keypair::new();
+        let mut bytes = keypair.to_zeroizing_bytes();
+        assert_eq!(*bytes, keypair.to_bytes());
+        zeroize::Zeroize::zeroize(&mut bytes);
+        assert_eq!(*bytes, [0; KEYPAIR_LENGTH]);
+    }
+
+    #[test]
+    fn test_keypair_from_seed_phrase_and_passphrase_checked() {
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        let passphrase = "42";
+        let keypair = keypair_from_seed_phrase_and_passphrase_checked(
+            mnemonic.phrase(),
+            passphrase,
+            Language::English,
+        )
+        .unwrap();
+        assert_eq!(
+            keypair.pubkey(),
+            keypair_from_seed_phrase_and_passphrase(mnemonic.phrase(), passphrase)
+                .unwrap()
+                .pubkey()
+        );
+
+        let bad_checksum = ["abandon"; 12].join(" ");
+        assert!(keypair_from_seed_phrase_and_passphrase_checked(
+            &bad_checksum,
+            passphrase,
+            Language::English
+        )
+        .is_err());
+        assert!(keypair_from_seed_phrase_and_passphrase_checked(
+            &format!("{} typo", mnemonic.phrase()),
+            passphrase,
+            Language::English
+        )
+        .is_err());
+    }
 }
@@ -6,6 +6,7 @@ use {
     solana_derivation_path::DerivationPath,
     solana_seed_derivable::SeedDerivable,
     std::error,
+    zeroize::Zeroize,
 };
 
 impl SeedDerivable for Keypair {
@@ -43,8 +44,9 @@ fn bip32_derived_keypair(
     seed: &[u8],
     derivation_path: DerivationPath,
 ) -> Result<Keypair, Bip32Error> {
-    let extended = ed25519_dalek_bip32::ExtendedSecretKey::from_seed(seed)
+    let mut extended = ed25519_dalek_bip32::ExtendedSecretKey::from_seed(seed)
         .and_then(|extended| extended.derive(&derivation_path))?;
+    extended.chain_code.zeroize();
     let extended_public_key = extended.public_key();
     Ok(Keypair::from(ed25519_dalek::Keypair {
         secret: extended.secret_key,
@@ -0,0 +1,678 @@
+//! Shamir secret sharing of keypair secrets and seeds.
+//!
+//! [`split_keypair`] and [`split_seed`] split a secret into `num_shares`
+//! [`Share`]s such that any `threshold` of them recover the secret with
+//! [`recover_keypair`] or [`recover_seed`], while fewer reveal nothing about
+//! it. Each byte of the secret is shared with its own random polynomial of
+//! degree `threshold - 1` over GF(2^8), using the field of AES.
+//!
+//! Before splitting, the first 4 bytes of the SHA-256 digest of the secret
+//! are appended to it. The digest is recovered along with the secret, which
+//! catches shares that are individually well formed but don't belong
+//! together.
+//!
+//! # Share format
+//!
+//! A share is printed as the base58 encoding of these bytes:
+//!
+//! | Bytes    | Field                                                        |
+//! |----------|--------------------------------------------------------------|
+//! | 1        | Format version, currently 1                                  |
+//! | 1        | Kind of secret: 0 for a keypair secret, 1 for a seed         |
+//! | 4        | Random identifier, the same for all shares of one split      |
+//! | 1        | Threshold, the number of shares needed to recover the secret |
+//! | 1        | Index of the share, the x coordinate of the points, from 1   |
+//! | variable | Share value, as long as the secret plus its 4 byte digest    |
+//! | 4        | Checksum, the first 4 bytes of SHA-256 of the fields above   |
+//!
+//! Whitespace in a printed share is ignored, so shares can be written down
+//! in groups of characters. Shares are read from and written to files
+//! through [`EncodableKey`], one share per file.
+
+use {
+    crate::Keypair,
+    core::{fmt, str::FromStr},
+    rand0_7::{rngs::OsRng, RngCore},
+    sha2::{Digest, Sha256},
+    solana_seed_phrase::SecretSeed,
+    solana_signer::EncodableKey,
+    std::{
+        error,
+        io::{Read, Write},
+    },
+    zeroize::Zeroizing,
+};
+
+/// The version of the share format written by this crate
+pub const SHARE_VERSION: u8 = 1;
+
+const DIGEST_LENGTH: usize = 4;
+const CHECKSUM_LENGTH: usize = 4;
+const IDENTIFIER_LENGTH: usize = 4;
+/// Version, kind, identifier, threshold and index
+const HEADER_LENGTH: usize = 4 + IDENTIFIER_LENGTH;
+
+/// Reasons that a secret can't be split, or shares can't be decoded or
+/// recovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShamirError {
+    /// The threshold is zero or greater than the number of shares
+    InvalidThreshold,
+    /// The secret to split is empty
+    EmptySecret,
+    /// Fewer shares than the threshold were provided
+    NotEnoughShares { threshold: u8, provided: usize },
+    /// The shares belong to different splits
+    MismatchedShares,
+    /// Two shares have the same index
+    DuplicateShareIndex(u8),
+    /// The shares hold a different kind of secret than was asked for
+    WrongSecretKind(ShareKind),
+    /// The share format version is not supported
+    UnsupportedVersion(u8),
+    /// A share is not valid base58 or its fields are out of range
+    InvalidEncoding,
+    /// A share's checksum doesn't match, usually because of a typo
+    InvalidChecksum,
+    /// The recovered secret doesn't match its digest, so at least one share
+    /// was corrupted or doesn't belong with the others
+    DigestMismatch,
+}
+
+impl error::Error for ShamirError {}
+
+impl fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidThreshold => {
+                f.write_str("threshold must be between 1 and the number of shares")
+            }
+            Self::EmptySecret => f.write_str("cannot split an empty secret"),
+            Self::NotEnoughShares {
+                threshold,
+                provided,
+            } => write!(
+                f,
+                "{threshold} shares are needed to recover the secret, only {provided} provided"
+            ),
+            Self::MismatchedShares => f.write_str("shares belong to different splits"),
+            Self::DuplicateShareIndex(index) => write!(f, "duplicate share with index {index}"),
+            Self::WrongSecretKind(kind) => write!(f, "shares hold a {kind}"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported share version {version}"),
+            Self::InvalidEncoding => f.write_str("invalid share encoding"),
+            Self::InvalidChecksum => f.write_str("invalid share checksum"),
+            Self::DigestMismatch => {
+                f.write_str("recovered secret does not match its digest: corrupted or mixed shares")
+            }
+        }
+    }
+}
+
+/// The kind of secret that a share is part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShareKind {
+    /// The 32 byte secret key of a [`Keypair`]
+    KeypairSecret,
+    /// A seed, such as one derived from a BIP-39 seed phrase
+    Seed,
+}
+
+impl ShareKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::KeypairSecret => 0,
+            Self::Seed => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::KeypairSecret),
+            1 => Some(Self::Seed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ShareKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::KeypairSecret => f.write_str("keypair secret"),
+            Self::Seed => f.write_str("seed"),
+        }
+    }
+}
+
+/// One share of a secret. The share value is zeroized on drop and redacted
+/// from `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    kind: ShareKind,
+    identifier: [u8; IDENTIFIER_LENGTH],
+    threshold: u8,
+    index: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    /// The kind of secret this share is part of
+    pub fn kind(&self) -> ShareKind {
+        self.kind
+    }
+
+    /// The random identifier shared by all shares of one split
+    pub fn identifier(&self) -> [u8; IDENTIFIER_LENGTH] {
+        self.identifier
+    }
+
+    /// The number of shares needed to recover the secret
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// The index of this share, from 1
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Encode the share in its printable form
+    pub fn to_encoded_string(&self) -> Zeroizing<String> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(
+            HEADER_LENGTH
+                .saturating_add(self.value.len())
+                .saturating_add(CHECKSUM_LENGTH),
+        ));
+        bytes.push(SHARE_VERSION);
+        bytes.push(self.kind.to_byte());
+        bytes.extend_from_slice(&self.identifier);
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.value);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        Zeroizing::new(bs58::encode(bytes.as_slice()).into_string())
+    }
+
+    /// Whether `other` belongs to the same split as this share
+    fn matches(&self, other: &Share) -> bool {
+        self.kind == other.kind
+            && self.identifier == other.identifier
+            && self.threshold == other.threshold
+            && self.value.len() == other.value.len()
+    }
+}
+
+impl FromStr for Share {
+    type Err = ShamirError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = Zeroizing::new(s.split_whitespace().collect::<String>());
+        let bytes = Zeroizing::new(
+            bs58::decode(encoded.as_str())
+                .into_vec()
+                .map_err(|_| ShamirError::InvalidEncoding)?,
+        );
+        let Some(body_length) = bytes.len().checked_sub(CHECKSUM_LENGTH) else {
+            return Err(ShamirError::InvalidEncoding);
+        };
+        let (body, expected_checksum) = bytes.split_at(body_length);
+        if checksum(body) != expected_checksum {
+            return Err(ShamirError::InvalidChecksum);
+        }
+        if body.len() <= HEADER_LENGTH.saturating_add(DIGEST_LENGTH) {
+            return Err(ShamirError::InvalidEncoding);
+        }
+        let (header, value) = body.split_at(HEADER_LENGTH);
+        if header[0] != SHARE_VERSION {
+            return Err(ShamirError::UnsupportedVersion(header[0]));
+        }
+        let kind = ShareKind::from_byte(header[1]).ok_or(ShamirError::InvalidEncoding)?;
+        let identifier = header[2..6].try_into().unwrap();
+        let threshold = header[6];
+        let index = header[7];
+        if threshold == 0 || index == 0 {
+            return Err(ShamirError::InvalidEncoding);
+        }
+        Ok(Self {
+            kind,
+            identifier,
+            threshold,
+            index,
+            value: Zeroizing::new(value.to_vec()),
+        })
+    }
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("kind", &self.kind)
+            .field("identifier", &self.identifier)
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("value", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl EncodableKey for Share {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        let mut encoded = Zeroizing::new(String::with_capacity(1024));
+        reader.read_to_string(&mut encoded)?;
+        Ok(encoded.parse()?)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+        let encoded = self.to_encoded_string();
+        writeln!(writer, "{}", encoded.as_str())?;
+        Ok(encoded)
+    }
+}
+
+/// Split `secret` into `num_shares` shares of `kind`, any `threshold` of
+/// which recover it
+pub fn split_secret(
+    kind: ShareKind,
+    secret: &[u8],
+    threshold: u8,
+    num_shares: u8,
+) -> Result<Vec<Share>, ShamirError> {
+    if threshold == 0 || threshold > num_shares {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    if secret.is_empty() {
+        return Err(ShamirError::EmptySecret);
+    }
+
+    let mut value = Zeroizing::new(Vec::with_capacity(
+        secret.len().saturating_add(DIGEST_LENGTH),
+    ));
+    value.extend_from_slice(secret);
+    value.extend_from_slice(&digest(secret));
+
+    let mut identifier = [0; IDENTIFIER_LENGTH];
+    OsRng.fill_bytes(&mut identifier);
+
+    // The random coefficients of degree 1 and up of the polynomial of each
+    // byte of the value
+    let degree = usize::from(threshold.saturating_sub(1));
+    let mut coefficients = Zeroizing::new(vec![0; value.len().saturating_mul(degree)]);
+    OsRng.fill_bytes(&mut coefficients);
+
+    Ok((1..=num_shares)
+        .map(|index| {
+            let value = value
+                .iter()
+                .enumerate()
+                .map(|(position, secret_byte)| {
+                    let start = position.saturating_mul(degree);
+                    let coefficients = &coefficients[start..start.saturating_add(degree)];
+                    let y = coefficients
+                        .iter()
+                        .rev()
+                        .fold(0, |y, coefficient| gf_mul(y, index) ^ coefficient);
+                    gf_mul(y, index) ^ secret_byte
+                })
+                .collect();
+            Share {
+                kind,
+                identifier,
+                threshold,
+                index,
+                value: Zeroizing::new(value),
+            }
+        })
+        .collect())
+}
+
+/// Recover a secret from at least `threshold` of its shares. The kind of the
+/// secret is the [`Share::kind`] of the shares.
+pub fn recover_secret(shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, ShamirError> {
+    let first = shares.first().ok_or(ShamirError::NotEnoughShares {
+        threshold: 1,
+        provided: 0,
+    })?;
+    for (position, share) in shares.iter().enumerate() {
+        if !first.matches(share) {
+            return Err(ShamirError::MismatchedShares);
+        }
+        if shares[..position]
+            .iter()
+            .any(|other| other.index == share.index)
+        {
+            return Err(ShamirError::DuplicateShareIndex(share.index));
+        }
+    }
+    let threshold = first.threshold;
+    if shares.len() < usize::from(threshold) {
+        return Err(ShamirError::NotEnoughShares {
+            threshold,
+            provided: shares.len(),
+        });
+    }
+    let shares = &shares[..usize::from(threshold)];
+
+    // Lagrange basis polynomials of the share indexes, evaluated at 0
+    let basis: Vec<u8> = shares
+        .iter()
+        .map(|share| {
+            shares
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |product, other| {
+                    gf_mul(
+                        product,
+                        gf_mul(other.index, gf_inv(other.index ^ share.index)),
+                    )
+                })
+        })
+        .collect();
+
+    let mut value = Zeroizing::new(vec![0; first.value.len()]);
+    for (share, basis) in shares.iter().zip(basis) {
+        for (byte, y) in value.iter_mut().zip(share.value.iter()) {
+            *byte ^= gf_mul(*y, basis);
+        }
+    }
+
+    let secret_length = value.len().saturating_sub(DIGEST_LENGTH);
+    let (secret, expected_digest) = value.split_at(secret_length);
+    if digest(secret) != expected_digest {
+        return Err(ShamirError::DigestMismatch);
+    }
+    value.truncate(secret_length);
+    Ok(value)
+}
+
+/// Split the secret key of `keypair` into `num_shares` shares, any
+/// `threshold` of which recover it
+pub fn split_keypair(
+    keypair: &Keypair,
+    threshold: u8,
+    num_shares: u8,
+) -> Result<Vec<Share>, ShamirError> {
+    split_secret(
+        ShareKind::KeypairSecret,
+        keypair.secret_bytes(),
+        threshold,
+        num_shares,
+    )
+}
+
+/// Recover a keypair from at least `threshold` of the shares of its secret
+/// key
+pub fn recover_keypair(shares: &[Share]) -> Result<Keypair, ShamirError> {
+    check_kind(shares, ShareKind::KeypairSecret)?;
+    let secret = recover_secret(shares)?;
+    let secret_key = Zeroizing::new(
+        <[u8; Keypair::SECRET_KEY_LENGTH]>::try_from(secret.as_slice())
+            .map_err(|_| ShamirError::InvalidEncoding)?,
+    );
+    Ok(Keypair::new_from_array(*secret_key))
+}
+
+/// Split `seed` into `num_shares` shares, any `threshold` of which recover
+/// it
+pub fn split_seed(
+    seed: &SecretSeed,
+    threshold: u8,
+    num_shares: u8,
+) -> Result<Vec<Share>, ShamirError> {
+    split_secret(ShareKind::Seed, seed.expose_secret(), threshold, num_shares)
+}
+
+/// Recover a seed from at least `threshold` of its shares
+pub fn recover_seed(shares: &[Share]) -> Result<SecretSeed, ShamirError> {
+    check_kind(shares, ShareKind::Seed)?;
+    recover_secret(shares).map(SecretSeed::from)
+}
+
+fn check_kind(shares: &[Share], kind: ShareKind) -> Result<(), ShamirError> {
+    match shares.first() {
+        Some(share) if share.kind != kind => Err(ShamirError::WrongSecretKind(share.kind)),
+        _ => Ok(()),
+    }
+}
+
+fn digest(secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    Sha256::digest(secret)[..DIGEST_LENGTH].try_into().unwrap()
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    Sha256::digest(bytes)[..CHECKSUM_LENGTH].try_into().unwrap()
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without
+/// branches or table lookups that depend on the operands
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = a >> 7;
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(carry));
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8), computed as a^254. Maps 0 to 0.
+fn gf_inv(a: u8) -> u8 {
+    let mut square = a;
+    let mut inverse = 1;
+    for _ in 0..7 {
+        square = gf_mul(square, square);
+        inverse = gf_mul(inverse, square);
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_signer::Signer};
+
+    #[test]
+    fn test_gf_arithmetic() {
+        // Example from FIPS 197
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_inv(0x53), 0xca);
+        assert_eq!(gf_inv(0), 0);
+        for a in 1..=u8::MAX {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+            assert_eq!(gf_mul(a, 1), a);
+        }
+    }
+
+    #[test]
+    fn test_split_recover_keypair() {
+        let keypair = Keypair::new();
+        for (threshold, num_shares) in [(1, 1), (1, 3), (2, 3), (3, 5), (5, 5)] {
+            let shares = split_keypair(&keypair, threshold, num_shares).unwrap();
+            assert_eq!(shares.len(), usize::from(num_shares));
+
+            // Every subset of `threshold` shares recovers the keypair
+            for mask in 0u32..1 << num_shares {
+                if mask.count_ones() != u32::from(threshold) {
+                    continue;
+                }
+                let subset: Vec<_> = shares
+                    .iter()
+                    .filter(|share| mask & (1 << (share.index() - 1)) != 0)
+                    .cloned()
+                    .collect();
+                assert_eq!(recover_keypair(&subset).unwrap(), keypair);
+            }
+
+            // So do all of them
+            assert_eq!(recover_keypair(&shares).unwrap().pubkey(), keypair.pubkey());
+
+            if threshold > 1 {
+                let provided = usize::from(threshold - 1);
+                assert_eq!(
+                    recover_keypair(&shares[..provided]).unwrap_err(),
+                    ShamirError::NotEnoughShares {
+                        threshold,
+                        provided
+                    }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_recover_seed() {
+        let seed = SecretSeed::from_seed_phrase_and_passphrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon about",
+            "TREZOR",
+        );
+        let shares = split_seed(&seed, 3, 7).unwrap();
+        assert_eq!(recover_seed(&shares[2..5]).unwrap(), seed);
+        assert_eq!(recover_seed(&shares[4..]).unwrap(), seed);
+        assert_eq!(
+            recover_keypair(&shares).unwrap_err(),
+            ShamirError::WrongSecretKind(ShareKind::Seed)
+        );
+    }
+
+    #[test]
+    fn test_invalid_split() {
+        let keypair = Keypair::new();
+        assert_eq!(
+            split_keypair(&keypair, 0, 3).unwrap_err(),
+            ShamirError::InvalidThreshold
+        );
+        assert_eq!(
+            split_keypair(&keypair, 4, 3).unwrap_err(),
+            ShamirError::InvalidThreshold
+        );
+        assert_eq!(
+            split_secret(ShareKind::Seed, &[], 2, 3).unwrap_err(),
+            ShamirError::EmptySecret
+        );
+        assert_eq!(
+            split_keypair(&keypair, u8::MAX, u8::MAX).unwrap().len(),
+            255
+        );
+    }
+
+    #[test]
+    fn test_recover_invalid_shares() {
+        let keypair = Keypair::new();
+        let shares = split_keypair(&keypair, 2, 3).unwrap();
+        let other_shares = split_keypair(&keypair, 2, 3).unwrap();
+
+        assert_eq!(
+            recover_secret(&[]).unwrap_err(),
+            ShamirError::NotEnoughShares {
+                threshold: 1,
+                provided: 0
+            }
+        );
+        assert_eq!(
+            recover_keypair(&[shares[0].clone(), other_shares[1].clone()]).unwrap_err(),
+            ShamirError::MismatchedShares
+        );
+        assert_eq!(
+            recover_keypair(&[shares[1].clone(), shares[1].clone()]).unwrap_err(),
+            ShamirError::DuplicateShareIndex(2)
+        );
+
+        // A share from another split with the same identifier
+        let mut impostor = other_shares[1].clone();
+        impostor.identifier = shares[0].identifier;
+        assert_eq!(
+            recover_keypair(&[shares[0].clone(), impostor]).unwrap_err(),
+            ShamirError::DigestMismatch
+        );
+
+        let mut corrupted = shares[2].clone();
+        corrupted.value[5] ^= 1;
+        assert_eq!(
+            recover_keypair(&[corrupted, shares[0].clone()]).unwrap_err(),
+            ShamirError::DigestMismatch
+        );
+    }
+
+    #[test]
+    fn test_share_encoding() {
+        let keypair = Keypair::new();
+        let shares = split_keypair(&keypair, 2, 3).unwrap();
+        for share in &shares {
+            let encoded = share.to_encoded_string();
+            assert_eq!(&encoded.parse::<Share>().unwrap(), share);
+
+            // Whitespace is ignored
+            let grouped: String = encoded
+                .chars()
+                .enumerate()
+                .flat_map(|(i, c)| (i % 5 == 0).then_some(' ').into_iter().chain([c]))
+                .collect();
+            assert_eq!(&format!("\n{grouped}\n").parse::<Share>().unwrap(), share);
+
+            // A typo is caught by the checksum
+            let mut typo = encoded.as_bytes().to_vec();
+            typo[10] = if typo[10] == b'2' { b'3' } else { b'2' };
+            assert_eq!(
+                String::from_utf8(typo).unwrap().parse::<Share>(),
+                Err(ShamirError::InvalidChecksum)
+            );
+        }
+        assert!(!format!("{:?}", shares[0]).contains(&format!("{:?}", shares[0].value)));
+
+        let encode = |bytes: &[u8]| {
+            let mut bytes = bytes.to_vec();
+            bytes.extend_from_slice(&checksum(&bytes));
+            bs58::encode(bytes).into_string()
+        };
+        let share = [1, 0, 1, 2, 3, 4, 2, 1, 5, 6, 7, 8, 9];
+        assert!(encode(&share).parse::<Share>().is_ok());
+        let mut version = share;
+        version[0] = 2;
+        assert_eq!(
+            encode(&version).parse::<Share>(),
+            Err(ShamirError::UnsupportedVersion(2))
+        );
+        let mut kind = share;
+        kind[1] = 2;
+        assert_eq!(
+            encode(&kind).parse::<Share>(),
+            Err(ShamirError::InvalidEncoding)
+        );
+        let mut index = share;
+        index[7] = 0;
+        assert_eq!(
+            encode(&index).parse::<Share>(),
+            Err(ShamirError::InvalidEncoding)
+        );
+        assert_eq!(
+            encode(&share[..12]).parse::<Share>(),
+            Err(ShamirError::InvalidEncoding)
+        );
+        assert_eq!("0OIl".parse::<Share>(), Err(ShamirError::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_share_files() {
+        let keypair = Keypair::new();
+        let shares = split_keypair(&keypair, 2, 3).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = shares
+            .iter()
+            .map(|share| {
+                let path = dir.path().join(format!("share-{}", share.index()));
+                let written = share.write_to_file(&path).unwrap();
+                assert_eq!(written, share.to_encoded_string());
+                path
+            })
+            .collect();
+
+        let read: Vec<_> = paths[1..]
+            .iter()
+            .map(|path| Share::read_from_file(path).unwrap())
+            .collect();
+        assert_eq!(recover_keypair(&read).unwrap(), keypair);
+
+        std::fs::write(&paths[0], "not a share").unwrap();
+        assert!(Share::read_from_file(&paths[0]).is_err());
+    }
+}
@@ -21,7 +21,10 @@ use solana_frozen_abi_macro::{frozen_abi, AbiExample};
 use wasm_bindgen::prelude::wasm_bindgen;
 use {
     crate::{
-        compiled_instruction::CompiledInstruction, compiled_keys::CompiledKeys, MessageHeader,
+        compiled_instruction::CompiledInstruction,
+        compiled_keys::CompiledKeys,
+        sanitize_report::{SanitizeLocation, SanitizeReport, SanitizeRule},
+        MessageHeader,
     },
     badchain_sdk_ids::{
         bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable, system_program, sysvar,
@@ -210,36 +213,84 @@ pub struct Message {
 
 impl Sanitize for Message {
     fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        self.sanitize_report()?;
+        self.account_keys.sanitize()?;
+        self.recent_blockhash.sanitize()?;
+        self.instructions.sanitize()?;
+        Ok(())
+    }
+}
+
+impl Message {
+    /// Sanitize the message, describing which rule failed and where.
+    ///
+    /// [`Sanitize::sanitize`] is implemented on top of this and fails with
+    /// the report's `error`. Use `sanitize` where the details aren't needed.
+    pub fn sanitize_report(&self) -> std::result::Result<(), SanitizeReport> {
+        let num_account_keys = self.account_keys.len();
         // signing area and read-only non-signing area should not overlap
         if self.header.num_required_signatures as usize
             + self.header.num_readonly_unsigned_accounts as usize
-            > self.account_keys.len()
+            > num_account_keys
         {
-            return Err(SanitizeError::IndexOutOfBounds);
+            return Err(SanitizeReport::new(
+                SanitizeRule::HeaderAccountsOverlap {
+                    num_required_signatures: self.header.num_required_signatures,
+                    num_readonly_unsigned_accounts: self.header.num_readonly_unsigned_accounts,
+                    num_static_account_keys: num_account_keys,
+                },
+                SanitizeLocation::Header,
+                SanitizeError::IndexOutOfBounds,
+            ));
         }
 
         // there should be at least 1 RW fee-payer account.
         if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
-            return Err(SanitizeError::IndexOutOfBounds);
+            return Err(SanitizeReport::new(
+                SanitizeRule::MissingWritableFeePayer {
+                    num_required_signatures: self.header.num_required_signatures,
+                    num_readonly_signed_accounts: self.header.num_readonly_signed_accounts,
+                },
+                SanitizeLocation::Header,
+                SanitizeError::IndexOutOfBounds,
+            ));
         }
 
-        for ci in &self.instructions {
-            if ci.program_id_index as usize >= self.account_keys.len() {
-                return Err(SanitizeError::IndexOutOfBounds);
+        for (instruction, ci) in self.instructions.iter().enumerate() {
+            if ci.program_id_index as usize >= num_account_keys {
+                return Err(SanitizeReport::new(
+                    SanitizeRule::ProgramIdIndexOutOfBounds {
+                        program_id_index: ci.program_id_index,
+                        num_static_account_keys: num_account_keys,
+                    },
+                    SanitizeLocation::Instruction { instruction },
+                    SanitizeError::IndexOutOfBounds,
+                ));
             }
             // A program cannot be a payer.
             if ci.program_id_index == 0 {
-                return Err(SanitizeError::IndexOutOfBounds);
+                return Err(SanitizeReport::new(
+                    SanitizeRule::ProgramIdIsFeePayer,
+                    SanitizeLocation::Instruction { instruction },
+                    SanitizeError::IndexOutOfBounds,
+                ));
             }
-            for ai in &ci.accounts {
-                if *ai as usize >= self.account_keys.len() {
-                    return Err(SanitizeError::IndexOutOfBounds);
+            for (position, ai) in ci.accounts.iter().enumerate() {
+                if *ai as usize >= num_account_keys {
+                    return Err(SanitizeReport::new(
+                        SanitizeRule::AccountIndexOutOfBounds {
+                            account_index: *ai,
+                            num_account_keys,
+                        },
+                        SanitizeLocation::InstructionAccount {
+                            instruction,
+                            position,
+                        },
+                        SanitizeError::IndexOutOfBounds,
+                    ));
                 }
             }
         }
-        self.account_keys.sanitize()?;
-        self.recent_blockhash.sanitize()?;
-        self.instructions.sanitize()?;
         Ok(())
     }
 }
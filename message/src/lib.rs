@@ -43,6 +43,7 @@ pub mod compiled_instruction;
 mod compiled_keys;
 pub mod inner_instruction;
 pub mod legacy;
+pub mod sanitize_report;
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
@@ -0,0 +1,340 @@
+//! Diagnostics for messages which fail sanitization.
+//!
+//! [`Sanitize`] only returns a bare [`SanitizeError`], which is all the
+//! runtime needs. When debugging a rejected transaction it's more useful to
+//! know which rule failed and where, so each message type also provides a
+//! `sanitize_report` method which returns a [`SanitizeReport`] on failure.
+//! Each message type's `sanitize` is implemented on top of `sanitize_report`,
+//! so the two always agree.
+//!
+//! [`Sanitize`]: solana_sanitize::Sanitize
+
+use {core::fmt, solana_sanitize::SanitizeError, std::error::Error};
+
+/// The sanitization rule that a message broke
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeRule {
+    /// The header describes more signed and readonly unsigned accounts than
+    /// there are static account keys
+    HeaderAccountsOverlap {
+        num_required_signatures: u8,
+        num_readonly_unsigned_accounts: u8,
+        num_static_account_keys: usize,
+    },
+    /// The header doesn't describe a writable, signing fee payer
+    MissingWritableFeePayer {
+        num_required_signatures: u8,
+        num_readonly_signed_accounts: u8,
+    },
+    /// An address table lookup doesn't load any accounts
+    EmptyAddressTableLookup,
+    /// The message has no static account keys
+    MissingAccountKeys,
+    /// The message loads more accounts than can be indexed by a `u8`
+    TooManyAccountKeys { num_account_keys: usize },
+    /// An instruction's program id index doesn't refer to a static account key
+    ProgramIdIndexOutOfBounds {
+        program_id_index: u8,
+        num_static_account_keys: usize,
+    },
+    /// An instruction's program id is the fee payer
+    ProgramIdIsFeePayer,
+    /// An instruction's account index doesn't refer to an account key
+    AccountIndexOutOfBounds {
+        account_index: u8,
+        num_account_keys: usize,
+    },
+}
+
+/// Where in a message a sanitization rule was broken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeLocation {
+    /// The message header
+    Header,
+    /// The list of static account keys
+    AccountKeys,
+    /// The address table lookup at index `lookup`
+    AddressTableLookup { lookup: usize },
+    /// The instruction at index `instruction`
+    Instruction { instruction: usize },
+    /// The account at index `position` of the instruction at index
+    /// `instruction`
+    InstructionAccount { instruction: usize, position: usize },
+}
+
+/// Detailed description of a sanitization failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizeReport {
+    /// The rule that was broken
+    pub rule: SanitizeRule,
+    /// Where the rule was broken
+    pub location: SanitizeLocation,
+    /// The error returned by `sanitize` for the same message
+    pub error: SanitizeError,
+}
+
+impl SanitizeReport {
+    pub(crate) fn new(
+        rule: SanitizeRule,
+        location: SanitizeLocation,
+        error: SanitizeError,
+    ) -> Self {
+        Self {
+            rule,
+            location,
+            error,
+        }
+    }
+}
+
+impl From<SanitizeReport> for SanitizeError {
+    fn from(report: SanitizeReport) -> Self {
+        report.error
+    }
+}
+
+impl Error for SanitizeReport {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for SanitizeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::HeaderAccountsOverlap {
+                num_required_signatures,
+                num_readonly_unsigned_accounts,
+                num_static_account_keys,
+            } => write!(
+                f,
+                "{num_required_signatures} required signatures and \
+                 {num_readonly_unsigned_accounts} readonly unsigned accounts exceed \
+                 {num_static_account_keys} static account keys"
+            ),
+            Self::MissingWritableFeePayer {
+                num_required_signatures,
+                num_readonly_signed_accounts,
+            } => write!(
+                f,
+                "{num_readonly_signed_accounts} readonly signed accounts leave no writable fee \
+                 payer among {num_required_signatures} required signatures"
+            ),
+            Self::EmptyAddressTableLookup => {
+                f.write_str("address table lookup does not load any accounts")
+            }
+            Self::MissingAccountKeys => f.write_str("message has no static account keys"),
+            Self::TooManyAccountKeys { num_account_keys } => write!(
+                f,
+                "{num_account_keys} account keys exceed the maximum of 256"
+            ),
+            Self::ProgramIdIndexOutOfBounds {
+                program_id_index,
+                num_static_account_keys,
+            } => write!(
+                f,
+                "program id index {program_id_index} is out of bounds for \
+                 {num_static_account_keys} static account keys"
+            ),
+            Self::ProgramIdIsFeePayer => f.write_str("program id is the fee payer"),
+            Self::AccountIndexOutOfBounds {
+                account_index,
+                num_account_keys,
+            } => write!(
+                f,
+                "account index {account_index} is out of bounds for {num_account_keys} account \
+                 keys"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SanitizeLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Header => f.write_str("header"),
+            Self::AccountKeys => f.write_str("account keys"),
+            Self::AddressTableLookup { lookup } => write!(f, "address table lookup {lookup}"),
+            Self::Instruction { instruction } => write!(f, "instruction {instruction}"),
+            Self::InstructionAccount {
+                instruction,
+                position,
+            } => write!(f, "instruction {instruction}, account position {position}"),
+        }
+    }
+}
+
+impl fmt::Display for SanitizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.error, self.location, self.rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            compiled_instruction::CompiledInstruction, legacy, v0, MessageHeader, VersionedMessage,
+        },
+        solana_pubkey::Pubkey,
+        solana_sanitize::Sanitize,
+    };
+
+    fn headers() -> impl Iterator<Item = MessageHeader> {
+        (0..4).flat_map(|num_required_signatures| {
+            (0..4).flat_map(move |num_readonly_signed_accounts| {
+                (0..3).map(move |num_readonly_unsigned_accounts| MessageHeader {
+                    num_required_signatures,
+                    num_readonly_signed_accounts,
+                    num_readonly_unsigned_accounts,
+                })
+            })
+        })
+    }
+
+    fn instructions() -> impl Iterator<Item = Vec<CompiledInstruction>> {
+        (0..5).flat_map(|program_id_index| {
+            (0..5).map(move |account_index| {
+                vec![
+                    CompiledInstruction::new_from_raw_parts(1, vec![], vec![0]),
+                    CompiledInstruction::new_from_raw_parts(
+                        program_id_index,
+                        vec![],
+                        vec![0, account_index],
+                    ),
+                ]
+            })
+        })
+    }
+
+    fn lookups() -> impl Iterator<Item = Vec<v0::MessageAddressTableLookup>> {
+        let lookup = |writable: usize, readonly: usize| v0::MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![0; writable],
+            readonly_indexes: vec![1; readonly],
+        };
+        [
+            vec![],
+            vec![lookup(1, 0)],
+            vec![lookup(0, 1), lookup(0, 0)],
+            vec![lookup(128, 127), lookup(0, 1)],
+        ]
+        .into_iter()
+    }
+
+    #[test]
+    fn test_sanitize_report_matches_sanitize() {
+        for header in headers() {
+            for num_account_keys in 0..4 {
+                let account_keys: Vec<_> = (0..num_account_keys)
+                    .map(|_| Pubkey::new_unique())
+                    .collect();
+                for instructions in instructions() {
+                    let message = legacy::Message {
+                        header,
+                        account_keys: account_keys.clone(),
+                        instructions: instructions.clone(),
+                        ..legacy::Message::default()
+                    };
+                    assert_eq!(
+                        message.sanitize_report().map_err(SanitizeError::from),
+                        message.sanitize(),
+                        "{message:?}"
+                    );
+
+                    for address_table_lookups in lookups() {
+                        let message = VersionedMessage::V0(v0::Message {
+                            header,
+                            account_keys: account_keys.clone(),
+                            instructions: instructions.clone(),
+                            address_table_lookups,
+                            ..v0::Message::default()
+                        });
+                        assert_eq!(
+                            message.sanitize_report().map_err(SanitizeError::from),
+                            message.sanitize(),
+                            "{message:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sanitize_report_location() {
+        let header = MessageHeader {
+            num_required_signatures: 1,
+            ..MessageHeader::default()
+        };
+        let message = v0::Message {
+            header,
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            instructions: vec![
+                CompiledInstruction::new_from_raw_parts(1, vec![], vec![0]),
+                CompiledInstruction::new_from_raw_parts(1, vec![], vec![0, 1, 2, 3]),
+            ],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+            ..v0::Message::default()
+        };
+        let report = message.sanitize_report().unwrap_err();
+        assert_eq!(
+            report,
+            SanitizeReport {
+                rule: SanitizeRule::AccountIndexOutOfBounds {
+                    account_index: 3,
+                    num_account_keys: 3,
+                },
+                location: SanitizeLocation::InstructionAccount {
+                    instruction: 1,
+                    position: 3,
+                },
+                error: SanitizeError::IndexOutOfBounds,
+            }
+        );
+        assert_eq!(
+            report.to_string(),
+            "index out of bounds (instruction 1, account position 3): account index 3 is out \
+             of bounds for 3 account keys"
+        );
+
+        let message = v0::Message {
+            address_table_lookups: vec![
+                v0::MessageAddressTableLookup::default(),
+                v0::MessageAddressTableLookup::default(),
+            ],
+            ..message
+        };
+        let report = message.sanitize_report().unwrap_err();
+        assert_eq!(report.rule, SanitizeRule::EmptyAddressTableLookup);
+        assert_eq!(
+            report.location,
+            SanitizeLocation::AddressTableLookup { lookup: 0 }
+        );
+        assert_eq!(report.error, SanitizeError::InvalidValue);
+
+        let message = legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 1,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: vec![Pubkey::new_unique()],
+            ..legacy::Message::default()
+        };
+        let report = message.sanitize_report().unwrap_err();
+        assert_eq!(
+            report.rule,
+            SanitizeRule::MissingWritableFeePayer {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 1,
+            }
+        );
+        assert_eq!(report.location, SanitizeLocation::Header);
+    }
+}
@@ -3,7 +3,7 @@ use solana_frozen_abi_macro::{frozen_abi, AbiEnumVisitor, AbiExample};
 use {
     crate::{
         compiled_instruction::CompiledInstruction, legacy::Message as LegacyMessage,
-        v0::MessageAddressTableLookup, MessageHeader,
+        sanitize_report::SanitizeReport, v0::MessageAddressTableLookup, MessageHeader,
     },
     solana_hash::Hash,
     solana_pubkey::Pubkey,
@@ -55,6 +55,17 @@ impl VersionedMessage {
         }
     }
 
+    /// Sanitize the message, describing which rule failed and where.
+    ///
+    /// Fails with the same error as [`VersionedMessage::sanitize`], wrapped in
+    /// a [`SanitizeReport`].
+    pub fn sanitize_report(&self) -> Result<(), SanitizeReport> {
+        match self {
+            Self::Legacy(message) => message.sanitize_report(),
+            Self::V0(message) => message.sanitize_report(),
+        }
+    }
+
     pub fn header(&self) -> &MessageHeader {
         match self {
             Self::Legacy(message) => &message.header,
@@ -18,6 +18,7 @@ use {
     crate::{
         compiled_instruction::CompiledInstruction,
         compiled_keys::{CompileError, CompiledKeys},
+        sanitize_report::{SanitizeLocation, SanitizeReport, SanitizeRule},
         AccountKeys, AddressLookupTableAccount, MessageHeader,
     },
     badchain_sdk_ids::bpf_loader_upgradeable,
@@ -102,80 +103,121 @@ pub struct Message {
 impl Message {
     /// Sanitize message fields and compiled instruction indexes
     pub fn sanitize(&self) -> Result<(), SanitizeError> {
+        self.sanitize_report().map_err(|report| report.error)
+    }
+
+    /// Sanitize the message, describing which rule failed and where.
+    ///
+    /// [`Message::sanitize`] is implemented on top of this and fails with the
+    /// report's `error`. Use `sanitize` where the details aren't needed.
+    pub fn sanitize_report(&self) -> Result<(), SanitizeReport> {
         let num_static_account_keys = self.account_keys.len();
         if usize::from(self.header.num_required_signatures)
             .saturating_add(usize::from(self.header.num_readonly_unsigned_accounts))
             > num_static_account_keys
         {
-            return Err(SanitizeError::IndexOutOfBounds);
+            return Err(SanitizeReport::new(
+                SanitizeRule::HeaderAccountsOverlap {
+                    num_required_signatures: self.header.num_required_signatures,
+                    num_readonly_unsigned_accounts: self.header.num_readonly_unsigned_accounts,
+                    num_static_account_keys,
+                },
+                SanitizeLocation::Header,
+                SanitizeError::IndexOutOfBounds,
+            ));
         }
 
         // there should be at least 1 RW fee-payer account.
         if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
-            return Err(SanitizeError::InvalidValue);
+            return Err(SanitizeReport::new(
+                SanitizeRule::MissingWritableFeePayer {
+                    num_required_signatures: self.header.num_required_signatures,
+                    num_readonly_signed_accounts: self.header.num_readonly_signed_accounts,
+                },
+                SanitizeLocation::Header,
+                SanitizeError::InvalidValue,
+            ));
         }
 
-        let num_dynamic_account_keys = {
-            let mut total_lookup_keys: usize = 0;
-            for lookup in &self.address_table_lookups {
-                let num_lookup_indexes = lookup
-                    .writable_indexes
-                    .len()
-                    .saturating_add(lookup.readonly_indexes.len());
-
-                // each lookup table must be used to load at least one account
-                if num_lookup_indexes == 0 {
-                    return Err(SanitizeError::InvalidValue);
-                }
-
-                total_lookup_keys = total_lookup_keys.saturating_add(num_lookup_indexes);
+        let mut num_dynamic_account_keys: usize = 0;
+        for (lookup_index, lookup) in self.address_table_lookups.iter().enumerate() {
+            let num_lookup_indexes = lookup
+                .writable_indexes
+                .len()
+                .saturating_add(lookup.readonly_indexes.len());
+
+            // each lookup table must be used to load at least one account
+            if num_lookup_indexes == 0 {
+                return Err(SanitizeReport::new(
+                    SanitizeRule::EmptyAddressTableLookup,
+                    SanitizeLocation::AddressTableLookup {
+                        lookup: lookup_index,
+                    },
+                    SanitizeError::InvalidValue,
+                ));
             }
-            total_lookup_keys
-        };
+            num_dynamic_account_keys = num_dynamic_account_keys.saturating_add(num_lookup_indexes);
+        }
 
         // this is redundant with the above sanitization checks which require that:
         // 1) the header describes at least 1 RW account
         // 2) the header doesn't describe more account keys than the number of account keys
         if num_static_account_keys == 0 {
-            return Err(SanitizeError::InvalidValue);
+            return Err(SanitizeReport::new(
+                SanitizeRule::MissingAccountKeys,
+                SanitizeLocation::AccountKeys,
+                SanitizeError::InvalidValue,
+            ));
         }
 
         // the combined number of static and dynamic account keys must be <= 256
         // since account indices are encoded as `u8`
         // Note that this is different from the per-transaction account load cap
         // as defined in `Bank::get_transaction_account_lock_limit`
-        let total_account_keys = num_static_account_keys.saturating_add(num_dynamic_account_keys);
-        if total_account_keys > 256 {
-            return Err(SanitizeError::IndexOutOfBounds);
+        let num_account_keys = num_static_account_keys.saturating_add(num_dynamic_account_keys);
+        if num_account_keys > 256 {
+            return Err(SanitizeReport::new(
+                SanitizeRule::TooManyAccountKeys { num_account_keys },
+                SanitizeLocation::AccountKeys,
+                SanitizeError::IndexOutOfBounds,
+            ));
         }
 
-        // `expect` is safe because of earlier check that
-        // `num_static_account_keys` is non-zero
-        let max_account_ix = total_account_keys
-            .checked_sub(1)
-            .expect("message doesn't contain any account keys");
-
-        // reject program ids loaded from lookup tables so that
-        // static analysis on program instructions can be performed
-        // without loading on-chain data from a bank
-        let max_program_id_ix =
-            // `expect` is safe because of earlier check that
-            // `num_static_account_keys` is non-zero
-            num_static_account_keys
-                .checked_sub(1)
-                .expect("message doesn't contain any static account keys");
-
-        for ci in &self.instructions {
-            if usize::from(ci.program_id_index) > max_program_id_ix {
-                return Err(SanitizeError::IndexOutOfBounds);
+        for (instruction, ci) in self.instructions.iter().enumerate() {
+            // reject program ids loaded from lookup tables so that
+            // static analysis on program instructions can be performed
+            // without loading on-chain data from a bank
+            if usize::from(ci.program_id_index) >= num_static_account_keys {
+                return Err(SanitizeReport::new(
+                    SanitizeRule::ProgramIdIndexOutOfBounds {
+                        program_id_index: ci.program_id_index,
+                        num_static_account_keys,
+                    },
+                    SanitizeLocation::Instruction { instruction },
+                    SanitizeError::IndexOutOfBounds,
+                ));
             }
             // A program cannot be a payer.
             if ci.program_id_index == 0 {
-                return Err(SanitizeError::IndexOutOfBounds);
+                return Err(SanitizeReport::new(
+                    SanitizeRule::ProgramIdIsFeePayer,
+                    SanitizeLocation::Instruction { instruction },
+                    SanitizeError::IndexOutOfBounds,
+                ));
             }
-            for ai in &ci.accounts {
-                if usize::from(*ai) > max_account_ix {
-                    return Err(SanitizeError::IndexOutOfBounds);
+            for (position, ai) in ci.accounts.iter().enumerate() {
+                if usize::from(*ai) >= num_account_keys {
+                    return Err(SanitizeReport::new(
+                        SanitizeRule::AccountIndexOutOfBounds {
+                            account_index: *ai,
+                            num_account_keys,
+                        },
+                        SanitizeLocation::InstructionAccount {
+                            instruction,
+                            position,
+                        },
+                        SanitizeError::IndexOutOfBounds,
+                    ));
                 }
             }
         }
@@ -3,6 +3,7 @@
 use {
     num_enum::{IntoPrimitive, TryFromPrimitive},
     solana_hash::Hash,
+    solana_pubkey::Pubkey,
     solana_sanitize::SanitizeError,
     solana_signature::Signature,
     solana_signer::Signer,
@@ -14,6 +15,10 @@ static_assertions::const_assert_eq!(OffchainMessage::HEADER_LEN, 17);
 static_assertions::const_assert_eq!(v0::OffchainMessage::MAX_LEN, 65515);
 #[cfg(test)]
 static_assertions::const_assert_eq!(v0::OffchainMessage::MAX_LEN_LEDGER, 1212);
+#[cfg(test)]
+static_assertions::const_assert_eq!(v1::OffchainMessage::MAX_LEN, 65450);
+#[cfg(test)]
+static_assertions::const_assert_eq!(v1::OffchainMessage::MAX_LEN_LEDGER, 1147);
 
 /// Check if given bytes contain only printable ASCII characters
 pub fn is_printable_ascii(data: &[u8]) -> bool {
@@ -30,6 +35,9 @@ pub fn is_utf8(data: &[u8]) -> bool {
     std::str::from_utf8(data).is_ok()
 }
 
+#[allow(clippy::arithmetic_side_effects)]
+pub mod siws;
+
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive, IntoPrimitive)]
 pub enum MessageFormat {
@@ -158,9 +166,217 @@ pub mod v0 {
     }
 }
 
+#[allow(clippy::arithmetic_side_effects)]
+pub mod v1 {
+    use {
+        super::{is_printable_ascii, is_utf8, MessageFormat, OffchainMessage as Base},
+        solana_hash::Hash,
+        solana_packet::PACKET_DATA_SIZE,
+        solana_pubkey::{Pubkey, PUBKEY_BYTES},
+        solana_sanitize::SanitizeError,
+        solana_sha256_hasher::Hasher,
+    };
+
+    /// OffchainMessage Version 1.
+    /// Struct always contains a non-empty valid message, signed by a non-empty
+    /// list of distinct signers, on behalf of an application domain.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct OffchainMessage {
+        application_domain: [u8; 32],
+        format: MessageFormat,
+        signers: Vec<Pubkey>,
+        message: Vec<u8>,
+    }
+
+    impl OffchainMessage {
+        // Header Length = Application Domain (32) + Message Format (1) + Signer Count (1)
+        // + Message Length (2), not counting the signers
+        pub const HEADER_LEN: usize = 36;
+        // Max number of signers of the OffchainMessage
+        pub const MAX_SIGNERS: usize = u8::MAX as usize;
+        // Max length of the OffchainMessage with a single signer
+        pub const MAX_LEN: usize = Self::max_len(1);
+        // Max Length of the OffchainMessage with a single signer supported by the Ledger
+        pub const MAX_LEN_LEDGER: usize = Self::max_len_ledger(1);
+
+        /// Max length of the OffchainMessage with `num_signers` signers
+        pub const fn max_len(num_signers: usize) -> usize {
+            (u16::MAX as usize).saturating_sub(Self::header_len(num_signers))
+        }
+
+        /// Max length of the OffchainMessage with `num_signers` signers
+        /// supported by the Ledger, which is zero when the signers alone
+        /// don't fit
+        pub const fn max_len_ledger(num_signers: usize) -> usize {
+            PACKET_DATA_SIZE.saturating_sub(Self::header_len(num_signers))
+        }
+
+        // Length of the full header, including signing domain and signers
+        const fn header_len(num_signers: usize) -> usize {
+            Base::HEADER_LEN + Self::HEADER_LEN + num_signers * PUBKEY_BYTES
+        }
+
+        /// Construct a new OffchainMessage object from the given application
+        /// domain, signers and message
+        pub fn new(
+            application_domain: [u8; 32],
+            signers: &[Pubkey],
+            message: &[u8],
+        ) -> Result<Self, SanitizeError> {
+            Self::check_signers(signers)?;
+            let format = if message.is_empty() {
+                return Err(SanitizeError::InvalidValue);
+            } else if message.len() <= Self::max_len_ledger(signers.len()) {
+                if is_printable_ascii(message) {
+                    MessageFormat::RestrictedAscii
+                } else if is_utf8(message) {
+                    MessageFormat::LimitedUtf8
+                } else {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            } else if message.len() <= Self::max_len(signers.len()) {
+                if is_utf8(message) {
+                    MessageFormat::ExtendedUtf8
+                } else {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            } else {
+                return Err(SanitizeError::ValueOutOfBounds);
+            };
+            Ok(Self {
+                application_domain,
+                format,
+                signers: signers.to_vec(),
+                message: message.to_vec(),
+            })
+        }
+
+        fn check_signers(signers: &[Pubkey]) -> Result<(), SanitizeError> {
+            if signers.is_empty() || signers.len() > Self::MAX_SIGNERS {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            for (i, signer) in signers.iter().enumerate() {
+                if signers[..i].contains(signer) {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            }
+            Ok(())
+        }
+
+        /// Serialize the message to bytes, including the full header
+        pub fn serialize(&self, data: &mut Vec<u8>) -> Result<(), SanitizeError> {
+            // invalid messages shouldn't be possible, but a quick sanity check never hurts
+            assert!(
+                !self.message.is_empty() && self.message.len() <= Self::max_len(self.signers.len())
+            );
+            data.reserve(
+                Self::HEADER_LEN
+                    .saturating_add(self.signers.len().saturating_mul(PUBKEY_BYTES))
+                    .saturating_add(self.message.len()),
+            );
+            // application domain
+            data.extend_from_slice(&self.application_domain);
+            // format
+            data.push(self.format.into());
+            // signer count
+            data.push(self.signers.len() as u8);
+            // signers
+            for signer in &self.signers {
+                data.extend_from_slice(signer.as_ref());
+            }
+            // message length
+            data.extend_from_slice(&(self.message.len() as u16).to_le_bytes());
+            // message
+            data.extend_from_slice(&self.message);
+            Ok(())
+        }
+
+        /// Deserialize the message from bytes that include a full header
+        pub fn deserialize(data: &[u8]) -> Result<Self, SanitizeError> {
+            // validate data length
+            if data.len() <= Self::HEADER_LEN + PUBKEY_BYTES
+                || data.len() > u16::MAX as usize - Base::HEADER_LEN
+            {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            // decode header
+            let application_domain: [u8; 32] = data[..32].try_into().unwrap();
+            let format =
+                MessageFormat::try_from(data[32]).map_err(|_| SanitizeError::InvalidValue)?;
+            let num_signers = data[33] as usize;
+            let signers_end = 34 + num_signers * PUBKEY_BYTES;
+            if data.len() < signers_end + 2 {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            let signers: Vec<Pubkey> = data[34..signers_end]
+                .chunks_exact(PUBKEY_BYTES)
+                .map(|signer| Pubkey::try_from(signer).unwrap())
+                .collect();
+            Self::check_signers(&signers)?;
+            let message_len =
+                u16::from_le_bytes([data[signers_end], data[signers_end + 1]]) as usize;
+            // check header
+            let message_start = signers_end + 2;
+            if message_start.saturating_add(message_len) != data.len() {
+                return Err(SanitizeError::InvalidValue);
+            }
+            let message = &data[message_start..];
+            // check format
+            let is_valid = !message.is_empty()
+                && match format {
+                    MessageFormat::RestrictedAscii => {
+                        (message.len() <= Self::max_len_ledger(num_signers))
+                            && is_printable_ascii(message)
+                    }
+                    MessageFormat::LimitedUtf8 => {
+                        (message.len() <= Self::max_len_ledger(num_signers)) && is_utf8(message)
+                    }
+                    MessageFormat::ExtendedUtf8 => {
+                        (message.len() <= Self::max_len(num_signers)) && is_utf8(message)
+                    }
+                };
+
+            if is_valid {
+                Ok(Self {
+                    application_domain,
+                    format,
+                    signers,
+                    message: message.to_vec(),
+                })
+            } else {
+                Err(SanitizeError::InvalidValue)
+            }
+        }
+
+        /// Compute the SHA256 hash of the serialized off-chain message
+        pub fn hash(serialized_message: &[u8]) -> Result<Hash, SanitizeError> {
+            let mut hasher = Hasher::default();
+            hasher.hash(serialized_message);
+            Ok(hasher.result())
+        }
+
+        pub fn get_application_domain(&self) -> &[u8; 32] {
+            &self.application_domain
+        }
+
+        pub fn get_format(&self) -> MessageFormat {
+            self.format
+        }
+
+        pub fn get_signers(&self) -> &[Pubkey] {
+            &self.signers
+        }
+
+        pub fn get_message(&self) -> &Vec<u8> {
+            &self.message
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum OffchainMessage {
     V0(v0::OffchainMessage),
+    V1(v1::OffchainMessage),
 }
 
 impl OffchainMessage {
@@ -168,7 +384,9 @@ impl OffchainMessage {
     // Header Length = Signing Domain (16) + Header Version (1)
     pub const HEADER_LEN: usize = Self::SIGNING_DOMAIN.len() + 1;
 
-    /// Construct a new OffchainMessage object from the given version and message
+    /// Construct a new OffchainMessage object from the given version and message.
+    /// Version 1 messages also need an application domain and signers, see
+    /// [`OffchainMessage::new_v1`].
     pub fn new(version: u8, message: &[u8]) -> Result<Self, SanitizeError> {
         match version {
             0 => Ok(Self::V0(v0::OffchainMessage::new(message)?)),
@@ -176,6 +394,20 @@ impl OffchainMessage {
         }
     }
 
+    /// Construct a new version 1 OffchainMessage object from the given
+    /// application domain, signers and message
+    pub fn new_v1(
+        application_domain: [u8; 32],
+        signers: &[Pubkey],
+        message: &[u8],
+    ) -> Result<Self, SanitizeError> {
+        Ok(Self::V1(v1::OffchainMessage::new(
+            application_domain,
+            signers,
+            message,
+        )?))
+    }
+
     /// Serialize the off-chain message to bytes including full header
     pub fn serialize(&self) -> Result<Vec<u8>, SanitizeError> {
         // serialize signing domain
@@ -187,6 +419,10 @@ impl OffchainMessage {
                 data.push(0);
                 msg.serialize(&mut data)?;
             }
+            Self::V1(msg) => {
+                data.push(1);
+                msg.serialize(&mut data)?;
+            }
         }
         Ok(data)
     }
@@ -200,6 +436,7 @@ impl OffchainMessage {
         let data = &data[Self::SIGNING_DOMAIN.len().saturating_add(1)..];
         match version {
             0 => Ok(Self::V0(v0::OffchainMessage::deserialize(data)?)),
+            1 => Ok(Self::V1(v1::OffchainMessage::deserialize(data)?)),
             _ => Err(SanitizeError::ValueOutOfBounds),
         }
     }
@@ -208,40 +445,106 @@ impl OffchainMessage {
     pub fn hash(&self) -> Result<Hash, SanitizeError> {
         match self {
             Self::V0(_) => v0::OffchainMessage::hash(&self.serialize()?),
+            Self::V1(_) => v1::OffchainMessage::hash(&self.serialize()?),
         }
     }
 
     pub fn get_version(&self) -> u8 {
         match self {
             Self::V0(_) => 0,
+            Self::V1(_) => 1,
+        }
+    }
+
+    /// The application domain of the message, if its version has one
+    pub fn get_application_domain(&self) -> Option<&[u8; 32]> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(msg) => Some(msg.get_application_domain()),
         }
     }
 
     pub fn get_format(&self) -> MessageFormat {
         match self {
             Self::V0(msg) => msg.get_format(),
+            Self::V1(msg) => msg.get_format(),
+        }
+    }
+
+    /// The signers of the message, which is empty if its version doesn't
+    /// list them
+    pub fn get_signers(&self) -> &[Pubkey] {
+        match self {
+            Self::V0(_) => &[],
+            Self::V1(msg) => msg.get_signers(),
         }
     }
 
     pub fn get_message(&self) -> &Vec<u8> {
         match self {
             Self::V0(msg) => msg.get_message(),
+            Self::V1(msg) => msg.get_message(),
         }
     }
 
-    /// Sign the message with provided keypair
+    fn is_signer(&self, pubkey: &Pubkey) -> bool {
+        match self {
+            Self::V0(_) => true,
+            Self::V1(msg) => msg.get_signers().contains(pubkey),
+        }
+    }
+
+    /// Sign the message with provided keypair, which must be one of the
+    /// message signers if the message lists them
     pub fn sign(&self, signer: &dyn Signer) -> Result<Signature, SanitizeError> {
+        if !self.is_signer(&signer.pubkey()) {
+            return Err(SanitizeError::InvalidValue);
+        }
         Ok(signer.sign_message(&self.serialize()?))
     }
 
+    /// Sign the message with all of its signers, given in any order. The
+    /// signatures are returned in the order of [`OffchainMessage::get_signers`].
+    pub fn sign_all(&self, signers: &[&dyn Signer]) -> Result<Vec<Signature>, SanitizeError> {
+        if self.get_signers().is_empty() {
+            return Err(SanitizeError::InvalidValue);
+        }
+        let serialized = self.serialize()?;
+        self.get_signers()
+            .iter()
+            .map(|pubkey| {
+                signers
+                    .iter()
+                    .find(|signer| signer.pubkey() == *pubkey)
+                    .map(|signer| signer.sign_message(&serialized))
+                    .ok_or(SanitizeError::InvalidValue)
+            })
+            .collect()
+    }
+
     #[cfg(feature = "verify")]
-    /// Verify that the message signature is valid for the given public key
-    pub fn verify(
-        &self,
-        signer: &solana_pubkey::Pubkey,
-        signature: &Signature,
-    ) -> Result<bool, SanitizeError> {
-        Ok(signature.verify(signer.as_ref(), &self.serialize()?))
+    /// Verify that the message signature is valid for the given public key,
+    /// which must be one of the message signers if the message lists them
+    pub fn verify(&self, signer: &Pubkey, signature: &Signature) -> Result<bool, SanitizeError> {
+        Ok(self.is_signer(signer) && signature.verify(signer.as_ref(), &self.serialize()?))
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify that the message is signed by all of its signers, with the
+    /// signatures in the order of [`OffchainMessage::get_signers`]
+    pub fn verify_all(&self, signatures: &[Signature]) -> Result<bool, SanitizeError> {
+        let signers = self.get_signers();
+        if signers.is_empty() {
+            return Err(SanitizeError::InvalidValue);
+        }
+        if signatures.len() != signers.len() {
+            return Ok(false);
+        }
+        let serialized = self.serialize()?;
+        Ok(signers
+            .iter()
+            .zip(signatures)
+            .all(|(signer, signature)| signature.verify(signer.as_ref(), &serialized)))
     }
 }
 
@@ -299,4 +602,149 @@ mod tests {
         let signature = message.sign(&keypair).unwrap();
         assert!(message.verify(&keypair.pubkey(), &signature).unwrap());
     }
+
+    #[test]
+    fn test_offchain_message_v1() {
+        let signers = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        let message = OffchainMessage::new_v1([7; 32], &signers, b"Test Message").unwrap();
+        assert_eq!(message.get_version(), 1);
+        assert_eq!(message.get_format(), MessageFormat::RestrictedAscii);
+        assert_eq!(message.get_application_domain(), Some(&[7; 32]));
+        assert_eq!(message.get_signers(), signers);
+        assert_eq!(message.get_message().as_slice(), b"Test Message");
+
+        let mut serialized = OffchainMessage::SIGNING_DOMAIN.to_vec();
+        serialized.push(1);
+        serialized.extend_from_slice(&[7; 32]);
+        serialized.extend_from_slice(&[0, 2]);
+        serialized.extend_from_slice(&[1; 32]);
+        serialized.extend_from_slice(&[2; 32]);
+        serialized.extend_from_slice(&[12, 0]);
+        serialized.extend_from_slice(b"Test Message");
+        assert_eq!(message.serialize().unwrap(), serialized);
+        assert_eq!(message, OffchainMessage::deserialize(&serialized).unwrap());
+        assert_eq!(
+            message.hash().unwrap(),
+            solana_sha256_hasher::hash(&serialized)
+        );
+
+        // The application domain and signers are part of the signed bytes
+        let other_domain = OffchainMessage::new_v1([8; 32], &signers, b"Test Message").unwrap();
+        assert_ne!(message.hash().unwrap(), other_domain.hash().unwrap());
+        let other_signers = OffchainMessage::new_v1([7; 32], &signers[..1], b"Test Message");
+        assert_ne!(
+            message.hash().unwrap(),
+            other_signers.unwrap().hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_offchain_message_v1_invalid() {
+        let signer = Pubkey::new_from_array([1; 32]);
+        assert_eq!(
+            OffchainMessage::new_v1([0; 32], &[], b"Test Message"),
+            Err(SanitizeError::ValueOutOfBounds)
+        );
+        assert_eq!(
+            OffchainMessage::new_v1([0; 32], &[signer, signer], b"Test Message"),
+            Err(SanitizeError::InvalidValue)
+        );
+        assert_eq!(
+            OffchainMessage::new_v1([0; 32], &[signer], b""),
+            Err(SanitizeError::InvalidValue)
+        );
+
+        // Longer messages no longer fit the Ledger
+        let message = vec![b'a'; v1::OffchainMessage::MAX_LEN_LEDGER];
+        let message = OffchainMessage::new_v1([0; 32], &[signer], &message).unwrap();
+        assert_eq!(message.get_format(), MessageFormat::RestrictedAscii);
+        let message = vec![b'a'; v1::OffchainMessage::MAX_LEN_LEDGER + 1];
+        let message = OffchainMessage::new_v1([0; 32], &[signer], &message).unwrap();
+        assert_eq!(message.get_format(), MessageFormat::ExtendedUtf8);
+        let message = vec![b'a'; v1::OffchainMessage::MAX_LEN + 1];
+        assert_eq!(
+            OffchainMessage::new_v1([0; 32], &[signer], &message),
+            Err(SanitizeError::ValueOutOfBounds)
+        );
+
+        let serialized = OffchainMessage::new_v1([0; 32], &[signer], b"Test Message")
+            .unwrap()
+            .serialize()
+            .unwrap();
+        // Truncated message
+        assert!(OffchainMessage::deserialize(&serialized[..serialized.len() - 1]).is_err());
+        // Signer count beyond the data
+        let mut invalid = serialized.clone();
+        invalid[OffchainMessage::HEADER_LEN + 33] = 2;
+        assert!(OffchainMessage::deserialize(&invalid).is_err());
+        // No signers
+        let mut invalid = serialized.clone();
+        invalid[OffchainMessage::HEADER_LEN + 33] = 0;
+        assert!(OffchainMessage::deserialize(&invalid).is_err());
+        // Format that doesn't match the message
+        let mut invalid = serialized;
+        invalid[OffchainMessage::HEADER_LEN + 32] = 3;
+        assert_eq!(
+            OffchainMessage::deserialize(&invalid),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_offchain_message_v1_multiple_signers() {
+        let keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let signers: Vec<Pubkey> = keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+        let message = OffchainMessage::new_v1([7; 32], &signers, b"Proposal #1").unwrap();
+
+        // Each signer signs separately
+        let signatures: Vec<Signature> = keypairs
+            .iter()
+            .map(|keypair| message.sign(keypair).unwrap())
+            .collect();
+        for (signer, signature) in signers.iter().zip(&signatures) {
+            assert!(message.verify(signer, signature).unwrap());
+        }
+        assert!(message.verify_all(&signatures).unwrap());
+
+        // Or all at once, in any order
+        let reversed: Vec<&dyn Signer> = keypairs
+            .iter()
+            .rev()
+            .map(|keypair| keypair as &dyn Signer)
+            .collect();
+        assert_eq!(message.sign_all(&reversed).unwrap(), signatures);
+
+        // Missing, misordered and foreign signatures fail
+        assert!(!message.verify_all(&signatures[..2]).unwrap());
+        let mut swapped = signatures.clone();
+        swapped.swap(0, 1);
+        assert!(!message.verify_all(&swapped).unwrap());
+        let outsider = Keypair::new();
+        assert_eq!(message.sign(&outsider), Err(SanitizeError::InvalidValue));
+        assert!(!message
+            .verify(
+                &outsider.pubkey(),
+                &outsider.sign_message(&message.serialize().unwrap())
+            )
+            .unwrap());
+        assert_eq!(
+            message.sign_all(&reversed[..2]),
+            Err(SanitizeError::InvalidValue)
+        );
+
+        // Version 0 messages don't list signers
+        let message = OffchainMessage::new(0, b"Test Message").unwrap();
+        assert!(message.get_signers().is_empty());
+        assert_eq!(
+            message.sign_all(&reversed),
+            Err(SanitizeError::InvalidValue)
+        );
+        assert_eq!(
+            message.verify_all(&signatures),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
 }
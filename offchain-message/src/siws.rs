@@ -0,0 +1,926 @@
+//! Sign-In-With-Solana messages.
+//!
+//! A [`SiwsMessage`] asks a wallet to prove the ownership of an address to
+//! a website, in the format of [CAIP-122] used by the Solana wallet standard:
+//!
+//! ```text
+//! example.com wants you to sign in with your Solana account:
+//! 9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM
+//!
+//! Sign in to Example
+//!
+//! URI: https://example.com/login
+//! Version: 1
+//! Chain ID: mainnet
+//! Nonce: 32891756
+//! Issued At: 2021-09-30T16:25:24Z
+//! Expiration Time: 2021-09-30T17:25:24Z
+//! Not Before: 2021-09-30T16:25:24Z
+//! Request ID: 7a3c
+//! Resources:
+//! - https://example.com/terms
+//! ```
+//!
+//! Only the domain and address are required, all other fields are optional
+//! but must appear in this order. Wallets sign the UTF-8 text of the
+//! message as is, without an off-chain message header.
+//!
+//! Parsing is strict: a message only parses if it is in the canonical form
+//! that it serializes to, so that the text a wallet signed is always
+//! exactly the text of the parsed message.
+//!
+//! [CAIP-122]: https://chainagnostic.org/CAIPs/caip-122
+
+use {
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_signer::Signer,
+    std::{error::Error, fmt, str::FromStr},
+};
+
+const PREAMBLE_SUFFIX: &str = " wants you to sign in with your Solana account:";
+const URI_TAG: &str = "URI: ";
+const VERSION_TAG: &str = "Version: ";
+const CHAIN_ID_TAG: &str = "Chain ID: ";
+const NONCE_TAG: &str = "Nonce: ";
+const ISSUED_AT_TAG: &str = "Issued At: ";
+const EXPIRATION_TIME_TAG: &str = "Expiration Time: ";
+const NOT_BEFORE_TAG: &str = "Not Before: ";
+const REQUEST_ID_TAG: &str = "Request ID: ";
+const RESOURCES_TAG: &str = "Resources:";
+const RESOURCE_PREFIX: &str = "- ";
+
+/// The only version of the message format
+pub const SIWS_VERSION: &str = "1";
+
+/// The minimum length of a nonce
+pub const MIN_NONCE_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SiwsError {
+    InvalidDomain,
+    InvalidAddress,
+    InvalidStatement,
+    InvalidUri,
+    InvalidVersion,
+    InvalidChainId,
+    InvalidNonce,
+    InvalidTimestamp,
+    InvalidRequestId,
+    InvalidResource,
+    InvalidFormat,
+    AddressMismatch,
+    DomainMismatch,
+    InvalidSignature,
+    Expired,
+    NotYetValid,
+}
+
+impl Error for SiwsError {}
+
+impl fmt::Display for SiwsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SiwsError::InvalidDomain => f.write_str("invalid domain"),
+            SiwsError::InvalidAddress => f.write_str("invalid address"),
+            SiwsError::InvalidStatement => f.write_str("invalid statement"),
+            SiwsError::InvalidUri => f.write_str("invalid URI"),
+            SiwsError::InvalidVersion => f.write_str("unsupported version"),
+            SiwsError::InvalidChainId => f.write_str("invalid chain ID"),
+            SiwsError::InvalidNonce => f.write_str("invalid nonce"),
+            SiwsError::InvalidTimestamp => f.write_str("invalid RFC 3339 timestamp"),
+            SiwsError::InvalidRequestId => f.write_str("invalid request ID"),
+            SiwsError::InvalidResource => f.write_str("invalid resource URI"),
+            SiwsError::InvalidFormat => f.write_str("malformed or non-canonical message"),
+            SiwsError::AddressMismatch => f.write_str("signer doesn't match the message address"),
+            SiwsError::DomainMismatch => f.write_str("message domain doesn't match"),
+            SiwsError::InvalidSignature => f.write_str("invalid signature"),
+            SiwsError::Expired => f.write_str("message has expired"),
+            SiwsError::NotYetValid => f.write_str("message is not valid yet"),
+        }
+    }
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn is_sub_delim(c: char) -> bool {
+    matches!(
+        c,
+        '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+    )
+}
+
+/// Check that every `%` of `s` starts a percent-encoded byte and all other
+/// characters satisfy `is_allowed`
+fn is_pct_encoded_with(s: &str, is_allowed: impl Fn(char) -> bool) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            match bytes.get(i + 1..i + 3) {
+                Some(hex) if hex.iter().all(u8::is_ascii_hexdigit) => i += 3,
+                _ => return false,
+            }
+        } else if is_allowed(bytes[i] as char) && bytes[i].is_ascii() {
+            i += 1;
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+/// `authority` of RFC 3986
+fn is_authority(s: &str) -> bool {
+    !s.is_empty()
+        && is_pct_encoded_with(s, |c| {
+            is_unreserved(c) || is_sub_delim(c) || matches!(c, ':' | '@' | '[' | ']')
+        })
+}
+
+/// `URI` of RFC 3986, with a scheme and only valid URI characters
+fn is_uri(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once(':') else {
+        return false;
+    };
+    let mut scheme_chars = scheme.chars();
+    scheme_chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && is_pct_encoded_with(rest, |c| {
+            is_unreserved(c)
+                || is_sub_delim(c)
+                || matches!(c, ':' | '/' | '?' | '#' | '[' | ']' | '@')
+        })
+}
+
+/// `*pchar` of RFC 3986
+fn is_pchars(s: &str) -> bool {
+    is_pct_encoded_with(s, |c| {
+        is_unreserved(c) || is_sub_delim(c) || matches!(c, ':' | '@')
+    })
+}
+
+/// A CAIP-2 chain ID like `solana:mainnet`, or a bare cluster name like
+/// `mainnet`
+fn is_chain_id(s: &str) -> bool {
+    !s.is_empty()
+        && s.split(':').count() <= 2
+        && s.split(':').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        })
+}
+
+/// A statement is a single line of text, that can't be mistaken for a field
+fn is_statement(s: &str) -> bool {
+    !s.is_empty()
+        && !s.chars().any(char::is_control)
+        && ![
+            URI_TAG,
+            VERSION_TAG,
+            CHAIN_ID_TAG,
+            NONCE_TAG,
+            ISSUED_AT_TAG,
+            EXPIRATION_TIME_TAG,
+            NOT_BEFORE_TAG,
+            REQUEST_ID_TAG,
+            RESOURCES_TAG,
+        ]
+        .iter()
+        .any(|tag| s.starts_with(tag))
+}
+
+fn parse_digits(s: &str) -> Option<i64> {
+    if s.is_empty() || !s.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since the Unix epoch of a date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date of the proleptic Gregorian calendar of days since the Unix epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// An RFC 3339 `date-time`, kept as written since it is part of the signed
+/// text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp {
+    text: String,
+    unix_timestamp: i64,
+}
+
+impl Timestamp {
+    /// The timestamp of `unix_timestamp` in UTC, like `2021-09-30T16:25:24Z`
+    pub fn from_unix_timestamp(unix_timestamp: i64) -> Result<Self, SiwsError> {
+        let (year, month, day) = civil_from_days(unix_timestamp.div_euclid(86400));
+        if !(0..=9999).contains(&year) {
+            return Err(SiwsError::InvalidTimestamp);
+        }
+        let seconds = unix_timestamp.rem_euclid(86400);
+        let text = format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        Ok(Self {
+            text,
+            unix_timestamp,
+        })
+    }
+
+    /// The timestamp as written in the message
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Seconds since the Unix epoch, rounded down
+    pub fn unix_timestamp(&self) -> i64 {
+        self.unix_timestamp
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = SiwsError;
+
+    /// Parse a `date-time` of RFC 3339, like `2021-09-30T16:25:24.000+02:00`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || SiwsError::InvalidTimestamp;
+        let bytes = text.as_bytes();
+        if bytes.len() < 20
+            || !text.is_ascii()
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !matches!(bytes[10], b'T' | b't')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(invalid());
+        }
+        let year = parse_digits(&text[0..4]).ok_or_else(invalid)?;
+        let month = parse_digits(&text[5..7]).ok_or_else(invalid)?;
+        let day = parse_digits(&text[8..10]).ok_or_else(invalid)?;
+        let hour = parse_digits(&text[11..13]).ok_or_else(invalid)?;
+        let minute = parse_digits(&text[14..16]).ok_or_else(invalid)?;
+        // Up to 60 for leap seconds
+        let second = parse_digits(&text[17..19]).ok_or_else(invalid)?;
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return Err(invalid());
+        }
+
+        let mut offset = &text[19..];
+        if let Some(fraction) = offset.strip_prefix('.') {
+            let digits = fraction
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(invalid)?;
+            if digits == 0 {
+                return Err(invalid());
+            }
+            offset = &fraction[digits..];
+        }
+        let offset_seconds = match offset {
+            "Z" | "z" => 0,
+            _ => {
+                let sign = match offset.as_bytes().first() {
+                    Some(b'+') => 1,
+                    Some(b'-') => -1,
+                    _ => return Err(invalid()),
+                };
+                if offset.len() != 6 || offset.as_bytes()[3] != b':' {
+                    return Err(invalid());
+                }
+                let offset_hour = parse_digits(&offset[1..3]).ok_or_else(invalid)?;
+                let offset_minute = parse_digits(&offset[4..6]).ok_or_else(invalid)?;
+                if offset_hour > 23 || offset_minute > 59 {
+                    return Err(invalid());
+                }
+                sign * (offset_hour * 3600 + offset_minute * 60)
+            }
+        };
+
+        let unix_timestamp =
+            days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+                - offset_seconds;
+        Ok(Self {
+            text: text.to_string(),
+            unix_timestamp,
+        })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A Sign-In-With-Solana message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiwsMessage {
+    domain: String,
+    address: Pubkey,
+    statement: Option<String>,
+    uri: Option<String>,
+    version: Option<String>,
+    chain_id: Option<String>,
+    nonce: Option<String>,
+    issued_at: Option<Timestamp>,
+    expiration_time: Option<Timestamp>,
+    not_before: Option<Timestamp>,
+    request_id: Option<String>,
+    resources: Vec<String>,
+}
+
+impl SiwsMessage {
+    /// Construct a message asking `address` to sign in to `domain`, an RFC
+    /// 3986 authority like `example.com` or `localhost:3000`
+    pub fn new(domain: &str, address: Pubkey) -> Result<Self, SiwsError> {
+        if !is_authority(domain) {
+            return Err(SiwsError::InvalidDomain);
+        }
+        Ok(Self {
+            domain: domain.to_string(),
+            address,
+            statement: None,
+            uri: None,
+            version: None,
+            chain_id: None,
+            nonce: None,
+            issued_at: None,
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: Vec::new(),
+        })
+    }
+
+    /// Set a human-readable statement, on a single line
+    pub fn with_statement(mut self, statement: &str) -> Result<Self, SiwsError> {
+        if !is_statement(statement) {
+            return Err(SiwsError::InvalidStatement);
+        }
+        self.statement = Some(statement.to_string());
+        Ok(self)
+    }
+
+    /// Set the URI of the resource that is the subject of the signing
+    pub fn with_uri(mut self, uri: &str) -> Result<Self, SiwsError> {
+        if !is_uri(uri) {
+            return Err(SiwsError::InvalidUri);
+        }
+        self.uri = Some(uri.to_string());
+        Ok(self)
+    }
+
+    /// Set the version of the message format, which must be [`SIWS_VERSION`]
+    pub fn with_version(mut self, version: &str) -> Result<Self, SiwsError> {
+        if version != SIWS_VERSION {
+            return Err(SiwsError::InvalidVersion);
+        }
+        self.version = Some(version.to_string());
+        Ok(self)
+    }
+
+    /// Set the chain ID, like `mainnet` or `solana:mainnet`
+    pub fn with_chain_id(mut self, chain_id: &str) -> Result<Self, SiwsError> {
+        if !is_chain_id(chain_id) {
+            return Err(SiwsError::InvalidChainId);
+        }
+        self.chain_id = Some(chain_id.to_string());
+        Ok(self)
+    }
+
+    /// Set the nonce, of at least [`MIN_NONCE_LEN`] alphanumeric characters
+    pub fn with_nonce(mut self, nonce: &str) -> Result<Self, SiwsError> {
+        if nonce.len() < MIN_NONCE_LEN || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(SiwsError::InvalidNonce);
+        }
+        self.nonce = Some(nonce.to_string());
+        Ok(self)
+    }
+
+    /// Set the time the message was issued
+    pub fn with_issued_at(mut self, issued_at: Timestamp) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    /// Set the time after which the message is no longer valid
+    pub fn with_expiration_time(mut self, expiration_time: Timestamp) -> Self {
+        self.expiration_time = Some(expiration_time);
+        self
+    }
+
+    /// Set the time before which the message is not valid yet
+    pub fn with_not_before(mut self, not_before: Timestamp) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    /// Set an identifier of the request, for the website's own use
+    pub fn with_request_id(mut self, request_id: &str) -> Result<Self, SiwsError> {
+        if !is_pchars(request_id) {
+            return Err(SiwsError::InvalidRequestId);
+        }
+        self.request_id = Some(request_id.to_string());
+        Ok(self)
+    }
+
+    /// Add the URI of a resource that the user wishes to have resolved as
+    /// part of the signing
+    pub fn with_resource(mut self, resource: &str) -> Result<Self, SiwsError> {
+        if !is_uri(resource) {
+            return Err(SiwsError::InvalidResource);
+        }
+        self.resources.push(resource.to_string());
+        Ok(self)
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    pub fn address(&self) -> &Pubkey {
+        &self.address
+    }
+
+    pub fn statement(&self) -> Option<&str> {
+        self.statement.as_deref()
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn chain_id(&self) -> Option<&str> {
+        self.chain_id.as_deref()
+    }
+
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    pub fn issued_at(&self) -> Option<&Timestamp> {
+        self.issued_at.as_ref()
+    }
+
+    pub fn expiration_time(&self) -> Option<&Timestamp> {
+        self.expiration_time.as_ref()
+    }
+
+    pub fn not_before(&self) -> Option<&Timestamp> {
+        self.not_before.as_ref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    pub fn resources(&self) -> &[String] {
+        &self.resources
+    }
+
+    /// Sign the text of the message with the keypair of its address
+    pub fn sign(&self, signer: &dyn Signer) -> Result<Signature, SiwsError> {
+        if signer.pubkey() != self.address {
+            return Err(SiwsError::AddressMismatch);
+        }
+        Ok(signer.sign_message(self.to_string().as_bytes()))
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify that the message is signed by its address, that it is for
+    /// `domain` and that it is valid at `unix_timestamp`
+    pub fn verify(
+        &self,
+        signature: &Signature,
+        domain: &str,
+        unix_timestamp: i64,
+    ) -> Result<(), SiwsError> {
+        if self.domain != domain {
+            return Err(SiwsError::DomainMismatch);
+        }
+        if let Some(expiration_time) = &self.expiration_time {
+            if unix_timestamp >= expiration_time.unix_timestamp() {
+                return Err(SiwsError::Expired);
+            }
+        }
+        if let Some(not_before) = &self.not_before {
+            if unix_timestamp < not_before.unix_timestamp() {
+                return Err(SiwsError::NotYetValid);
+            }
+        }
+        if !signature.verify(self.address.as_ref(), self.to_string().as_bytes()) {
+            return Err(SiwsError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SiwsMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{PREAMBLE_SUFFIX}\n{}", self.domain, self.address)?;
+        if let Some(statement) = &self.statement {
+            write!(f, "\n\n{statement}")?;
+        }
+
+        let fields = [
+            (URI_TAG, self.uri.as_deref()),
+            (VERSION_TAG, self.version.as_deref()),
+            (CHAIN_ID_TAG, self.chain_id.as_deref()),
+            (NONCE_TAG, self.nonce.as_deref()),
+            (
+                ISSUED_AT_TAG,
+                self.issued_at.as_ref().map(Timestamp::as_str),
+            ),
+            (
+                EXPIRATION_TIME_TAG,
+                self.expiration_time.as_ref().map(Timestamp::as_str),
+            ),
+            (
+                NOT_BEFORE_TAG,
+                self.not_before.as_ref().map(Timestamp::as_str),
+            ),
+            (REQUEST_ID_TAG, self.request_id.as_deref()),
+        ];
+        let mut separator = "\n\n";
+        for (tag, value) in fields {
+            if let Some(value) = value {
+                write!(f, "{separator}{tag}{value}")?;
+                separator = "\n";
+            }
+        }
+        if !self.resources.is_empty() {
+            write!(f, "{separator}{RESOURCES_TAG}")?;
+            for resource in &self.resources {
+                write!(f, "\n{RESOURCE_PREFIX}{resource}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiwsMessage {
+    type Err = SiwsError;
+
+    /// Parse a message in its canonical form
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let (preamble, lines) = lines.split_first().ok_or(SiwsError::InvalidFormat)?;
+        let domain = preamble
+            .strip_suffix(PREAMBLE_SUFFIX)
+            .ok_or(SiwsError::InvalidFormat)?;
+        let (address, mut lines) = lines.split_first().ok_or(SiwsError::InvalidFormat)?;
+        let address = Pubkey::from_str(address).map_err(|_| SiwsError::InvalidAddress)?;
+        let mut message = Self::new(domain, address)?;
+
+        // A blank line before the statement, and another one before the
+        // fields when there is a statement
+        if let [_, statement, rest @ ..] = lines {
+            if is_statement(statement) {
+                message = message.with_statement(statement)?;
+                lines = rest.get(1..).unwrap_or_default();
+            } else {
+                lines = &lines[1..];
+            }
+        }
+
+        let mut next_field = |tag: &str| {
+            let (line, rest) = lines.split_first()?;
+            let value = line.strip_prefix(tag)?;
+            lines = rest;
+            Some(value)
+        };
+        if let Some(uri) = next_field(URI_TAG) {
+            message = message.with_uri(uri)?;
+        }
+        if let Some(version) = next_field(VERSION_TAG) {
+            message = message.with_version(version)?;
+        }
+        if let Some(chain_id) = next_field(CHAIN_ID_TAG) {
+            message = message.with_chain_id(chain_id)?;
+        }
+        if let Some(nonce) = next_field(NONCE_TAG) {
+            message = message.with_nonce(nonce)?;
+        }
+        if let Some(issued_at) = next_field(ISSUED_AT_TAG) {
+            message = message.with_issued_at(issued_at.parse()?);
+        }
+        if let Some(expiration_time) = next_field(EXPIRATION_TIME_TAG) {
+            message = message.with_expiration_time(expiration_time.parse()?);
+        }
+        if let Some(not_before) = next_field(NOT_BEFORE_TAG) {
+            message = message.with_not_before(not_before.parse()?);
+        }
+        if let Some(request_id) = next_field(REQUEST_ID_TAG) {
+            message = message.with_request_id(request_id)?;
+        }
+        if next_field(RESOURCES_TAG).is_some_and(str::is_empty) {
+            while let Some(resource) = next_field(RESOURCE_PREFIX) {
+                message = message.with_resource(resource)?;
+            }
+        }
+
+        // Anything left over, an empty resource list or a field separator
+        // without fields makes the message non-canonical
+        if message.to_string() != text {
+            return Err(SiwsError::InvalidFormat);
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_keypair::Keypair};
+
+    const ADDRESS: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    fn full_message() -> SiwsMessage {
+        SiwsMessage::new("example.com", Pubkey::from_str(ADDRESS).unwrap())
+            .unwrap()
+            .with_statement("Sign in to Example")
+            .unwrap()
+            .with_uri("https://example.com/login")
+            .unwrap()
+            .with_version("1")
+            .unwrap()
+            .with_chain_id("mainnet")
+            .unwrap()
+            .with_nonce("32891756")
+            .unwrap()
+            .with_issued_at("2021-09-30T16:25:24Z".parse().unwrap())
+            .with_expiration_time("2021-09-30T17:25:24Z".parse().unwrap())
+            .with_not_before("2021-09-30T16:25:24Z".parse().unwrap())
+            .with_request_id("7a3c")
+            .unwrap()
+            .with_resource("https://example.com/terms")
+            .unwrap()
+            .with_resource("ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_siws_message_text() {
+        let text = "example.com wants you to sign in with your Solana account:
+9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM
+
+Sign in to Example
+
+URI: https://example.com/login
+Version: 1
+Chain ID: mainnet
+Nonce: 32891756
+Issued At: 2021-09-30T16:25:24Z
+Expiration Time: 2021-09-30T17:25:24Z
+Not Before: 2021-09-30T16:25:24Z
+Request ID: 7a3c
+Resources:
+- https://example.com/terms
+- ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/";
+        let message = full_message();
+        assert_eq!(message.to_string(), text);
+        assert_eq!(text.parse(), Ok(message));
+
+        // Only the domain and address are required
+        let address = Pubkey::from_str(ADDRESS).unwrap();
+        let minimal = SiwsMessage::new("localhost:3000", address).unwrap();
+        let text =
+            format!("localhost:3000 wants you to sign in with your Solana account:\n{ADDRESS}");
+        assert_eq!(minimal.to_string(), text);
+        assert_eq!(text.parse(), Ok(minimal.clone()));
+
+        let with_statement = minimal.clone().with_statement("Hello").unwrap();
+        assert_eq!(with_statement.to_string(), format!("{text}\n\nHello"));
+        assert_eq!(format!("{text}\n\nHello").parse(), Ok(with_statement));
+
+        let with_nonce = minimal.with_nonce("abcdefgh").unwrap();
+        assert_eq!(with_nonce.to_string(), format!("{text}\n\nNonce: abcdefgh"));
+        assert_eq!(format!("{text}\n\nNonce: abcdefgh").parse(), Ok(with_nonce));
+    }
+
+    #[test]
+    fn test_siws_message_parse_invalid() {
+        let text = full_message().to_string();
+        let invalid = [
+            // Not canonical
+            (text.replace("\n\nURI", "\nURI"), SiwsError::InvalidFormat),
+            (format!("{text}\n"), SiwsError::InvalidFormat),
+            (text.replace('\n', "\r\n"), SiwsError::InvalidFormat),
+            (
+                text.replace(
+                    "Version: 1\nChain ID: mainnet",
+                    "Chain ID: mainnet\nVersion: 1",
+                ),
+                SiwsError::InvalidFormat,
+            ),
+            // An empty resource list
+            (
+                text[..text.find("\n- ").unwrap()].to_string(),
+                SiwsError::InvalidFormat,
+            ),
+            (text.replace("Solana", "Ethereum"), SiwsError::InvalidFormat),
+            // Invalid fields
+            (
+                text.replace("example.com ", "exa mple.com "),
+                SiwsError::InvalidDomain,
+            ),
+            (text.replace(ADDRESS, "0x1234"), SiwsError::InvalidAddress),
+            (
+                text.replace("https://example.com/login", "login"),
+                SiwsError::InvalidUri,
+            ),
+            (
+                text.replace("Version: 1", "Version: 2"),
+                SiwsError::InvalidVersion,
+            ),
+            (
+                text.replace("mainnet", "main net"),
+                SiwsError::InvalidChainId,
+            ),
+            (text.replace("32891756", "3289175"), SiwsError::InvalidNonce),
+            (
+                text.replace("32891756", "3289175!"),
+                SiwsError::InvalidNonce,
+            ),
+            (
+                text.replace("T17:25", "T24:25"),
+                SiwsError::InvalidTimestamp,
+            ),
+            (text.replace("7a3c", "7a 3c"), SiwsError::InvalidRequestId),
+            (
+                text.replace("https://example.com/terms", "https://example.com/%zz"),
+                SiwsError::InvalidResource,
+            ),
+        ];
+        for (text, error) in invalid {
+            assert_eq!(text.parse::<SiwsMessage>(), Err(error), "{text}");
+        }
+
+        // A statement can't be mistaken for a field
+        let address = Pubkey::from_str(ADDRESS).unwrap();
+        assert_eq!(
+            SiwsMessage::new("example.com", address)
+                .unwrap()
+                .with_statement("URI: https://example.com"),
+            Err(SiwsError::InvalidStatement)
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp: Timestamp = "2021-09-30T16:25:24Z".parse().unwrap();
+        assert_eq!(timestamp.unix_timestamp(), 1633019124);
+        assert_eq!(Timestamp::from_unix_timestamp(1633019124), Ok(timestamp));
+        for equivalent in [
+            "2021-09-30t16:25:24z",
+            "2021-09-30T16:25:24.999Z",
+            "2021-09-30T18:25:24+02:00",
+            "2021-09-30T06:55:24-09:30",
+        ] {
+            let timestamp: Timestamp = equivalent.parse().unwrap();
+            assert_eq!(timestamp.unix_timestamp(), 1633019124, "{equivalent}");
+            assert_eq!(timestamp.as_str(), equivalent);
+        }
+        assert_eq!(
+            "1970-01-01T00:00:00Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .unix_timestamp(),
+            0
+        );
+        assert_eq!(
+            "1969-12-31T23:59:59Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .unix_timestamp(),
+            -1
+        );
+        assert_eq!(
+            "2024-02-29T00:00:00Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .unix_timestamp(),
+            1709164800
+        );
+        assert_eq!(
+            Timestamp::from_unix_timestamp(1709164800).unwrap().as_str(),
+            "2024-02-29T00:00:00Z"
+        );
+
+        for invalid in [
+            "",
+            "2021-09-30",
+            "2021-09-30 16:25:24Z",
+            "2021-09-30T16:25:24",
+            "2021-09-30T16:25:24.Z",
+            "2021-09-30T16:25:24+0200",
+            "2021-09-30T16:25:24+24:00",
+            "2023-02-29T16:25:24Z",
+            "2021-13-30T16:25:24Z",
+            "2021-09-31T16:25:24Z",
+            "2021-09-30T16:60:24Z",
+            "+021-09-30T16:25:24Z",
+        ] {
+            assert_eq!(
+                invalid.parse::<Timestamp>(),
+                Err(SiwsError::InvalidTimestamp),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_siws_message_sign_and_verify() {
+        let keypair = Keypair::new();
+        let message = SiwsMessage::new("example.com", keypair.pubkey())
+            .unwrap()
+            .with_nonce("32891756")
+            .unwrap()
+            .with_expiration_time(Timestamp::from_unix_timestamp(2000).unwrap())
+            .with_not_before(Timestamp::from_unix_timestamp(1000).unwrap());
+        let signature = message.sign(&keypair).unwrap();
+        assert!(signature.verify(keypair.pubkey().as_ref(), message.to_string().as_bytes()));
+        assert_eq!(message.verify(&signature, "example.com", 1000), Ok(()));
+        assert_eq!(message.verify(&signature, "example.com", 1999), Ok(()));
+
+        // The parsed message verifies as well
+        let parsed: SiwsMessage = message.to_string().parse().unwrap();
+        assert_eq!(parsed.verify(&signature, "example.com", 1500), Ok(()));
+
+        assert_eq!(
+            message.verify(&signature, "example.com", 999),
+            Err(SiwsError::NotYetValid)
+        );
+        assert_eq!(
+            message.verify(&signature, "example.com", 2000),
+            Err(SiwsError::Expired)
+        );
+        assert_eq!(
+            message.verify(&signature, "evil.com", 1500),
+            Err(SiwsError::DomainMismatch)
+        );
+        let other = message.clone().with_request_id("1").unwrap();
+        assert_eq!(
+            other.verify(&signature, "example.com", 1500),
+            Err(SiwsError::InvalidSignature)
+        );
+        assert_eq!(
+            message.sign(&Keypair::new()),
+            Err(SiwsError::AddressMismatch)
+        );
+    }
+}
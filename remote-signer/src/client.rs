@@ -0,0 +1,240 @@
+//! A `Signer` that forwards signing requests to a [`SignerServer`].
+//!
+//! [`SignerServer`]: crate::SignerServer
+
+use {
+    crate::protocol::{
+        read_frame, split_array, write_frame, Direction, ErrorCode, Hello, Session,
+        MAX_FRAME_LENGTH, REQUEST_GET_PUBKEY, REQUEST_SIGN_MESSAGE, RESPONSE_ERROR,
+        RESPONSE_PUBKEY, RESPONSE_SIGNATURE,
+    },
+    solana_keypair::Keypair,
+    solana_pubkey::Pubkey,
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    solana_signer::{Signer, SignerError},
+    std::{
+        fmt,
+        io::{self, Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        sync::Mutex,
+        time::Duration,
+    },
+};
+
+/// A bidirectional byte stream to a server
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// How a [`RemoteSigner`] authenticates itself and the server
+#[derive(Debug, Default)]
+pub struct RemoteSignerConfig {
+    /// The key the client authenticates its requests with
+    pub auth_keypair: Option<Keypair>,
+    /// The key the server must authenticate its responses with
+    pub server_pubkey: Option<Pubkey>,
+    /// How long to wait for the server to read a request or respond to it
+    pub timeout: Option<Duration>,
+}
+
+struct Connection {
+    stream: Box<dyn Stream>,
+    session: Session,
+    auth_keypair: Option<Keypair>,
+    server_pubkey: Option<Pubkey>,
+    /// Set once an I/O error leaves the stream in an unknown state
+    broken: bool,
+}
+
+impl Connection {
+    fn request(&mut self, payload: &[u8]) -> Result<Vec<u8>, SignerError> {
+        if self.broken {
+            return Err(SignerError::Connection("connection closed".to_string()));
+        }
+        let response = self.exchange(payload).map_err(|err| {
+            self.broken = true;
+            SignerError::Connection(err.to_string())
+        })?;
+        let response = self
+            .session
+            .open(Direction::Response, self.server_pubkey.as_ref(), &response)
+            .ok_or_else(|| {
+                self.broken = true;
+                SignerError::Protocol("response authentication failed".to_string())
+            })?
+            .to_vec();
+        self.session.sequence = self.session.sequence.wrapping_add(1);
+        Ok(response)
+    }
+
+    fn exchange(&mut self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let auth = self
+            .auth_keypair
+            .as_ref()
+            .map(|keypair| keypair as &dyn Signer);
+        let frame = self.session.seal(Direction::Request, auth, payload);
+        write_frame(&mut self.stream, &frame)?;
+        read_frame(&mut self.stream)
+    }
+}
+
+/// A [`Signer`] whose key is held by a [`SignerServer`], reached over a Unix
+/// socket or TCP
+///
+/// [`SignerServer`]: crate::SignerServer
+pub struct RemoteSigner {
+    pubkey: Pubkey,
+    connection: Mutex<Connection>,
+}
+
+impl fmt::Debug for RemoteSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("pubkey", &self.pubkey)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RemoteSigner {
+    /// Connect to a server listening on TCP
+    pub fn connect_tcp<A: ToSocketAddrs>(
+        addr: A,
+        config: RemoteSignerConfig,
+    ) -> Result<Self, SignerError> {
+        let stream = TcpStream::connect(addr).map_err(connection_error)?;
+        stream
+            .set_nodelay(true)
+            .and_then(|()| stream.set_read_timeout(config.timeout))
+            .and_then(|()| stream.set_write_timeout(config.timeout))
+            .map_err(connection_error)?;
+        Self::from_stream(stream, config)
+    }
+
+    /// Connect to a server listening on a Unix socket
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<std::path::Path>>(
+        path: P,
+        config: RemoteSignerConfig,
+    ) -> Result<Self, SignerError> {
+        let stream = std::os::unix::net::UnixStream::connect(path).map_err(connection_error)?;
+        stream
+            .set_read_timeout(config.timeout)
+            .and_then(|()| stream.set_write_timeout(config.timeout))
+            .map_err(connection_error)?;
+        Self::from_stream(stream, config)
+    }
+
+    /// Talk to a server over an already connected stream. The timeout of
+    /// `config` is not applied to the stream.
+    pub fn from_stream<S: Stream + 'static>(
+        mut stream: S,
+        config: RemoteSignerConfig,
+    ) -> Result<Self, SignerError> {
+        let RemoteSignerConfig {
+            auth_keypair,
+            server_pubkey,
+            timeout: _,
+        } = config;
+        let hello = Hello::new(auth_keypair.as_ref().map(Signer::pubkey));
+        write_frame(&mut stream, &hello.encode()).map_err(connection_error)?;
+        let server_hello = read_frame(&mut stream)
+            .and_then(|frame| Hello::decode(&frame))
+            .map_err(connection_error)?;
+        if server_pubkey.is_some() && server_hello.auth_pubkey != server_pubkey {
+            return Err(SignerError::Protocol(
+                "server did not authenticate with the expected key".to_string(),
+            ));
+        }
+
+        let mut connection = Connection {
+            stream: Box::new(stream),
+            session: Session {
+                client_nonce: hello.nonce,
+                server_nonce: server_hello.nonce,
+                sequence: 0,
+            },
+            auth_keypair,
+            server_pubkey: server_hello.auth_pubkey,
+            broken: false,
+        };
+        let response = connection.request(&[REQUEST_GET_PUBKEY])?;
+        let pubkey = Pubkey::try_from(parse_response(&response, RESPONSE_PUBKEY)?)
+            .map_err(|_| malformed_response())?;
+        Ok(Self {
+            pubkey,
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        // Leave room for the request tag and an authentication signature
+        if message.len() > MAX_FRAME_LENGTH.saturating_sub(1 + SIGNATURE_BYTES) {
+            return Err(SignerError::InvalidInput("message too long".to_string()));
+        }
+        let mut request = Vec::with_capacity(message.len().saturating_add(1));
+        request.push(REQUEST_SIGN_MESSAGE);
+        request.extend_from_slice(message);
+
+        let response = self
+            .connection
+            .lock()
+            .map_err(|_| SignerError::Connection("connection poisoned".to_string()))?
+            .request(&request)?;
+        let (signature, rest) =
+            split_array::<SIGNATURE_BYTES>(parse_response(&response, RESPONSE_SIGNATURE)?)
+                .map_err(|_| malformed_response())?;
+        if !rest.is_empty() {
+            return Err(malformed_response());
+        }
+        let signature = Signature::from(signature);
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Protocol(
+                "remote signer returned an invalid signature".to_string(),
+            ));
+        }
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Strip the tag of a response, turning error responses into errors
+fn parse_response(response: &[u8], expected_tag: u8) -> Result<&[u8], SignerError> {
+    match response {
+        [tag, rest @ ..] if *tag == expected_tag => Ok(rest),
+        [RESPONSE_ERROR, code, description @ ..] => {
+            let description = String::from_utf8_lossy(description);
+            Err(match ErrorCode::from_byte(*code) {
+                Some(ErrorCode::PolicyRejected) => {
+                    SignerError::Custom(format!("rejected by signer policy: {description}"))
+                }
+                Some(ErrorCode::SigningFailed) => {
+                    SignerError::Custom(format!("remote signing failed: {description}"))
+                }
+                Some(ErrorCode::Unauthorized) => {
+                    SignerError::Protocol(format!("unauthorized: {description}"))
+                }
+                Some(ErrorCode::BadRequest) | None => {
+                    SignerError::Protocol(format!("bad request: {description}"))
+                }
+            })
+        }
+        _ => Err(malformed_response()),
+    }
+}
+
+fn malformed_response() -> SignerError {
+    SignerError::Protocol("malformed response".to_string())
+}
+
+fn connection_error(err: io::Error) -> SignerError {
+    SignerError::Connection(err.to_string())
+}
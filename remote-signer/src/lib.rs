@@ -0,0 +1,295 @@
+//! Sign with a key held by another process.
+//!
+//! A [`SignerServer`] wraps any local [`Signer`] and serves signing requests
+//! over a Unix socket or TCP. A [`RemoteSigner`] connects to it and
+//! implements [`Signer`] by forwarding requests, so that hot services can
+//! sign without holding keys.
+//!
+//! The server can restrict what it signs with a policy, which sees every
+//! message along with its decoding as a transaction message. Both sides can
+//! authenticate with an ed25519 key: a client configured with the server's
+//! pubkey rejects any other server, and a server configured with allowed
+//! client pubkeys rejects any other client. A server only serves TCP on a
+//! non-loopback address if it has allowed clients. The [`protocol`] module
+//! describes the wire format.
+//!
+//! ```
+//! # use {
+//! #     solana_keypair::Keypair,
+//! #     solana_remote_signer::{RemoteSigner, RemoteSignerConfig, SignerServer},
+//! #     solana_signer::Signer,
+//! #     std::{net::TcpListener, thread},
+//! # };
+//! let keypair = Keypair::new();
+//! let pubkey = keypair.pubkey();
+//! let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+//! let addr = listener.local_addr().unwrap();
+//! thread::spawn(move || {
+//!     SignerServer::new(keypair)
+//!         .with_policy(|request| {
+//!             if request.message.starts_with(b"ok") {
+//!                 Ok(())
+//!             } else {
+//!                 Err("unexpected message".to_string())
+//!             }
+//!         })
+//!         .serve_tcp(listener)
+//! });
+//!
+//! let signer = RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()).unwrap();
+//! assert_eq!(signer.pubkey(), pubkey);
+//! assert!(signer.try_sign_message(b"ok").is_ok());
+//! assert!(signer.try_sign_message(b"not ok").is_err());
+//! ```
+//!
+//! [`Signer`]: solana_signer::Signer
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+
+mod client;
+pub mod protocol;
+mod server;
+
+pub use {
+    client::{RemoteSigner, RemoteSignerConfig, Stream},
+    server::{
+        SignRequest, SignerServer, SigningPolicy, DEFAULT_CONNECTION_TIMEOUT,
+        DEFAULT_MAX_CONNECTIONS,
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::{Message, VersionedMessage},
+        solana_pubkey::Pubkey,
+        solana_signer::{Signer, SignerError},
+        std::{
+            io,
+            net::{Shutdown, SocketAddr, TcpListener},
+            sync::{mpsc, Arc, Mutex},
+            thread,
+            time::Duration,
+        },
+    };
+
+    fn spawn_tcp_server<S: Signer + Send + Sync + 'static>(server: SignerServer<S>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve_tcp(listener));
+        addr
+    }
+
+    fn transfer_message(payer: &Pubkey, lamports: u64) -> Message {
+        let program_id = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &lamports.to_le_bytes(),
+            vec![AccountMeta::new(*payer, true)],
+        );
+        Message::new_with_blockhash(&[instruction], Some(payer), &Hash::new_unique())
+    }
+
+    #[test]
+    fn test_remote_signer_tcp() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let policy_seen = seen.clone();
+        let addr = spawn_tcp_server(SignerServer::new(keypair.insecure_clone()).with_policy(
+            move |request| {
+                let decoded = request.decoded.cloned();
+                policy_seen.lock().unwrap().push(decoded.clone());
+                // Refuse transfers of more than 100 lamports
+                let lamports = match decoded {
+                    Some(VersionedMessage::Legacy(message)) => {
+                        u64::from_le_bytes(message.instructions[0].data[..8].try_into().unwrap())
+                    }
+                    _ => 0,
+                };
+                if lamports > 100 {
+                    Err("too many lamports".to_string())
+                } else {
+                    Ok(())
+                }
+            },
+        ));
+
+        let signer = RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+        assert!(!signer.is_interactive());
+
+        let message = transfer_message(&pubkey, 10).serialize();
+        assert_eq!(
+            signer.try_sign_message(&message),
+            Ok(keypair.sign_message(&message))
+        );
+        let message = transfer_message(&pubkey, 1000).serialize();
+        assert_eq!(
+            signer.try_sign_message(&message),
+            Err(SignerError::Custom(
+                "rejected by signer policy: too many lamports".to_string()
+            ))
+        );
+        // The connection is still usable after a rejection, and messages
+        // which aren't transactions aren't decoded
+        assert_eq!(
+            signer.try_sign_message(b"hello"),
+            Ok(keypair.sign_message(b"hello"))
+        );
+        let mut trailing = transfer_message(&pubkey, 10).serialize();
+        trailing.push(0);
+        assert!(signer.try_sign_message(&trailing).is_ok());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 4);
+        assert!(seen[0].is_some() && seen[1].is_some());
+        assert!(seen[2].is_none() && seen[3].is_none());
+    }
+
+    #[test]
+    fn test_remote_signer_mutual_auth() {
+        let keypair = Keypair::new();
+        let server_auth = Keypair::new();
+        let client_auth = Keypair::new();
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let policy_clients = clients.clone();
+        let addr = spawn_tcp_server(
+            SignerServer::new(keypair.insecure_clone())
+                .with_auth_keypair(server_auth.insecure_clone())
+                .with_allowed_clients(vec![client_auth.pubkey()])
+                .with_policy(move |request| {
+                    policy_clients.lock().unwrap().push(request.client.copied());
+                    Ok(())
+                }),
+        );
+
+        let config = RemoteSignerConfig {
+            auth_keypair: Some(client_auth.insecure_clone()),
+            server_pubkey: Some(server_auth.pubkey()),
+            ..RemoteSignerConfig::default()
+        };
+        let signer = RemoteSigner::connect_tcp(addr, config).unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Ok(keypair.sign_message(b"message"))
+        );
+        assert_eq!(*clients.lock().unwrap(), [Some(client_auth.pubkey())]);
+
+        // Unknown and anonymous clients are refused
+        for auth_keypair in [Some(Keypair::new()), None] {
+            let config = RemoteSignerConfig {
+                auth_keypair,
+                server_pubkey: Some(server_auth.pubkey()),
+                ..RemoteSignerConfig::default()
+            };
+            assert!(matches!(
+                RemoteSigner::connect_tcp(addr, config),
+                Err(SignerError::Protocol(message)) if message.starts_with("unauthorized")
+            ));
+        }
+
+        // The client refuses a server with another key
+        let config = RemoteSignerConfig {
+            auth_keypair: Some(client_auth.insecure_clone()),
+            server_pubkey: Some(Pubkey::new_unique()),
+            ..RemoteSignerConfig::default()
+        };
+        assert!(matches!(
+            RemoteSigner::connect_tcp(addr, config),
+            Err(SignerError::Protocol(_))
+        ));
+        assert_eq!(clients.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_remote_signer_server_gone() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let keypair = Keypair::new();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            sender.send(stream.try_clone().unwrap()).unwrap();
+            SignerServer::new(keypair).serve_connection(stream)
+        });
+        let signer = RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()).unwrap();
+        receiver.recv().unwrap().shutdown(Shutdown::Both).unwrap();
+        assert!(matches!(
+            signer.try_sign_message(b"message"),
+            Err(SignerError::Connection(_))
+        ));
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Err(SignerError::Connection("connection closed".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_remote_signer_non_loopback() {
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        assert_eq!(
+            SignerServer::new(Keypair::new())
+                .serve_tcp(listener)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn test_remote_signer_connection_limits() {
+        let keypair = Keypair::new();
+        let addr = spawn_tcp_server(
+            SignerServer::new(keypair.insecure_clone())
+                .with_max_connections(1)
+                .with_timeout(Some(Duration::from_millis(200))),
+        );
+
+        let signer = RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()).unwrap();
+        assert!(matches!(
+            RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()),
+            Err(SignerError::Connection(_))
+        ));
+
+        // The idle connection is closed, which frees its slot
+        thread::sleep(Duration::from_millis(400));
+        assert!(matches!(
+            signer.try_sign_message(b"message"),
+            Err(SignerError::Connection(_))
+        ));
+        let signer = RemoteSigner::connect_tcp(addr, RemoteSignerConfig::default()).unwrap();
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Ok(keypair.sign_message(b"message"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_signer_unix() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let keypair = Keypair::new();
+        let server_keypair = keypair.insecure_clone();
+        thread::spawn(move || SignerServer::new(server_keypair).serve_unix(listener));
+
+        let signer = RemoteSigner::connect_unix(&path, RemoteSignerConfig::default()).unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Ok(keypair.sign_message(b"message"))
+        );
+        assert_eq!(
+            signer.try_sign_message(&[0; protocol::MAX_FRAME_LENGTH]),
+            Err(SignerError::InvalidInput("message too long".to_string()))
+        );
+    }
+}
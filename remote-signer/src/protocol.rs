@@ -0,0 +1,314 @@
+//! The wire protocol shared by [`RemoteSigner`] and [`SignerServer`].
+//!
+//! Every frame is a little-endian `u32` length followed by that many bytes
+//! of payload. A connection starts with each side sending a hello frame:
+//!
+//! | Bytes | Field                                                  |
+//! |-------|--------------------------------------------------------|
+//! | 1     | Protocol version, currently 1                          |
+//! | 32    | Random nonce                                           |
+//! | 1     | 1 if an authentication pubkey follows, otherwise 0     |
+//! | 32    | Authentication pubkey, if any                          |
+//!
+//! The client then sends requests, each answered by one response:
+//!
+//! | Request                      | Response                            |
+//! |------------------------------|-------------------------------------|
+//! | `0`: get pubkey              | `0` and the 32 byte pubkey          |
+//! | `1` and the message to sign  | `1` and the 64 byte signature       |
+//!
+//! Any request may instead be answered by an error response, `2`, an
+//! [`ErrorCode`] byte and a UTF-8 description.
+//!
+//! A side that sent an authentication pubkey prefixes the payload of each of
+//! its requests or responses with an ed25519 signature by that key over
+//! [`AUTH_DOMAIN`], the direction, both nonces, the sequence number of the
+//! request and the payload. The nonces and sequence number bind every
+//! signature to one position in one connection, so frames can't be replayed
+//! or reordered. Frames are not encrypted.
+//!
+//! [`RemoteSigner`]: crate::RemoteSigner
+//! [`SignerServer`]: crate::SignerServer
+
+use {
+    solana_pubkey::Pubkey,
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    solana_signer::Signer,
+    std::io::{self, Read, Write},
+};
+
+/// The version of the protocol implemented by this crate
+pub const PROTOCOL_VERSION: u8 = 1;
+/// The longest frame either side accepts. That's enough for off-chain
+/// messages of the maximum length, which are larger than transactions.
+pub const MAX_FRAME_LENGTH: usize = 70 * 1024;
+/// Domain separator of authentication signatures, so that they can't be
+/// confused with signatures made for any other purpose
+pub const AUTH_DOMAIN: &[u8] = b"solana remote signer auth v1";
+pub(crate) const NONCE_LENGTH: usize = 32;
+
+const PUBKEY_BYTES: usize = 32;
+
+pub(crate) const REQUEST_GET_PUBKEY: u8 = 0;
+pub(crate) const REQUEST_SIGN_MESSAGE: u8 = 1;
+pub(crate) const RESPONSE_PUBKEY: u8 = 0;
+pub(crate) const RESPONSE_SIGNATURE: u8 = 1;
+pub(crate) const RESPONSE_ERROR: u8 = 2;
+
+/// Why a server refused a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The policy rejected the message
+    PolicyRejected,
+    /// The wrapped signer failed to sign
+    SigningFailed,
+    /// The client isn't allowed to connect, or a request wasn't
+    /// authenticated
+    Unauthorized,
+    /// The request is malformed
+    BadRequest,
+}
+
+impl ErrorCode {
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            Self::PolicyRejected => 0,
+            Self::SigningFailed => 1,
+            Self::Unauthorized => 2,
+            Self::BadRequest => 3,
+        }
+    }
+
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::PolicyRejected),
+            1 => Some(Self::SigningFailed),
+            2 => Some(Self::Unauthorized),
+            3 => Some(Self::BadRequest),
+            _ => None,
+        }
+    }
+}
+
+/// Which side of the connection signed a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Request,
+    Response,
+}
+
+/// The hello frame that each side sends first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Hello {
+    pub(crate) nonce: [u8; NONCE_LENGTH],
+    pub(crate) auth_pubkey: Option<Pubkey>,
+}
+
+impl Hello {
+    pub(crate) fn new(auth_pubkey: Option<Pubkey>) -> Self {
+        let mut nonce = [0; NONCE_LENGTH];
+        rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut nonce);
+        Self { nonce, auth_pubkey }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(2 + NONCE_LENGTH + PUBKEY_BYTES);
+        bytes.push(PROTOCOL_VERSION);
+        bytes.extend_from_slice(&self.nonce);
+        match &self.auth_pubkey {
+            Some(pubkey) => {
+                bytes.push(1);
+                bytes.extend_from_slice(pubkey.as_ref());
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let (&version, rest) = bytes
+            .split_first()
+            .ok_or_else(|| invalid_data("empty hello"))?;
+        if version != PROTOCOL_VERSION {
+            return Err(invalid_data("unsupported protocol version"));
+        }
+        let (nonce, rest) = split_array::<NONCE_LENGTH>(rest)?;
+        let auth_pubkey = match rest {
+            [0] => None,
+            [1, pubkey @ ..] => Some(Pubkey::from(split_array::<PUBKEY_BYTES>(pubkey)?.0)),
+            _ => return Err(invalid_data("invalid hello")),
+        };
+        if auth_pubkey.is_some() && rest.len() != 1 + PUBKEY_BYTES {
+            return Err(invalid_data("invalid hello"));
+        }
+        Ok(Self { nonce, auth_pubkey })
+    }
+}
+
+/// The state that binds authentication signatures to a connection
+pub(crate) struct Session {
+    pub(crate) client_nonce: [u8; NONCE_LENGTH],
+    pub(crate) server_nonce: [u8; NONCE_LENGTH],
+    pub(crate) sequence: u64,
+}
+
+impl Session {
+    fn signed_data(&self, direction: Direction, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(
+            AUTH_DOMAIN
+                .len()
+                .saturating_add(1 + 2 * NONCE_LENGTH + 8)
+                .saturating_add(payload.len()),
+        );
+        data.extend_from_slice(AUTH_DOMAIN);
+        data.push(match direction {
+            Direction::Request => 0,
+            Direction::Response => 1,
+        });
+        data.extend_from_slice(&self.client_nonce);
+        data.extend_from_slice(&self.server_nonce);
+        data.extend_from_slice(&self.sequence.to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// Prefix `payload` with a signature by `auth`, if any
+    pub(crate) fn seal(
+        &self,
+        direction: Direction,
+        auth: Option<&dyn Signer>,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let Some(auth) = auth else {
+            return payload.to_vec();
+        };
+        let signature = auth.sign_message(&self.signed_data(direction, payload));
+        let mut frame = Vec::with_capacity(SIGNATURE_BYTES.saturating_add(payload.len()));
+        frame.extend_from_slice(signature.as_ref());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// Check and strip the signature of `frame` by `auth_pubkey`, if any
+    pub(crate) fn open<'a>(
+        &self,
+        direction: Direction,
+        auth_pubkey: Option<&Pubkey>,
+        frame: &'a [u8],
+    ) -> Option<&'a [u8]> {
+        let Some(auth_pubkey) = auth_pubkey else {
+            return Some(frame);
+        };
+        let (signature, payload) = split_array::<SIGNATURE_BYTES>(frame).ok()?;
+        Signature::from(signature)
+            .verify(auth_pubkey.as_ref(), &self.signed_data(direction, payload))
+            .then_some(payload)
+    }
+}
+
+pub(crate) fn write_frame<W: Write + ?Sized>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_FRAME_LENGTH {
+        return Err(invalid_data("frame too long"));
+    }
+    writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+pub(crate) fn read_frame<R: Read + ?Sized>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(invalid_data("frame too long"));
+    }
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+pub(crate) fn split_array<const N: usize>(bytes: &[u8]) -> io::Result<([u8; N], &[u8])> {
+    if bytes.len() < N {
+        return Err(invalid_data("frame too short"));
+    }
+    let (array, rest) = bytes.split_at(N);
+    Ok((array.try_into().unwrap(), rest))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_keypair::Keypair};
+
+    #[test]
+    fn test_hello() {
+        for hello in [Hello::new(None), Hello::new(Some(Pubkey::new_unique()))] {
+            assert_eq!(Hello::decode(&hello.encode()).unwrap(), hello);
+        }
+        let mut bytes = Hello::new(Some(Pubkey::new_unique())).encode();
+        bytes.push(0);
+        assert!(Hello::decode(&bytes).is_err());
+        bytes[0] = 2;
+        assert!(Hello::decode(&bytes).is_err());
+        assert!(Hello::decode(&[PROTOCOL_VERSION; 33]).is_err());
+        assert!(Hello::decode(&[]).is_err());
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"hello").unwrap();
+        write_frame(&mut buffer, &[]).unwrap();
+        assert!(write_frame(&mut buffer, &vec![0; MAX_FRAME_LENGTH + 1]).is_err());
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_frame(&mut reader).unwrap(), b"hello");
+        assert_eq!(read_frame(&mut reader).unwrap(), b"");
+        assert!(read_frame(&mut reader).is_err());
+
+        let too_long = (MAX_FRAME_LENGTH as u32 + 1).to_le_bytes();
+        assert!(read_frame(&mut too_long.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_session_auth() {
+        let auth = Keypair::new();
+        let mut session = Session {
+            client_nonce: [1; NONCE_LENGTH],
+            server_nonce: [2; NONCE_LENGTH],
+            sequence: 0,
+        };
+        let pubkey = auth.pubkey();
+        let frame = session.seal(Direction::Request, Some(&auth), b"payload");
+        assert_eq!(
+            session.open(Direction::Request, Some(&pubkey), &frame),
+            Some(&b"payload"[..])
+        );
+        // Signatures are bound to the direction, the key and the sequence
+        assert_eq!(
+            session.open(Direction::Response, Some(&pubkey), &frame),
+            None
+        );
+        assert_eq!(
+            session.open(Direction::Request, Some(&Pubkey::new_unique()), &frame),
+            None
+        );
+        session.sequence = 1;
+        assert_eq!(
+            session.open(Direction::Request, Some(&pubkey), &frame),
+            None
+        );
+
+        // Without authentication frames are just the payload
+        assert_eq!(
+            session.seal(Direction::Request, None, b"payload"),
+            b"payload"
+        );
+        assert_eq!(
+            session.open(Direction::Request, None, b"payload"),
+            Some(&b"payload"[..])
+        );
+    }
+}
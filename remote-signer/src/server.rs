@@ -0,0 +1,287 @@
+//! A server that signs on behalf of [`RemoteSigner`] clients.
+//!
+//! [`RemoteSigner`]: crate::RemoteSigner
+
+use {
+    crate::protocol::{
+        read_frame, write_frame, Direction, ErrorCode, Hello, Session, MAX_FRAME_LENGTH,
+        REQUEST_GET_PUBKEY, REQUEST_SIGN_MESSAGE, RESPONSE_ERROR, RESPONSE_PUBKEY,
+        RESPONSE_SIGNATURE,
+    },
+    bincode::Options,
+    log::warn,
+    solana_keypair::Keypair,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    std::{
+        io::{self, Read, Write},
+        net::TcpListener,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    },
+};
+
+/// How long a connection may wait for the next request, or for a response to
+/// be written, before the server closes it
+pub const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+/// How many connections a server serves at once
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+/// A request to sign a message, as seen by a [`SigningPolicy`]
+#[derive(Debug)]
+pub struct SignRequest<'a> {
+    /// The message bytes to sign
+    pub message: &'a [u8],
+    /// The message decoded as a transaction message, if it is one
+    pub decoded: Option<&'a VersionedMessage>,
+    /// The authenticated key of the client, if it authenticated
+    pub client: Option<&'a Pubkey>,
+}
+
+/// Decides whether a message may be signed, returning the reason when it may
+/// not
+pub type SigningPolicy = Box<dyn Fn(&SignRequest) -> Result<(), String> + Send + Sync>;
+
+/// Serves signing requests of [`RemoteSigner`] clients with a local signer
+///
+/// [`RemoteSigner`]: crate::RemoteSigner
+pub struct SignerServer<S> {
+    signer: S,
+    auth_keypair: Option<Keypair>,
+    allowed_clients: Option<Vec<Pubkey>>,
+    policy: Option<SigningPolicy>,
+    timeout: Option<Duration>,
+    max_connections: usize,
+}
+
+impl<S: Signer + Sync> SignerServer<S> {
+    /// A server that signs every request with `signer`
+    pub fn new(signer: S) -> Self {
+        Self {
+            signer,
+            auth_keypair: None,
+            allowed_clients: None,
+            policy: None,
+            timeout: Some(DEFAULT_CONNECTION_TIMEOUT),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+        }
+    }
+
+    /// Authenticate responses with `auth_keypair`
+    pub fn with_auth_keypair(mut self, auth_keypair: Keypair) -> Self {
+        self.auth_keypair = Some(auth_keypair);
+        self
+    }
+
+    /// Only serve clients that authenticate with one of `allowed_clients`
+    pub fn with_allowed_clients(mut self, allowed_clients: Vec<Pubkey>) -> Self {
+        self.allowed_clients = Some(allowed_clients);
+        self
+    }
+
+    /// Only sign messages that `policy` accepts
+    pub fn with_policy<P>(mut self, policy: P) -> Self
+    where
+        P: Fn(&SignRequest) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Close connections that wait longer than `timeout` for a request or
+    /// for a response to be written, or never close idle connections if
+    /// `timeout` is `None`. [`RemoteSigner`] clients don't reconnect, so a
+    /// client that is idle for longer has to connect again. Defaults to
+    /// [`DEFAULT_CONNECTION_TIMEOUT`].
+    ///
+    /// [`RemoteSigner`]: crate::RemoteSigner
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Serve at most `max_connections` connections at once, closing any
+    /// others as soon as they are accepted. Defaults to
+    /// [`DEFAULT_MAX_CONNECTIONS`].
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Accept connections on `listener`, serving each on its own thread.
+    /// Only returns if accepting a connection fails.
+    ///
+    /// Fails with [`io::ErrorKind::PermissionDenied`] if `listener` isn't
+    /// bound to a loopback address and no allowed clients were configured
+    /// with [`SignerServer::with_allowed_clients`], since the server would
+    /// sign for anyone who can reach it.
+    pub fn serve_tcp(&self, listener: TcpListener) -> io::Result<()> {
+        if !listener.local_addr()?.ip().is_loopback() && self.allowed_clients.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "serving on a non-loopback address requires allowed clients",
+            ));
+        }
+        let active_connections = AtomicUsize::new(0);
+        thread::scope(|scope| loop {
+            let (stream, peer) = listener.accept()?;
+            if let Err(err) = stream
+                .set_nodelay(true)
+                .and_then(|()| stream.set_read_timeout(self.timeout))
+                .and_then(|()| stream.set_write_timeout(self.timeout))
+            {
+                warn!("failed to configure connection from {peer}: {err}");
+                continue;
+            }
+            let Some(guard) = ConnectionGuard::acquire(&active_connections, self.max_connections)
+            else {
+                warn!("refusing connection from {peer}: too many connections");
+                continue;
+            };
+            scope.spawn(move || {
+                let _guard = guard;
+                if let Err(err) = self.serve_connection(stream) {
+                    warn!("connection from {peer} failed: {err}");
+                }
+            });
+        })
+    }
+
+    /// Accept connections on `listener`, serving each on its own thread.
+    /// Only returns if accepting a connection fails.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: std::os::unix::net::UnixListener) -> io::Result<()> {
+        let active_connections = AtomicUsize::new(0);
+        thread::scope(|scope| loop {
+            let (stream, _) = listener.accept()?;
+            if let Err(err) = stream
+                .set_read_timeout(self.timeout)
+                .and_then(|()| stream.set_write_timeout(self.timeout))
+            {
+                warn!("failed to configure connection: {err}");
+                continue;
+            }
+            let Some(guard) = ConnectionGuard::acquire(&active_connections, self.max_connections)
+            else {
+                warn!("refusing connection: too many connections");
+                continue;
+            };
+            scope.spawn(move || {
+                let _guard = guard;
+                if let Err(err) = self.serve_connection(stream) {
+                    warn!("connection failed: {err}");
+                }
+            });
+        })
+    }
+
+    /// Serve requests on a connected stream until the client disconnects
+    pub fn serve_connection<T: Read + Write>(&self, mut stream: T) -> io::Result<()> {
+        let client_hello = Hello::decode(&read_frame(&mut stream)?)?;
+        let hello = Hello::new(self.auth_keypair.as_ref().map(Signer::pubkey));
+        write_frame(&mut stream, &hello.encode())?;
+
+        let mut session = Session {
+            client_nonce: client_hello.nonce,
+            server_nonce: hello.nonce,
+            sequence: 0,
+        };
+        let client = client_hello.auth_pubkey;
+        let authorized = match (&self.allowed_clients, &client) {
+            (None, _) => true,
+            (Some(allowed_clients), Some(client)) => allowed_clients.contains(client),
+            (Some(_), None) => false,
+        };
+
+        loop {
+            let frame = match read_frame(&mut stream) {
+                Ok(frame) => frame,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            let request = session.open(Direction::Request, client.as_ref(), &frame);
+            let response = match request {
+                Some(request) if authorized => self.handle_request(request, client.as_ref()),
+                _ => error_response(ErrorCode::Unauthorized, "client not authorized"),
+            };
+            let auth = self
+                .auth_keypair
+                .as_ref()
+                .map(|keypair| keypair as &dyn Signer);
+            write_frame(
+                &mut stream,
+                &session.seal(Direction::Response, auth, &response),
+            )?;
+            if !authorized || request.is_none() {
+                return Ok(());
+            }
+            session.sequence = session.sequence.wrapping_add(1);
+        }
+    }
+
+    fn handle_request(&self, request: &[u8], client: Option<&Pubkey>) -> Vec<u8> {
+        match request {
+            [REQUEST_GET_PUBKEY] => match self.signer.try_pubkey() {
+                Ok(pubkey) => [&[RESPONSE_PUBKEY], pubkey.as_ref()].concat(),
+                Err(err) => error_response(ErrorCode::SigningFailed, &err.to_string()),
+            },
+            [REQUEST_SIGN_MESSAGE, message @ ..] => {
+                let decoded = decode_message(message);
+                let sign_request = SignRequest {
+                    message,
+                    decoded: decoded.as_ref(),
+                    client,
+                };
+                if let Some(policy) = &self.policy {
+                    if let Err(reason) = policy(&sign_request) {
+                        return error_response(ErrorCode::PolicyRejected, &reason);
+                    }
+                }
+                match self.signer.try_sign_message(message) {
+                    Ok(signature) => [&[RESPONSE_SIGNATURE], signature.as_ref()].concat(),
+                    Err(err) => error_response(ErrorCode::SigningFailed, &err.to_string()),
+                }
+            }
+            _ => error_response(ErrorCode::BadRequest, "unknown request"),
+        }
+    }
+}
+
+/// Counts a connection towards the limit of a server while it's alive
+struct ConnectionGuard<'a>(&'a AtomicUsize);
+
+impl<'a> ConnectionGuard<'a> {
+    fn acquire(active_connections: &'a AtomicUsize, max_connections: usize) -> Option<Self> {
+        active_connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < max_connections).then(|| active.saturating_add(1))
+            })
+            .ok()
+            .map(|_| Self(active_connections))
+    }
+}
+
+impl Drop for ConnectionGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Decode `message` as a sanitized transaction message, rejecting trailing
+/// bytes so that the policy sees exactly what is signed
+fn decode_message(message: &[u8]) -> Option<VersionedMessage> {
+    let decoded: VersionedMessage = bincode::options()
+        .with_limit(MAX_FRAME_LENGTH as u64)
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(message)
+        .ok()?;
+    decoded.sanitize().ok()?;
+    Some(decoded)
+}
+
+fn error_response(code: ErrorCode, description: &str) -> Vec<u8> {
+    [&[RESPONSE_ERROR, code.to_byte()], description.as_bytes()].concat()
+}
@@ -788,7 +788,12 @@
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "bincode")]
 use solana_instruction::Instruction;
-use {digest::Digest, solana_signature::error::Error};
+#[cfg(feature = "bincode")]
+use solana_secp256k1_recover::{eth::parse_eth_signature, Secp256k1RecoverError};
+use {
+    solana_keccak_hasher as keccak, solana_precompile_error::PrecompileError,
+    solana_secp256k1_recover::Secp256k1Pubkey, solana_signature::error::Error,
+};
 
 pub const SECP256K1_PUBKEY_SIZE: usize = 64;
 pub const SECP256K1_PRIVATE_KEY_SIZE: usize = 32;
@@ -829,12 +834,7 @@ pub fn sign_message(
 ) -> Result<([u8; SIGNATURE_SERIALIZED_SIZE], u8), Error> {
     let priv_key = libsecp256k1::SecretKey::parse(priv_key_bytes)
         .map_err(|e| Error::from_source(format!("{e}")))?;
-    let mut hasher = sha3::Keccak256::new();
-    hasher.update(message);
-    let message_hash = hasher.finalize();
-    let mut message_hash_arr = [0u8; 32];
-    message_hash_arr.copy_from_slice(message_hash.as_slice());
-    let message = libsecp256k1::Message::parse(&message_hash_arr);
+    let message = libsecp256k1::Message::parse(&keccak::hash(message).to_bytes());
     let (signature, recovery_id) = libsecp256k1::sign(&message, &priv_key);
     let signature_arr = signature.serialize();
     Ok((signature_arr, recovery_id.serialize()))
@@ -890,12 +890,413 @@ pub fn new_secp256k1_instruction_with_signature(
     }
 }
 
+/// Creates a secp256k1 instruction from a 65-byte Ethereum signature,
+/// `r || s || v`, parsed by [`parse_eth_signature`], so the recovery ID `v`
+/// can be 0, 1, 27 or 28.
+///
+/// Signatures with a high `s` are rejected, as Ethereum and
+/// `recover_eth_address` of `solana-secp256k1-recover` do, even though the
+/// secp256k1 program would verify them. Use
+/// [`new_secp256k1_instruction_with_signature`] to build an instruction for
+/// such a signature.
+///
+/// Wallets sign the keccak-256 hash of an EIP-191 or EIP-712 message, so
+/// `message_arr` is that message, as built by `eip191_message` or
+/// `eip712_message` of `solana-secp256k1-recover`, not the hash.
+///
+/// [`parse_eth_signature`]: solana_secp256k1_recover::eth::parse_eth_signature
+#[cfg(feature = "bincode")]
+pub fn new_secp256k1_instruction_with_eth_signature(
+    message_arr: &[u8],
+    eth_signature: &[u8; SIGNATURE_SERIALIZED_SIZE + 1],
+    eth_address: &[u8; HASHED_PUBKEY_SERIALIZED_SIZE],
+) -> Result<Instruction, PrecompileError> {
+    let (signature, recovery_id) = parse_eth_signature(eth_signature).map_err(|err| match err {
+        Secp256k1RecoverError::InvalidRecoveryId => PrecompileError::InvalidRecoveryId,
+        _ => PrecompileError::InvalidSignature,
+    })?;
+    Ok(new_secp256k1_instruction_with_signature(
+        message_arr,
+        &signature,
+        recovery_id,
+        eth_address,
+    ))
+}
+
 /// Creates an Ethereum address from a secp256k1 public key.
 pub fn eth_address_from_pubkey(
     pubkey: &[u8; SECP256K1_PUBKEY_SIZE],
 ) -> [u8; HASHED_PUBKEY_SERIALIZED_SIZE] {
-    let mut addr = [0u8; HASHED_PUBKEY_SERIALIZED_SIZE];
-    addr.copy_from_slice(&sha3::Keccak256::digest(pubkey)[12..]);
-    assert_eq!(addr.len(), HASHED_PUBKEY_SERIALIZED_SIZE);
-    addr
+    solana_secp256k1_recover::eth::eth_address_from_pubkey(&Secp256k1Pubkey(*pubkey))
+}
+
+impl SecpSignatureOffsets {
+    /// Decode the offsets from their serialized form, little-endian like
+    /// bincode
+    fn from_bytes(bytes: &[u8; SIGNATURE_OFFSETS_SERIALIZED_SIZE]) -> Self {
+        Self {
+            signature_offset: u16::from_le_bytes([bytes[0], bytes[1]]),
+            signature_instruction_index: bytes[2],
+            eth_address_offset: u16::from_le_bytes([bytes[3], bytes[4]]),
+            eth_address_instruction_index: bytes[5],
+            message_data_offset: u16::from_le_bytes([bytes[6], bytes[7]]),
+            message_data_size: u16::from_le_bytes([bytes[8], bytes[9]]),
+            message_instruction_index: bytes[10],
+        }
+    }
+}
+
+/// Verify the signatures of a secp256k1 instruction, as the runtime does.
+///
+/// `data` is the instruction data of the secp256k1 instruction and
+/// `instruction_datas` the instruction data of every instruction of the
+/// transaction, including the secp256k1 instruction itself, that the offsets
+/// refer to by index.
+pub fn verify(data: &[u8], instruction_datas: &[&[u8]]) -> Result<(), PrecompileError> {
+    if data.is_empty() {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let count = data[0] as usize;
+    if count == 0 && data.len() > 1 {
+        // count is zero but the instruction data indicates that is probably not
+        // correct, fail the instruction to catch probable invalid secp256k1
+        // instruction construction.
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let expected_data_size = count
+        .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .saturating_add(1);
+    if data.len() < expected_data_size {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    for i in 0..count {
+        let start = i
+            .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .saturating_add(1);
+        let end = start.saturating_add(SIGNATURE_OFFSETS_SERIALIZED_SIZE);
+
+        let offsets = SecpSignatureOffsets::from_bytes(data[start..end].try_into().unwrap());
+
+        // Parse out signature
+        let signature_index = offsets.signature_instruction_index as usize;
+        if signature_index >= instruction_datas.len() {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+        let signature_instruction = instruction_datas[signature_index];
+        let sig_start = offsets.signature_offset as usize;
+        let sig_end = sig_start.saturating_add(SIGNATURE_SERIALIZED_SIZE);
+        if sig_end >= signature_instruction.len() {
+            return Err(PrecompileError::InvalidSignature);
+        }
+
+        let signature = libsecp256k1::Signature::parse_standard_slice(
+            &signature_instruction[sig_start..sig_end],
+        )
+        .map_err(|_| PrecompileError::InvalidSignature)?;
+
+        let recovery_id = libsecp256k1::RecoveryId::parse(signature_instruction[sig_end])
+            .map_err(|_| PrecompileError::InvalidRecoveryId)?;
+
+        // Parse out pubkey
+        let eth_address_slice = get_data_slice(
+            instruction_datas,
+            offsets.eth_address_instruction_index,
+            offsets.eth_address_offset,
+            HASHED_PUBKEY_SERIALIZED_SIZE,
+        )?;
+
+        // Parse out message
+        let message_slice = get_data_slice(
+            instruction_datas,
+            offsets.message_instruction_index,
+            offsets.message_data_offset,
+            offsets.message_data_size as usize,
+        )?;
+
+        let message_hash = keccak::hash(message_slice).to_bytes();
+
+        let pubkey = libsecp256k1::recover(
+            &libsecp256k1::Message::parse(&message_hash),
+            &signature,
+            &recovery_id,
+        )
+        .map_err(|_| PrecompileError::InvalidSignature)?;
+        let eth_address = eth_address_from_pubkey(&pubkey.serialize()[1..].try_into().unwrap());
+
+        if eth_address_slice != eth_address {
+            return Err(PrecompileError::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+fn get_data_slice<'a>(
+    instruction_datas: &'a [&[u8]],
+    instruction_index: u8,
+    offset_start: u16,
+    size: usize,
+) -> Result<&'a [u8], PrecompileError> {
+    let signature_index = instruction_index as usize;
+    if signature_index >= instruction_datas.len() {
+        return Err(PrecompileError::InvalidDataOffsets);
+    }
+    let signature_instruction = &instruction_datas[signature_index];
+    let start = offset_start as usize;
+    let end = start.saturating_add(size);
+    if end > signature_instruction.len() {
+        return Err(PrecompileError::InvalidSignature);
+    }
+
+    Ok(&instruction_datas[signature_index][start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: [u8; SECP256K1_PRIVATE_KEY_SIZE] = [
+        0xb0, 0x57, 0xef, 0x17, 0x82, 0x44, 0x39, 0xd5, 0xa3, 0x8c, 0xb3, 0x0b, 0x8d, 0x3f, 0x1b,
+        0x2d, 0x62, 0x63, 0x4e, 0x0e, 0x64, 0x2d, 0x1e, 0xa3, 0x9c, 0x6b, 0x27, 0x36, 0x44, 0x66,
+        0xa9, 0x22,
+    ];
+    const MESSAGE: &[u8] = b"hello";
+
+    fn serialize_offsets(offsets: &SecpSignatureOffsets) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SIGNATURE_OFFSETS_SERIALIZED_SIZE);
+        bytes.extend_from_slice(&offsets.signature_offset.to_le_bytes());
+        bytes.push(offsets.signature_instruction_index);
+        bytes.extend_from_slice(&offsets.eth_address_offset.to_le_bytes());
+        bytes.push(offsets.eth_address_instruction_index);
+        bytes.extend_from_slice(&offsets.message_data_offset.to_le_bytes());
+        bytes.extend_from_slice(&offsets.message_data_size.to_le_bytes());
+        bytes.push(offsets.message_instruction_index);
+        bytes
+    }
+
+    fn test_case(
+        num_signatures: u8,
+        offsets: &SecpSignatureOffsets,
+    ) -> Result<(), PrecompileError> {
+        let mut instruction_data = vec![num_signatures];
+        instruction_data.extend_from_slice(&serialize_offsets(offsets));
+        verify(&instruction_data, &[&[0u8; 100]])
+    }
+
+    /// An instruction verifying a signature of `MESSAGE`, with all data
+    /// following the offsets, and the offsets themselves
+    fn signed_instruction_data() -> Vec<u8> {
+        let secret_key = libsecp256k1::SecretKey::parse(&PRIVATE_KEY).unwrap();
+        let pubkey = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let eth_address = eth_address_from_pubkey(&pubkey.serialize()[1..].try_into().unwrap());
+        let (signature, recovery_id) = sign_message(&PRIVATE_KEY, MESSAGE).unwrap();
+
+        let offsets = SecpSignatureOffsets {
+            signature_offset: (DATA_START + HASHED_PUBKEY_SERIALIZED_SIZE) as u16,
+            signature_instruction_index: 0,
+            eth_address_offset: DATA_START as u16,
+            eth_address_instruction_index: 0,
+            message_data_offset: (DATA_START + HASHED_PUBKEY_SERIALIZED_SIZE + 65) as u16,
+            message_data_size: MESSAGE.len() as u16,
+            message_instruction_index: 0,
+        };
+        let mut data = vec![1];
+        data.extend_from_slice(&serialize_offsets(&offsets));
+        data.extend_from_slice(&eth_address);
+        data.extend_from_slice(&signature);
+        data.push(recovery_id);
+        data.extend_from_slice(MESSAGE);
+        data
+    }
+
+    #[test]
+    fn test_verify_vector() {
+        let data = signed_instruction_data();
+        assert_eq!(verify(&data, &[&data]), Ok(()));
+
+        #[cfg(feature = "bincode")]
+        {
+            let (signature, recovery_id) = sign_message(&PRIVATE_KEY, MESSAGE).unwrap();
+            let instruction = new_secp256k1_instruction_with_signature(
+                MESSAGE,
+                &signature,
+                recovery_id,
+                data[DATA_START..DATA_START + HASHED_PUBKEY_SERIALIZED_SIZE]
+                    .try_into()
+                    .unwrap(),
+            );
+            assert_eq!(instruction.data, data);
+        }
+
+        // Indexes refer to the instructions of the transaction, not to the
+        // secp256k1 instruction
+        assert_eq!(
+            verify(&data, &[]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+        assert_eq!(
+            verify(&data, &[&[0u8; 200]]),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let mut invalid = data.clone();
+        *invalid.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify(&invalid, &[&invalid]),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let mut invalid = data.clone();
+        invalid[DATA_START] ^= 1;
+        assert_eq!(
+            verify(&invalid, &[&invalid]),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let mut invalid = data;
+        invalid[DATA_START + HASHED_PUBKEY_SERIALIZED_SIZE + SIGNATURE_SERIALIZED_SIZE] = 4;
+        assert_eq!(
+            verify(&invalid, &[&invalid]),
+            Err(PrecompileError::InvalidRecoveryId)
+        );
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_eth_signature_instruction() {
+        // An EIP-191 `personal_sign` message, whose hash the wallet signs
+        let message = [&b"\x19Ethereum Signed Message:\n5"[..], MESSAGE].concat();
+        let (signature, recovery_id) = sign_message(&PRIVATE_KEY, &message).unwrap();
+        let secret_key = libsecp256k1::SecretKey::parse(&PRIVATE_KEY).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let eth_address = eth_address_from_pubkey(public_key.serialize()[1..].try_into().unwrap());
+
+        let mut eth_signature = [0; SIGNATURE_SERIALIZED_SIZE + 1];
+        eth_signature[..SIGNATURE_SERIALIZED_SIZE].copy_from_slice(&signature);
+        for v in [recovery_id, recovery_id.saturating_add(27)] {
+            eth_signature[SIGNATURE_SERIALIZED_SIZE] = v;
+            let instruction = new_secp256k1_instruction_with_eth_signature(
+                &message,
+                &eth_signature,
+                &eth_address,
+            )
+            .unwrap();
+            assert_eq!(
+                instruction,
+                new_secp256k1_instruction_with_signature(
+                    &message,
+                    &signature,
+                    recovery_id,
+                    &eth_address
+                )
+            );
+            assert_eq!(verify(&instruction.data, &[&instruction.data]), Ok(()));
+        }
+
+        eth_signature[SIGNATURE_SERIALIZED_SIZE] = 29;
+        assert_eq!(
+            new_secp256k1_instruction_with_eth_signature(&message, &eth_signature, &eth_address),
+            Err(PrecompileError::InvalidRecoveryId)
+        );
+
+        // The same signature with s replaced by n - s is verified by the
+        // program but rejected by the builder
+        let mut high_s = libsecp256k1::Signature::parse_standard(&signature).unwrap();
+        high_s.s = -high_s.s;
+        let high_s = high_s.serialize();
+        let instruction = new_secp256k1_instruction_with_signature(
+            &message,
+            &high_s,
+            recovery_id ^ 1,
+            &eth_address,
+        );
+        assert_eq!(verify(&instruction.data, &[&instruction.data]), Ok(()));
+        eth_signature[..SIGNATURE_SERIALIZED_SIZE].copy_from_slice(&high_s);
+        eth_signature[SIGNATURE_SERIALIZED_SIZE] = recovery_id ^ 1;
+        assert_eq!(
+            new_secp256k1_instruction_with_eth_signature(&message, &eth_signature, &eth_address),
+            Err(PrecompileError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_invalid_offsets() {
+        let mut instruction_data = vec![1];
+        instruction_data.extend_from_slice(&serialize_offsets(&SecpSignatureOffsets::default()));
+        instruction_data.truncate(instruction_data.len() - 1);
+        assert_eq!(
+            verify(&instruction_data, &[&[0u8; 100]]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+
+        let offsets = SecpSignatureOffsets {
+            signature_instruction_index: 1,
+            ..SecpSignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+
+        let offsets = SecpSignatureOffsets {
+            message_instruction_index: 1,
+            ..SecpSignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        let offsets = SecpSignatureOffsets {
+            eth_address_instruction_index: 1,
+            ..SecpSignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidDataOffsets)
+        );
+
+        // No signatures, with trailing data
+        assert_eq!(
+            verify(&[0, 0], &[]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+        assert_eq!(verify(&[0], &[]), Ok(()));
+        assert_eq!(
+            verify(&[], &[]),
+            Err(PrecompileError::InvalidInstructionDataSize)
+        );
+    }
+
+    #[test]
+    fn test_message_data_offsets() {
+        let offsets = SecpSignatureOffsets {
+            message_data_offset: 99,
+            message_data_size: 1,
+            ..SecpSignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let offsets = SecpSignatureOffsets {
+            message_data_offset: 100,
+            message_data_size: 1,
+            ..SecpSignatureOffsets::default()
+        };
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidSignature)
+        );
+
+        let offsets = SecpSignatureOffsets {
+            signature_offset: 100 - SIGNATURE_SERIALIZED_SIZE as u16,
+            ..SecpSignatureOffsets::default()
+        };
+        // No room for the recovery id
+        assert_eq!(
+            test_case(1, &offsets),
+            Err(PrecompileError::InvalidSignature)
+        );
+    }
 }
@@ -0,0 +1,553 @@
+//! Hashing of [EIP-712] typed structured data.
+//!
+//! A typed data signature covers
+//!
+//! ```text
+//! keccak256(0x19 || 0x01 || domainSeparator || hashStruct(message))
+//! ```
+//!
+//! where `domainSeparator` is the struct hash of the [`Eip712Domain`] of the
+//! application, and `hashStruct` hashes a struct together with its type.
+//!
+//! Programs with a fixed message type can compute the type hash once and
+//! encode the fields by hand. [`hash_struct`] encodes any struct from its
+//! [`Eip712Types`] and an [`Eip712Value`], as wallets do for
+//! `eth_signTypedData_v4`.
+//!
+//! [EIP-712]: https://eips.ethereum.org/EIPS/eip-712
+
+use {
+    crate::eth::ETH_ADDRESS_LENGTH,
+    solana_keccak_hasher::{hash, hashv},
+    std::collections::{BTreeMap, BTreeSet},
+    thiserror::Error,
+};
+
+/// The length of the message of a typed data signature,
+/// `0x19 || 0x01 || domainSeparator || hashStruct(message)`
+pub const EIP712_MESSAGE_LENGTH: usize = 66;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Eip712Error {
+    #[error("Type {0} is not defined")]
+    UnknownType(String),
+    #[error("Field {0} is missing")]
+    MissingField(String),
+    #[error("Field {0} is not defined by the type")]
+    UnexpectedField(String),
+    #[error("Value doesn't match type {0}")]
+    TypeMismatch(String),
+}
+
+/// A member of a struct type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip712Field {
+    pub name: String,
+    pub r#type: String,
+}
+
+impl Eip712Field {
+    pub fn new(name: &str, r#type: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+        }
+    }
+}
+
+/// Struct types by name, the `types` of `eth_signTypedData_v4`
+pub type Eip712Types = BTreeMap<String, Vec<Eip712Field>>;
+
+/// A value of typed data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eip712Value {
+    Bool(bool),
+    /// A `uintN`, big-endian
+    Uint([u8; 32]),
+    /// An `intN`, big-endian two's complement
+    Int([u8; 32]),
+    Address([u8; ETH_ADDRESS_LENGTH]),
+    /// A `bytesN`, of exactly `N` bytes
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Eip712Value>),
+    /// A struct, by field name
+    Struct(BTreeMap<String, Eip712Value>),
+}
+
+impl Eip712Value {
+    pub fn from_u64(value: u64) -> Self {
+        let mut bytes = [0; 32];
+        bytes[24..].copy_from_slice(&value.to_be_bytes());
+        Self::Uint(bytes)
+    }
+
+    pub fn from_i64(value: i64) -> Self {
+        let mut bytes = if value < 0 { [0xff; 32] } else { [0; 32] };
+        bytes[24..].copy_from_slice(&value.to_be_bytes());
+        Self::Int(bytes)
+    }
+}
+
+/// The domain of an application, whose struct hash separates its signatures
+/// from those of other applications. Only the fields that are set are part
+/// of the `EIP712Domain` type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Eip712Domain {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: Option<u64>,
+    pub verifying_contract: Option<[u8; ETH_ADDRESS_LENGTH]>,
+    pub salt: Option<[u8; 32]>,
+}
+
+impl Eip712Domain {
+    /// The domain separator, the struct hash of the domain
+    pub fn separator(&self) -> [u8; 32] {
+        let mut fields = Vec::new();
+        let mut values = BTreeMap::new();
+        let mut add_field = |name: &str, r#type: &str, value: Eip712Value| {
+            fields.push(Eip712Field::new(name, r#type));
+            values.insert(name.to_string(), value);
+        };
+        if let Some(name) = &self.name {
+            add_field("name", "string", Eip712Value::String(name.clone()));
+        }
+        if let Some(version) = &self.version {
+            add_field("version", "string", Eip712Value::String(version.clone()));
+        }
+        if let Some(chain_id) = self.chain_id {
+            add_field("chainId", "uint256", Eip712Value::from_u64(chain_id));
+        }
+        if let Some(verifying_contract) = self.verifying_contract {
+            add_field(
+                "verifyingContract",
+                "address",
+                Eip712Value::Address(verifying_contract),
+            );
+        }
+        if let Some(salt) = self.salt {
+            add_field("salt", "bytes32", Eip712Value::FixedBytes(salt.to_vec()));
+        }
+
+        let types = Eip712Types::from([("EIP712Domain".to_string(), fields)]);
+        // The domain type is well-formed and the values match it
+        hash_struct(&types, "EIP712Domain", &values).unwrap()
+    }
+}
+
+/// The message of a typed data signature,
+/// `0x19 || 0x01 || domainSeparator || hashStruct(message)`, whose
+/// keccak-256 hash is signed
+pub fn eip712_message(
+    domain_separator: &[u8; 32],
+    struct_hash: &[u8; 32],
+) -> [u8; EIP712_MESSAGE_LENGTH] {
+    let mut message = [0; EIP712_MESSAGE_LENGTH];
+    message[..2].copy_from_slice(b"\x19\x01");
+    message[2..34].copy_from_slice(domain_separator);
+    message[34..].copy_from_slice(struct_hash);
+    message
+}
+
+/// The hash signed for a struct with `struct_hash` in the domain with
+/// `domain_separator`
+pub fn eip712_hash(domain_separator: &[u8; 32], struct_hash: &[u8; 32]) -> [u8; 32] {
+    hash(&eip712_message(domain_separator, struct_hash)).to_bytes()
+}
+
+/// The hash signed for `message`, a struct of type `primary_type`, in
+/// `domain`
+pub fn hash_typed_data(
+    domain: &Eip712Domain,
+    types: &Eip712Types,
+    primary_type: &str,
+    message: &BTreeMap<String, Eip712Value>,
+) -> Result<[u8; 32], Eip712Error> {
+    Ok(eip712_hash(
+        &domain.separator(),
+        &hash_struct(types, primary_type, message)?,
+    ))
+}
+
+/// Split the array dimensions off a type, `Person[][2]` into `Person` and
+/// `[][2]`
+fn base_type(r#type: &str) -> &str {
+    r#type.find('[').map_or(r#type, |index| &r#type[..index])
+}
+
+fn collect_dependencies<'a>(
+    types: &'a Eip712Types,
+    name: &'a str,
+    dependencies: &mut BTreeSet<&'a str>,
+) -> Result<(), Eip712Error> {
+    let fields = types
+        .get(name)
+        .ok_or_else(|| Eip712Error::UnknownType(name.to_string()))?;
+    for field in fields {
+        let base = base_type(&field.r#type);
+        if types.contains_key(base) {
+            if dependencies.insert(base) {
+                collect_dependencies(types, base, dependencies)?;
+            }
+        } else if !is_atomic_or_dynamic(base) {
+            return Err(Eip712Error::UnknownType(base.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// The encoding of a struct type, its signature followed by those of the
+/// struct types it references, sorted by name, like
+/// `Mail(Person from,Person to,string contents)Person(string name,address wallet)`
+pub fn encode_type(types: &Eip712Types, primary_type: &str) -> Result<String, Eip712Error> {
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(types, primary_type, &mut dependencies)?;
+    dependencies.remove(primary_type);
+
+    let mut encoded = String::new();
+    for name in std::iter::once(primary_type).chain(dependencies) {
+        let fields: Vec<String> = types[name]
+            .iter()
+            .map(|field| format!("{} {}", field.r#type, field.name))
+            .collect();
+        encoded.push_str(&format!("{name}({})", fields.join(",")));
+    }
+    Ok(encoded)
+}
+
+/// The type hash of a struct type, the keccak-256 hash of its encoding
+pub fn type_hash(types: &Eip712Types, primary_type: &str) -> Result<[u8; 32], Eip712Error> {
+    Ok(hash(encode_type(types, primary_type)?.as_bytes()).to_bytes())
+}
+
+/// The encoding of the fields of a struct, without its type hash
+pub fn encode_data(
+    types: &Eip712Types,
+    primary_type: &str,
+    values: &BTreeMap<String, Eip712Value>,
+) -> Result<Vec<u8>, Eip712Error> {
+    let fields = types
+        .get(primary_type)
+        .ok_or_else(|| Eip712Error::UnknownType(primary_type.to_string()))?;
+    if let Some(name) = values
+        .keys()
+        .find(|name| !fields.iter().any(|field| field.name == **name))
+    {
+        return Err(Eip712Error::UnexpectedField(name.clone()));
+    }
+
+    let mut encoded = Vec::with_capacity(fields.len().saturating_mul(32));
+    for field in fields {
+        let value = values
+            .get(&field.name)
+            .ok_or_else(|| Eip712Error::MissingField(field.name.clone()))?;
+        encoded.extend_from_slice(&encode_value(types, &field.r#type, value)?);
+    }
+    Ok(encoded)
+}
+
+/// The struct hash of a struct of type `primary_type`,
+/// `keccak256(typeHash || encodeData(s))`
+pub fn hash_struct(
+    types: &Eip712Types,
+    primary_type: &str,
+    values: &BTreeMap<String, Eip712Value>,
+) -> Result<[u8; 32], Eip712Error> {
+    let type_hash = type_hash(types, primary_type)?;
+    let data = encode_data(types, primary_type, values)?;
+    Ok(hashv(&[&type_hash, &data]).to_bytes())
+}
+
+fn is_atomic_or_dynamic(r#type: &str) -> bool {
+    matches!(r#type, "bool" | "address" | "bytes" | "string")
+        || integer_bits(r#type).is_some()
+        || fixed_bytes_len(r#type).is_some()
+}
+
+/// Parse a decimal size, without sign or leading zeros
+fn parse_size(size: &str) -> Option<usize> {
+    if size.starts_with('0') || !size.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    size.parse().ok()
+}
+
+/// The size of a `uintN` or `intN` type, and whether it is signed
+fn integer_bits(r#type: &str) -> Option<(usize, bool)> {
+    let (bits, signed) = match r#type.strip_prefix("uint") {
+        Some(bits) => (bits, false),
+        None => (r#type.strip_prefix("int")?, true),
+    };
+    let bits = parse_size(bits)?;
+    (bits % 8 == 0 && bits <= 256).then_some((bits, signed))
+}
+
+/// The size of a `bytesN` type
+fn fixed_bytes_len(r#type: &str) -> Option<usize> {
+    let len = parse_size(r#type.strip_prefix("bytes")?)?;
+    (1..=32).contains(&len).then_some(len)
+}
+
+/// The 32-byte encoding of a value of type `type`
+fn encode_value(
+    types: &Eip712Types,
+    r#type: &str,
+    value: &Eip712Value,
+) -> Result<[u8; 32], Eip712Error> {
+    let mismatch = || Eip712Error::TypeMismatch(r#type.to_string());
+    let mut encoded = [0; 32];
+
+    // Arrays hash the concatenation of the encodings of their elements
+    if let Some(element_type) = r#type.strip_suffix(']') {
+        let (element_type, len) = element_type.rsplit_once('[').ok_or_else(mismatch)?;
+        let Eip712Value::Array(elements) = value else {
+            return Err(mismatch());
+        };
+        if !len.is_empty() && parse_size(len) != Some(elements.len()) {
+            return Err(mismatch());
+        }
+        let mut data = Vec::with_capacity(elements.len().saturating_mul(32));
+        for element in elements {
+            data.extend_from_slice(&encode_value(types, element_type, element)?);
+        }
+        return Ok(hash(&data).to_bytes());
+    }
+
+    match value {
+        Eip712Value::Struct(values) if types.contains_key(r#type) => {
+            return hash_struct(types, r#type, values);
+        }
+        Eip712Value::Bool(value) if r#type == "bool" => encoded[31] = u8::from(*value),
+        Eip712Value::Address(address) if r#type == "address" => {
+            encoded[12..].copy_from_slice(address)
+        }
+        Eip712Value::Bytes(bytes) if r#type == "bytes" => return Ok(hash(bytes).to_bytes()),
+        Eip712Value::String(string) if r#type == "string" => {
+            return Ok(hash(string.as_bytes()).to_bytes())
+        }
+        Eip712Value::FixedBytes(bytes) if fixed_bytes_len(r#type) == Some(bytes.len()) => {
+            encoded[..bytes.len()].copy_from_slice(bytes)
+        }
+        Eip712Value::Uint(integer) | Eip712Value::Int(integer) => {
+            let (bits, signed) = integer_bits(r#type).ok_or_else(mismatch)?;
+            if signed != matches!(value, Eip712Value::Int(_)) {
+                return Err(mismatch());
+            }
+            // The value must fit in N bits, so the bytes above must be its
+            // sign extension
+            let (high, low) = integer.split_at(32_usize.saturating_sub(bits / 8));
+            let extension = if signed && low[0] & 0x80 != 0 {
+                0xff
+            } else {
+                0
+            };
+            if high.iter().any(|byte| *byte != extension) {
+                return Err(mismatch());
+            }
+            encoded = *integer;
+        }
+        _ => return Err(mismatch()),
+    }
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::eth::recover_eth_address, hex::FromHex};
+
+    fn person(name: &str, wallet: &str) -> Eip712Value {
+        Eip712Value::Struct(BTreeMap::from([
+            ("name".to_string(), Eip712Value::String(name.to_string())),
+            (
+                "wallet".to_string(),
+                Eip712Value::Address(<[u8; 20]>::from_hex(wallet).unwrap()),
+            ),
+        ]))
+    }
+
+    /// The example of EIP-712
+    fn mail() -> (Eip712Domain, Eip712Types, BTreeMap<String, Eip712Value>) {
+        let domain = Eip712Domain {
+            name: Some("Ether Mail".to_string()),
+            version: Some("1".to_string()),
+            chain_id: Some(1),
+            verifying_contract: Some(
+                <[u8; 20]>::from_hex("cccccccccccccccccccccccccccccccccccccccc").unwrap(),
+            ),
+            salt: None,
+        };
+        let types = Eip712Types::from([
+            (
+                "Person".to_string(),
+                vec![
+                    Eip712Field::new("name", "string"),
+                    Eip712Field::new("wallet", "address"),
+                ],
+            ),
+            (
+                "Mail".to_string(),
+                vec![
+                    Eip712Field::new("from", "Person"),
+                    Eip712Field::new("to", "Person"),
+                    Eip712Field::new("contents", "string"),
+                ],
+            ),
+        ]);
+        let message = BTreeMap::from([
+            (
+                "from".to_string(),
+                person("Cow", "cd2a3d9f938e13cd947ec05abc7fe734df8dd826"),
+            ),
+            (
+                "to".to_string(),
+                person("Bob", "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"),
+            ),
+            (
+                "contents".to_string(),
+                Eip712Value::String("Hello, Bob!".to_string()),
+            ),
+        ]);
+        (domain, types, message)
+    }
+
+    #[test]
+    fn test_eip712_example() {
+        let (domain, types, message) = mail();
+        assert_eq!(
+            encode_type(&types, "Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            type_hash(&types, "Mail").unwrap(),
+            <[u8; 32]>::from_hex(
+                "a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            domain.separator(),
+            <[u8; 32]>::from_hex(
+                "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+            )
+            .unwrap()
+        );
+        let struct_hash = hash_struct(&types, "Mail", &message).unwrap();
+        assert_eq!(
+            struct_hash,
+            <[u8; 32]>::from_hex(
+                "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+            )
+            .unwrap()
+        );
+        let digest = hash_typed_data(&domain, &types, "Mail", &message).unwrap();
+        assert_eq!(
+            digest,
+            <[u8; 32]>::from_hex(
+                "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+            )
+            .unwrap()
+        );
+        assert_eq!(eip712_hash(&domain.separator(), &struct_hash), digest);
+
+        // Signed by the key keccak256("cow")
+        let signature = <[u8; 65]>::from_hex(
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562\
+             1c",
+        )
+        .unwrap();
+        assert_eq!(
+            recover_eth_address(&digest, &signature),
+            Ok(<[u8; 20]>::from_hex("cd2a3d9f938e13cd947ec05abc7fe734df8dd826").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_encode_value() {
+        let types = Eip712Types::new();
+        for (r#type, value) in [
+            ("uint8", Eip712Value::from_u64(255)),
+            ("uint256", Eip712Value::Uint([0xff; 32])),
+            ("int8", Eip712Value::from_i64(-128)),
+            ("int64", Eip712Value::from_i64(i64::MIN)),
+            ("bytes4", Eip712Value::FixedBytes(vec![1, 2, 3, 4])),
+            ("bool", Eip712Value::Bool(true)),
+        ] {
+            assert!(encode_value(&types, r#type, &value).is_ok(), "{type}");
+        }
+        assert_eq!(
+            encode_value(&types, "bytes4", &Eip712Value::FixedBytes(vec![1, 2, 3, 4])).unwrap()
+                [..5],
+            [1, 2, 3, 4, 0]
+        );
+        assert_eq!(
+            encode_value(&types, "int16", &Eip712Value::from_i64(-1)).unwrap(),
+            [0xff; 32]
+        );
+
+        for (r#type, value) in [
+            ("uint8", Eip712Value::from_u64(256)),
+            ("int8", Eip712Value::from_i64(128)),
+            ("int8", Eip712Value::from_i64(-129)),
+            ("uint7", Eip712Value::from_u64(1)),
+            ("uint264", Eip712Value::from_u64(1)),
+            ("uint+8", Eip712Value::from_u64(1)),
+            ("uint08", Eip712Value::from_u64(1)),
+            ("int256", Eip712Value::from_u64(1)),
+            ("bytes4", Eip712Value::FixedBytes(vec![1, 2, 3])),
+            ("bytes33", Eip712Value::FixedBytes(vec![0; 33])),
+            ("string", Eip712Value::Bytes(vec![])),
+            ("Person", Eip712Value::Struct(BTreeMap::new())),
+        ] {
+            assert_eq!(
+                encode_value(&types, r#type, &value),
+                Err(Eip712Error::TypeMismatch(r#type.to_string()))
+            );
+        }
+
+        // Arrays hash the encodings of their elements
+        let elements = vec![Eip712Value::from_u64(1), Eip712Value::from_u64(2)];
+        let mut data = [0; 64];
+        data[31] = 1;
+        data[63] = 2;
+        for r#type in ["uint8[]", "uint8[2]"] {
+            assert_eq!(
+                encode_value(&types, r#type, &Eip712Value::Array(elements.clone())),
+                Ok(hash(&data).to_bytes())
+            );
+        }
+        assert!(encode_value(&types, "uint8[3]", &Eip712Value::Array(elements)).is_err());
+    }
+
+    #[test]
+    fn test_hash_struct_errors() {
+        let (_, mut types, mut message) = mail();
+        assert_eq!(
+            hash_struct(&types, "Letter", &message),
+            Err(Eip712Error::UnknownType("Letter".to_string()))
+        );
+
+        message.insert("date".to_string(), Eip712Value::from_u64(0));
+        assert_eq!(
+            hash_struct(&types, "Mail", &message),
+            Err(Eip712Error::UnexpectedField("date".to_string()))
+        );
+        message.remove("date");
+        message.remove("contents");
+        assert_eq!(
+            hash_struct(&types, "Mail", &message),
+            Err(Eip712Error::MissingField("contents".to_string()))
+        );
+
+        types
+            .get_mut("Person")
+            .unwrap()
+            .push(Eip712Field::new("pet", "Animal"));
+        assert_eq!(
+            encode_type(&types, "Mail"),
+            Err(Eip712Error::UnknownType("Animal".to_string()))
+        );
+    }
+}
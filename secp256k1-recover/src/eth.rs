@@ -0,0 +1,169 @@
+//! Ethereum-compatible signatures.
+//!
+//! Ethereum wallets sign the keccak-256 hash of a message, either an
+//! [EIP-191] `personal_sign` message or [EIP-712] typed data (see
+//! [`eip712`](crate::eip712)), and encode signatures in 65 bytes as
+//! `r || s || v`, where `v` is the recovery ID, plus 27 for most wallets.
+//! Signers are identified by their Ethereum address, the last 20 bytes of
+//! the keccak-256 hash of their public key.
+//!
+//! [`recover_eth_address`] recovers the address of a signer on-chain with the
+//! [`secp256k1_recover`] syscall. Unlike the syscall, it rejects signatures
+//! with a high `s`, as Ethereum does since [EIP-2], so that signatures can't
+//! be altered into other valid signatures.
+//!
+//! [EIP-191]: https://eips.ethereum.org/EIPS/eip-191
+//! [EIP-712]: https://eips.ethereum.org/EIPS/eip-712
+//! [EIP-2]: https://eips.ethereum.org/EIPS/eip-2
+
+use {
+    crate::{
+        secp256k1_recover, Secp256k1Pubkey, Secp256k1RecoverError, SECP256K1_SIGNATURE_LENGTH,
+    },
+    solana_keccak_hasher::{hash, hashv},
+};
+
+/// The length of an Ethereum address
+pub const ETH_ADDRESS_LENGTH: usize = 20;
+
+/// The length of an Ethereum signature, `r || s || v`
+pub const ETH_SIGNATURE_LENGTH: usize = 65;
+
+/// Half the order of the secp256k1 group, the largest `s` of a signature
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// The EIP-191 `personal_sign` message of `message`,
+/// `"\x19Ethereum Signed Message:\n" || len(message) || message`, where the
+/// length is in decimal. Its keccak-256 hash is what wallets sign.
+pub fn eip191_message(message: &[u8]) -> Vec<u8> {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    prefixed
+}
+
+/// The hash signed by `personal_sign` for `message`
+pub fn eip191_hash(message: &[u8]) -> [u8; 32] {
+    let length = message.len().to_string();
+    hashv(&[
+        b"\x19Ethereum Signed Message:\n",
+        length.as_bytes(),
+        message,
+    ])
+    .to_bytes()
+}
+
+/// The Ethereum address of a public key
+pub fn eth_address_from_pubkey(pubkey: &Secp256k1Pubkey) -> [u8; ETH_ADDRESS_LENGTH] {
+    let mut address = [0; ETH_ADDRESS_LENGTH];
+    address.copy_from_slice(&hash(&pubkey.0).to_bytes()[12..]);
+    address
+}
+
+/// Split an Ethereum signature, `r || s || v`, into the signature `r || s`
+/// and its recovery ID. `v` can be 0, 1, 27 or 28, and `s` must be low, as
+/// Ethereum requires since EIP-2.
+pub fn parse_eth_signature(
+    signature: &[u8; ETH_SIGNATURE_LENGTH],
+) -> Result<([u8; SECP256K1_SIGNATURE_LENGTH], u8), Secp256k1RecoverError> {
+    let (signature, v) = signature.split_at(SECP256K1_SIGNATURE_LENGTH);
+    let recovery_id = match v[0] {
+        0 | 1 => v[0],
+        27 | 28 => v[0].saturating_sub(27),
+        _ => return Err(Secp256k1RecoverError::InvalidRecoveryId),
+    };
+    // Big-endian with the same length, so the comparison is numeric
+    if signature[32..] > HALF_ORDER[..] {
+        return Err(Secp256k1RecoverError::InvalidSignature);
+    }
+    Ok((signature.try_into().unwrap(), recovery_id))
+}
+
+/// Recover the Ethereum address that signed `hash`. The signature is parsed
+/// by [`parse_eth_signature`].
+pub fn recover_eth_address(
+    hash: &[u8; 32],
+    signature: &[u8; ETH_SIGNATURE_LENGTH],
+) -> Result<[u8; ETH_ADDRESS_LENGTH], Secp256k1RecoverError> {
+    let (signature, recovery_id) = parse_eth_signature(signature)?;
+    let pubkey = secp256k1_recover(hash, recovery_id, &signature)?;
+    Ok(eth_address_from_pubkey(&pubkey))
+}
+
+/// Whether `signature` is a valid signature of `hash` by `eth_address`
+pub fn verify_eth_signature(
+    hash: &[u8; 32],
+    signature: &[u8; ETH_SIGNATURE_LENGTH],
+    eth_address: &[u8; ETH_ADDRESS_LENGTH],
+) -> bool {
+    recover_eth_address(hash, signature).is_ok_and(|address| address == *eth_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, hex::FromHex};
+
+    #[test]
+    fn test_eip191_hash() {
+        assert_eq!(
+            eip191_hash(b"hello world"),
+            <[u8; 32]>::from_hex(
+                "d9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            hash(&eip191_message(b"hello world")).to_bytes(),
+            eip191_hash(b"hello world")
+        );
+        assert_eq!(
+            eip191_message(b"hello world"),
+            b"\x19Ethereum Signed Message:\n11hello world"
+        );
+    }
+
+    #[test]
+    fn test_recover_eth_address() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let eth_address =
+            eth_address_from_pubkey(&Secp256k1Pubkey::new(&public_key.serialize()[1..]));
+
+        let hash = eip191_hash(b"bridge transfer #1");
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret_key);
+        let mut eth_signature = [0; ETH_SIGNATURE_LENGTH];
+        eth_signature[..64].copy_from_slice(&signature.serialize());
+        for v in [
+            recovery_id.serialize(),
+            recovery_id.serialize().saturating_add(27),
+        ] {
+            eth_signature[64] = v;
+            assert_eq!(recover_eth_address(&hash, &eth_signature), Ok(eth_address));
+            assert!(verify_eth_signature(&hash, &eth_signature, &eth_address));
+        }
+        assert!(!verify_eth_signature(
+            &eip191_hash(b"bridge transfer #2"),
+            &eth_signature,
+            &eth_address
+        ));
+
+        eth_signature[64] = 29;
+        assert_eq!(
+            recover_eth_address(&hash, &eth_signature),
+            Err(Secp256k1RecoverError::InvalidRecoveryId)
+        );
+
+        // The same signature with s replaced by n - s is rejected
+        let mut high_s = signature;
+        high_s.s = -high_s.s;
+        eth_signature[..64].copy_from_slice(&high_s.serialize());
+        eth_signature[64] = recovery_id.serialize() ^ 1;
+        assert_eq!(
+            recover_eth_address(&hash, &eth_signature),
+            Err(Secp256k1RecoverError::InvalidSignature)
+        );
+    }
+}
@@ -25,6 +25,11 @@
 //! [sp]: https://docs.rs/solana-program/latest/solana_program/secp256k1_program/
 //! [`ecrecover`]: https://docs.soliditylang.org/en/v0.8.14/units-and-global-variables.html?highlight=ecrecover#mathematical-and-cryptographic-functions
 
+#[cfg(feature = "eth")]
+pub mod eip712;
+#[cfg(feature = "eth")]
+pub mod eth;
+
 #[cfg(feature = "borsh")]
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use {core::convert::TryFrom, thiserror::Error};
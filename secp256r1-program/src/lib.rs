@@ -9,8 +9,22 @@
 //! This property can be problematic for developers who assume each signature is unique. Without enforcing
 //! low-S values, the same message and key can produce two different valid signatures, potentially breaking
 //! replay protection schemes that rely on signature uniqueness.
-pub use badchain_sdk_ids::secp256r1_program::{check_id, id, ID};
-use bytemuck::{Pod, Zeroable};
+pub use {
+    badchain_sdk_ids::secp256r1_program::{check_id, id, ID},
+    solana_instructions_sysvar::precompile::DataRef,
+};
+use {
+    bytemuck::{Pod, Zeroable},
+    solana_account_info::AccountInfo,
+    solana_instructions_sysvar::precompile,
+    solana_program_error::ProgramError,
+};
+
+pub const COMPRESSED_PUBKEY_SERIALIZED_SIZE: usize = 33;
+pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
+pub const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
+pub const SIGNATURE_OFFSETS_START: usize = 2;
+pub const DATA_START: usize = SIGNATURE_OFFSETS_SERIALIZED_SIZE + SIGNATURE_OFFSETS_START;
 
 #[derive(Default, Debug, Copy, Clone, Zeroable, Pod, Eq, PartialEq)]
 #[repr(C)]
@@ -37,21 +51,72 @@ pub struct Secp256r1SignatureOffsets {
     pub message_instruction_index: u16,
 }
 
+/// A signature verified by an secp256r1 instruction, with its public key and
+/// message
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedSignature {
+    pub signature: [u8; SIGNATURE_SERIALIZED_SIZE],
+    pub pubkey: [u8; COMPRESSED_PUBKEY_SERIALIZED_SIZE],
+    pub message: Vec<u8>,
+}
+
+/// Load the signatures verified by the secp256r1 instruction at `index` in the
+/// current transaction, from the instructions sysvar.
+///
+/// A transaction only executes if all of its secp256r1 instructions succeed,
+/// so a program can trust the returned signatures. Data referenced in other
+/// instructions is read from those instructions.
+///
+/// # Errors
+///
+/// Returns [`ProgramError::IncorrectProgramId`] if the instruction at
+/// `index` isn't a secp256r1 instruction, and the errors of
+/// [`load_instruction_at_checked`] if the sysvar can't be read.
+///
+/// [`load_instruction_at_checked`]: solana_instructions_sysvar::load_instruction_at_checked
+pub fn load_verified_signatures(
+    index: usize,
+    instructions_sysvar: &AccountInfo,
+) -> Result<Vec<VerifiedSignature>, ProgramError> {
+    let verified = precompile::load_verified_signatures(
+        index,
+        instructions_sysvar,
+        &id(),
+        SIGNATURE_SERIALIZED_SIZE,
+        COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+    )?;
+    Ok(verified
+        .into_iter()
+        .map(|verified| VerifiedSignature {
+            signature: verified.signature.try_into().unwrap(),
+            pubkey: verified.pubkey.try_into().unwrap(),
+            message: verified.message,
+        })
+        .collect())
+}
+
 #[cfg(all(not(target_arch = "wasm32"), not(target_os = "solana")))]
 mod target_arch {
     use {
-        crate::Secp256r1SignatureOffsets,
+        crate::{
+            precompile, DataRef, Secp256r1SignatureOffsets, COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+            DATA_START, SIGNATURE_OFFSETS_SERIALIZED_SIZE, SIGNATURE_OFFSETS_START,
+            SIGNATURE_SERIALIZED_SIZE,
+        },
         bytemuck::bytes_of,
-        openssl::{bn::BigNum, ec::EcKey, ecdsa::EcdsaSig, nid::Nid, pkey::PKey, sign::Signer},
+        openssl::{
+            bn::{BigNum, BigNumContext},
+            ec::{EcGroup, EcKey, EcPoint},
+            ecdsa::EcdsaSig,
+            hash::MessageDigest,
+            nid::Nid,
+            pkey::PKey,
+            sign::{Signer, Verifier},
+        },
         solana_instruction::Instruction,
+        solana_precompile_error::PrecompileError,
     };
 
-    pub const COMPRESSED_PUBKEY_SERIALIZED_SIZE: usize = 33;
-    pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
-    pub const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
-    pub const SIGNATURE_OFFSETS_START: usize = 2;
-    pub const DATA_START: usize = SIGNATURE_OFFSETS_SERIALIZED_SIZE + SIGNATURE_OFFSETS_START;
-
     // Order as defined in SEC2: 2.7.2 Recommended Parameters secp256r1
     pub const SECP256R1_ORDER: [u8; FIELD_SIZE] = [
         0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
@@ -74,6 +139,8 @@ mod target_arch {
     ];
     // Field size in bytes
     pub const FIELD_SIZE: usize = 32;
+    // Max number of signatures in a single instruction
+    pub const MAX_SIGNATURES: usize = 8;
 
     pub fn sign_message(
         message: &[u8],
@@ -163,6 +230,468 @@ mod target_arch {
             data: instruction_data,
         }
     }
+
+    /// Builder of a secp256r1 instruction verifying up to [`MAX_SIGNATURES`]
+    /// signatures.
+    ///
+    /// Each signature, public key and message is either appended to the
+    /// instruction or found in another instruction of the same transaction,
+    /// and [`Secp256r1InstructionBuilder::build`] fills in the
+    /// [`Secp256r1SignatureOffsets`] of every signature. Identical inline data,
+    /// like a message signed by several keys, is only appended once.
+    ///
+    /// The builder can't check references to other instructions against their
+    /// data, [`verify`] the built instruction with the data of every instruction
+    /// of the transaction for that.
+    #[derive(Debug, Default, Clone)]
+    pub struct Secp256r1InstructionBuilder<'a> {
+        // signature, public key and message of each signature
+        signatures: Vec<[DataRef<'a>; 3]>,
+    }
+
+    impl<'a> Secp256r1InstructionBuilder<'a> {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Add a signature to verify, with its data appended to the instruction
+        pub fn add_signature(
+            &mut self,
+            message: &'a [u8],
+            signature: &'a [u8; SIGNATURE_SERIALIZED_SIZE],
+            pubkey: &'a [u8; COMPRESSED_PUBKEY_SERIALIZED_SIZE],
+        ) -> &mut Self {
+            self.add_signature_ref(
+                DataRef::Inline(message),
+                DataRef::Inline(signature),
+                DataRef::Inline(pubkey),
+            )
+        }
+
+        /// Add a signature to verify, with its data wherever `message`,
+        /// `signature` and `pubkey` refer to
+        pub fn add_signature_ref(
+            &mut self,
+            message: DataRef<'a>,
+            signature: DataRef<'a>,
+            pubkey: DataRef<'a>,
+        ) -> &mut Self {
+            self.signatures.push([signature, pubkey, message]);
+            self
+        }
+
+        /// Build the instruction, checking that the offsets and sizes of all
+        /// data fit the instruction format
+        pub fn build(&self) -> Result<Instruction, PrecompileError> {
+            if self.signatures.is_empty() || self.signatures.len() > MAX_SIGNATURES {
+                return Err(PrecompileError::InvalidInstructionDataSize);
+            }
+            Ok(Instruction {
+                program_id: crate::id(),
+                accounts: vec![],
+                data: precompile::build_instruction_data(
+                    &self.signatures,
+                    SIGNATURE_SERIALIZED_SIZE,
+                    COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+                )?,
+            })
+        }
+    }
+
+    /// Verify the signatures of a secp256r1 instruction, as the runtime does,
+    /// including the requirement of low-S signatures.
+    ///
+    /// `data` is the instruction data of the secp256r1 instruction and
+    /// `instruction_datas` the instruction data of every instruction of the
+    /// transaction, that the offsets can refer to by index. An index of
+    /// `u16::MAX` refers to `data` itself.
+    pub fn verify(data: &[u8], instruction_datas: &[&[u8]]) -> Result<(), PrecompileError> {
+        if data.len() < SIGNATURE_OFFSETS_START {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+        let num_signatures = data[0] as usize;
+        if num_signatures == 0 || num_signatures > MAX_SIGNATURES {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+        let expected_data_size = num_signatures
+            .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .saturating_add(SIGNATURE_OFFSETS_START);
+        // We do not check or use the byte at data[1]
+        if data.len() < expected_data_size {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+
+        let half_order = BigNum::from_slice(&SECP256R1_HALF_ORDER)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+        let order_minus_one = BigNum::from_slice(&SECP256R1_ORDER_MINUS_ONE)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+        let one = BigNum::from_u32(1).map_err(|_| PrecompileError::InvalidSignature)?;
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+        let mut ctx = BigNumContext::new().map_err(|_| PrecompileError::InvalidSignature)?;
+
+        for i in 0..num_signatures {
+            let start = i
+                .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+                .saturating_add(SIGNATURE_OFFSETS_START);
+            let end = start.saturating_add(SIGNATURE_OFFSETS_SERIALIZED_SIZE);
+            let offsets: Secp256r1SignatureOffsets =
+                bytemuck::pod_read_unaligned(&data[start..end]);
+
+            // Parse out signature
+            let signature = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.signature_instruction_index,
+                offsets.signature_offset,
+                SIGNATURE_SERIALIZED_SIZE,
+            )?;
+
+            // Parse out pubkey
+            let pubkey = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.public_key_instruction_index,
+                offsets.public_key_offset,
+                COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+            )?;
+
+            // Parse out message
+            let message = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.message_instruction_index,
+                offsets.message_data_offset,
+                offsets.message_data_size as usize,
+            )?;
+
+            let r_bignum = BigNum::from_slice(&signature[..FIELD_SIZE])
+                .map_err(|_| PrecompileError::InvalidSignature)?;
+            let s_bignum = BigNum::from_slice(&signature[FIELD_SIZE..])
+                .map_err(|_| PrecompileError::InvalidSignature)?;
+
+            // Check that r is in [1, order - 1] and s in [1, half_order]
+            let within_range = r_bignum >= one
+                && r_bignum <= order_minus_one
+                && s_bignum >= one
+                && s_bignum <= half_order;
+            if !within_range {
+                return Err(PrecompileError::InvalidSignature);
+            }
+
+            let ecdsa_sig = EcdsaSig::from_private_components(r_bignum, s_bignum)
+                .and_then(|sig| sig.to_der())
+                .map_err(|_| PrecompileError::InvalidSignature)?;
+
+            let public_key_point = EcPoint::from_bytes(&group, pubkey, &mut ctx)
+                .map_err(|_| PrecompileError::InvalidPublicKey)?;
+            let public_key = EcKey::from_public_key(&group, &public_key_point)
+                .map_err(|_| PrecompileError::InvalidPublicKey)?;
+            let public_key_as_pkey =
+                PKey::from_ec_key(public_key).map_err(|_| PrecompileError::InvalidPublicKey)?;
+
+            let mut verifier = Verifier::new(MessageDigest::sha256(), &public_key_as_pkey)
+                .map_err(|_| PrecompileError::InvalidSignature)?;
+            verifier
+                .update(message)
+                .map_err(|_| PrecompileError::InvalidSignature)?;
+
+            if !verifier
+                .verify(&ecdsa_sig)
+                .map_err(|_| PrecompileError::InvalidSignature)?
+            {
+                return Err(PrecompileError::InvalidSignature);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_data_slice<'a>(
+        data: &'a [u8],
+        instruction_datas: &'a [&[u8]],
+        instruction_index: u16,
+        offset_start: u16,
+        size: usize,
+    ) -> Result<&'a [u8], PrecompileError> {
+        let instruction = if instruction_index == u16::MAX {
+            data
+        } else {
+            let signature_index = instruction_index as usize;
+            if signature_index >= instruction_datas.len() {
+                return Err(PrecompileError::InvalidDataOffsets);
+            }
+            instruction_datas[signature_index]
+        };
+
+        let start = offset_start as usize;
+        let end = start.saturating_add(size);
+        if end > instruction.len() {
+            return Err(PrecompileError::InvalidDataOffsets);
+        }
+
+        Ok(&instruction[start..end])
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use {
+            super::*,
+            crate::{load_verified_signatures, VerifiedSignature},
+            openssl::ec::PointConversionForm,
+            solana_account_info::AccountInfo,
+            solana_instruction::BorrowedInstruction,
+            solana_program_error::ProgramError,
+        };
+
+        fn generate_key() -> (Vec<u8>, [u8; COMPRESSED_PUBKEY_SERIALIZED_SIZE]) {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            let signing_key = EcKey::generate(&group).unwrap();
+            let mut ctx = BigNumContext::new().unwrap();
+            let pubkey = signing_key
+                .public_key()
+                .to_bytes(&group, PointConversionForm::COMPRESSED, &mut ctx)
+                .unwrap();
+            (
+                signing_key.private_key_to_der().unwrap(),
+                pubkey.try_into().unwrap(),
+            )
+        }
+
+        fn test_case(
+            num_signatures: u16,
+            offsets: &Secp256r1SignatureOffsets,
+        ) -> Result<(), PrecompileError> {
+            let mut instruction_data = vec![0u8; DATA_START];
+            instruction_data[0..SIGNATURE_OFFSETS_START].copy_from_slice(bytes_of(&num_signatures));
+            instruction_data[SIGNATURE_OFFSETS_START..DATA_START]
+                .copy_from_slice(bytes_of(offsets));
+            verify(&instruction_data, &[&[0u8; 100]])
+        }
+
+        #[test]
+        fn test_verify() {
+            let message = b"hello";
+            let (secret_key, pubkey) = generate_key();
+            let signature = sign_message(message, &secret_key).unwrap();
+            let instruction =
+                new_secp256r1_instruction_with_signature(message, &signature, &pubkey);
+            assert_eq!(verify(&instruction.data, &[]), Ok(()));
+
+            let mut invalid = instruction.data.clone();
+            *invalid.last_mut().unwrap() ^= 1;
+            assert_eq!(
+                verify(&invalid, &[]),
+                Err(PrecompileError::InvalidSignature)
+            );
+
+            // The same signature, with its data in other instructions
+            let offsets = Secp256r1SignatureOffsets {
+                signature_offset: 0,
+                signature_instruction_index: 1,
+                public_key_offset: 0,
+                public_key_instruction_index: 0,
+                message_data_offset: COMPRESSED_PUBKEY_SERIALIZED_SIZE as u16,
+                message_data_size: message.len() as u16,
+                message_instruction_index: 0,
+            };
+            let mut data = vec![1, 0];
+            data.extend_from_slice(bytes_of(&offsets));
+            let pubkey_and_message = [&pubkey[..], message].concat();
+            assert_eq!(verify(&data, &[&pubkey_and_message, &signature]), Ok(()));
+            assert_eq!(
+                verify(&data, &[&pubkey_and_message]),
+                Err(PrecompileError::InvalidDataOffsets)
+            );
+        }
+
+        #[test]
+        fn test_high_s() {
+            let message = b"hello";
+            let (secret_key, pubkey) = generate_key();
+            let mut signature = sign_message(message, &secret_key).unwrap();
+
+            // (r, order - s) is just as valid for ECDSA, but is rejected
+            let s = BigNum::from_slice(&signature[FIELD_SIZE..]).unwrap();
+            let order = BigNum::from_slice(&SECP256R1_ORDER).unwrap();
+            let mut high_s = BigNum::new().unwrap();
+            high_s.checked_sub(&order, &s).unwrap();
+            signature[FIELD_SIZE..].copy_from_slice(&high_s.to_vec_padded(32).unwrap());
+            let instruction =
+                new_secp256r1_instruction_with_signature(message, &signature, &pubkey);
+            assert_eq!(
+                verify(&instruction.data, &[]),
+                Err(PrecompileError::InvalidSignature)
+            );
+        }
+
+        #[test]
+        fn test_invalid_offsets() {
+            let offsets = Secp256r1SignatureOffsets::default();
+            assert_eq!(
+                test_case(0, &offsets),
+                Err(PrecompileError::InvalidInstructionDataSize)
+            );
+            assert_eq!(
+                test_case(MAX_SIGNATURES as u16 + 1, &offsets),
+                Err(PrecompileError::InvalidInstructionDataSize)
+            );
+            assert_eq!(
+                test_case(2, &offsets),
+                Err(PrecompileError::InvalidInstructionDataSize)
+            );
+
+            for offsets in [
+                Secp256r1SignatureOffsets {
+                    signature_instruction_index: 1,
+                    ..offsets
+                },
+                Secp256r1SignatureOffsets {
+                    public_key_instruction_index: 1,
+                    ..offsets
+                },
+                Secp256r1SignatureOffsets {
+                    message_instruction_index: 1,
+                    ..offsets
+                },
+                Secp256r1SignatureOffsets {
+                    signature_offset: 100 - SIGNATURE_SERIALIZED_SIZE as u16 + 1,
+                    ..offsets
+                },
+                Secp256r1SignatureOffsets {
+                    public_key_offset: u16::MAX,
+                    ..offsets
+                },
+                Secp256r1SignatureOffsets {
+                    message_data_offset: 100,
+                    message_data_size: 1,
+                    ..offsets
+                },
+            ] {
+                assert_eq!(
+                    test_case(1, &offsets),
+                    Err(PrecompileError::InvalidDataOffsets)
+                );
+            }
+
+            // A zero signature is out of range
+            assert_eq!(
+                test_case(1, &offsets),
+                Err(PrecompileError::InvalidSignature)
+            );
+        }
+
+        #[test]
+        fn test_builder() {
+            let message = b"proposal";
+            let keys: Vec<_> = (0..3).map(|_| generate_key()).collect();
+            let signatures: Vec<_> = keys
+                .iter()
+                .map(|(secret_key, _)| sign_message(message, secret_key).unwrap())
+                .collect();
+
+            let mut builder = Secp256r1InstructionBuilder::new();
+            assert_eq!(
+                builder.build(),
+                Err(PrecompileError::InvalidInstructionDataSize)
+            );
+            for ((_, pubkey), signature) in keys.iter().zip(&signatures) {
+                builder.add_signature(message, signature, pubkey);
+            }
+            let instruction = builder.build().unwrap();
+            assert_eq!(verify(&instruction.data, &[]), Ok(()));
+            // The message is only appended once
+            assert_eq!(
+                instruction.data.len(),
+                SIGNATURE_OFFSETS_START
+                    + 3 * (SIGNATURE_OFFSETS_SERIALIZED_SIZE
+                        + SIGNATURE_SERIALIZED_SIZE
+                        + COMPRESSED_PUBKEY_SERIALIZED_SIZE)
+                    + message.len()
+            );
+
+            let mut builder = Secp256r1InstructionBuilder::new();
+            for _ in 0..=MAX_SIGNATURES {
+                builder.add_signature(message, &signatures[0], &keys[0].1);
+            }
+            assert_eq!(
+                builder.build(),
+                Err(PrecompileError::InvalidInstructionDataSize)
+            );
+
+            let mut builder = Secp256r1InstructionBuilder::new();
+            builder.add_signature_ref(
+                DataRef::Inline(message),
+                DataRef::Inline(&signatures[0]),
+                DataRef::Inline(&keys[0].1[1..]),
+            );
+            assert_eq!(builder.build(), Err(PrecompileError::InvalidPublicKey));
+        }
+
+        #[test]
+        fn test_load_verified_signatures() {
+            let message = b"vote yes";
+            let (secret_key, pubkey) = generate_key();
+            let signature = sign_message(message, &secret_key).unwrap();
+
+            // The message is in the program instruction, at index 1
+            let program_id = badchain_sdk_ids::system_program::id();
+            let program_data = [&[7][..], message].concat();
+            let mut builder = Secp256r1InstructionBuilder::new();
+            builder.add_signature_ref(
+                DataRef::Instruction {
+                    instruction_index: 1,
+                    offset: 1,
+                    size: message.len() as u16,
+                },
+                DataRef::Inline(&signature),
+                DataRef::Inline(&pubkey),
+            );
+            let instruction = builder.build().unwrap();
+            assert_eq!(
+                verify(&instruction.data, &[&instruction.data, &program_data]),
+                Ok(())
+            );
+
+            let key = solana_instructions_sysvar::id();
+            let owner = badchain_sdk_ids::sysvar::id();
+            let mut lamports = 0;
+            let mut data = solana_instructions_sysvar::construct_instructions_data(&[
+                BorrowedInstruction {
+                    program_id: &instruction.program_id,
+                    accounts: vec![],
+                    data: &instruction.data,
+                },
+                BorrowedInstruction {
+                    program_id: &program_id,
+                    accounts: vec![],
+                    data: &program_data,
+                },
+            ]);
+            let account_info = AccountInfo::new(
+                &key,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                &owner,
+                false,
+                0,
+            );
+
+            assert_eq!(
+                load_verified_signatures(0, &account_info),
+                Ok(vec![VerifiedSignature {
+                    signature,
+                    pubkey,
+                    message: message.to_vec(),
+                }])
+            );
+            assert_eq!(
+                load_verified_signatures(1, &account_info),
+                Err(ProgramError::IncorrectProgramId)
+            );
+        }
+    }
 }
 
 pub use self::target_arch::*;
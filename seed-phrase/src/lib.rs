@@ -1,16 +1,26 @@
 //! Functions for generating keypairs from seed phrases.
-use hmac::Hmac;
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+#[cfg(feature = "bip39")]
+pub mod mnemonic;
 
+use {
+    core::fmt,
+    hmac::Hmac,
+    zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing},
+};
+
+/// Derive the seed of a seed phrase and passphrase. The seed is zeroized when
+/// it's dropped.
 pub fn generate_seed_from_seed_phrase_and_passphrase(
     seed_phrase: &str,
     passphrase: &str,
-) -> Vec<u8> {
+) -> Zeroizing<Vec<u8>> {
     const PBKDF2_ROUNDS: u32 = 2048;
     const PBKDF2_BYTES: usize = 64;
 
-    let salt = format!("mnemonic{passphrase}");
+    let salt = Zeroizing::new(format!("mnemonic{passphrase}"));
 
-    let mut seed = vec![0u8; PBKDF2_BYTES];
+    let mut seed = Zeroizing::new(vec![0u8; PBKDF2_BYTES]);
     pbkdf2::pbkdf2::<Hmac<sha2::Sha512>>(
         seed_phrase.as_bytes(),
         salt.as_bytes(),
@@ -19,3 +29,101 @@ pub fn generate_seed_from_seed_phrase_and_passphrase(
     );
     seed
 }
+
+/// A secret seed which is zeroized on drop.
+///
+/// The seed bytes are only reachable through [`SecretSeed::expose_secret`],
+/// which makes every use of the secret easy to find, and they are redacted
+/// from `Debug` output. `SecretSeed` deliberately doesn't implement `Clone`.
+#[derive(Default, PartialEq, Eq)]
+pub struct SecretSeed(Zeroizing<Vec<u8>>);
+
+impl SecretSeed {
+    /// Wrap seed bytes
+    pub fn new(seed: Vec<u8>) -> Self {
+        Self(Zeroizing::new(seed))
+    }
+
+    /// Derive the seed of a seed phrase and passphrase
+    pub fn from_seed_phrase_and_passphrase(seed_phrase: &str, passphrase: &str) -> Self {
+        Self(generate_seed_from_seed_phrase_and_passphrase(
+            seed_phrase,
+            passphrase,
+        ))
+    }
+
+    /// The seed bytes
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The length of the seed in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the seed has no bytes
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for SecretSeed {
+    fn from(seed: Vec<u8>) -> Self {
+        Self::new(seed)
+    }
+}
+
+impl From<Zeroizing<Vec<u8>>> for SecretSeed {
+    fn from(seed: Zeroizing<Vec<u8>>) -> Self {
+        Self(seed)
+    }
+}
+
+impl Zeroize for SecretSeed {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretSeed {}
+
+impl fmt::Debug for SecretSeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretSeed([REDACTED])")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_seed() {
+        let mut seed = SecretSeed::from_seed_phrase_and_passphrase("seed phrase", "passphrase");
+        assert_eq!(
+            seed.expose_secret(),
+            generate_seed_from_seed_phrase_and_passphrase("seed phrase", "passphrase").as_slice()
+        );
+        assert_eq!(seed.len(), 64);
+        assert_eq!(format!("{seed:?}"), "SecretSeed([REDACTED])");
+
+        seed.zeroize();
+        assert!(seed.is_empty());
+        assert_eq!(SecretSeed::from(vec![]), SecretSeed::default());
+    }
+
+    #[test]
+    fn test_seed_is_zeroized() {
+        let mut seed = generate_seed_from_seed_phrase_and_passphrase("seed phrase", "passphrase");
+        assert!(seed.iter().any(|byte| *byte != 0));
+        let capacity = seed.capacity();
+
+        // What `Zeroizing` does on drop, before the buffer is freed
+        seed.zeroize();
+        assert!(seed.is_empty());
+        // SAFETY: zeroize overwrote the whole capacity of the buffer with zeros
+        unsafe { seed.set_len(capacity) };
+        assert_eq!(*seed, vec![0; capacity]);
+    }
+}
@@ -0,0 +1,305 @@
+//! BIP-39 mnemonic seed phrases.
+//!
+//! [`generate_seed_from_seed_phrase_and_passphrase`] accepts any string, so a
+//! mistyped word silently derives a different seed. The functions in this
+//! module generate phrases from the standard BIP-39 wordlists and check the
+//! words and checksum of a phrase before deriving its seed. Phrases and
+//! passphrases are NFKD normalized as BIP-39 requires.
+//!
+//! [`generate_seed_from_seed_phrase_and_passphrase`]: crate::generate_seed_from_seed_phrase_and_passphrase
+
+pub use bip39::Language;
+use {
+    bip39::{ErrorKind, Mnemonic, MnemonicType},
+    core::fmt,
+    std::error::Error,
+    unicode_normalization::UnicodeNormalization,
+    zeroize::Zeroizing,
+};
+
+/// The number of words in a seed phrase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WordCount {
+    /// 128 bits of entropy
+    Words12,
+    /// 160 bits of entropy
+    Words15,
+    /// 192 bits of entropy
+    Words18,
+    /// 224 bits of entropy
+    Words21,
+    /// 256 bits of entropy
+    Words24,
+}
+
+impl WordCount {
+    /// The number of words
+    pub const fn word_count(self) -> usize {
+        match self {
+            Self::Words12 => 12,
+            Self::Words15 => 15,
+            Self::Words18 => 18,
+            Self::Words21 => 21,
+            Self::Words24 => 24,
+        }
+    }
+
+    /// The number of bits of entropy encoded by the words, not counting the
+    /// checksum
+    pub const fn entropy_bits(self) -> usize {
+        match self {
+            Self::Words12 => 128,
+            Self::Words15 => 160,
+            Self::Words18 => 192,
+            Self::Words21 => 224,
+            Self::Words24 => 256,
+        }
+    }
+
+    fn mnemonic_type(self) -> MnemonicType {
+        match self {
+            Self::Words12 => MnemonicType::Words12,
+            Self::Words15 => MnemonicType::Words15,
+            Self::Words18 => MnemonicType::Words18,
+            Self::Words21 => MnemonicType::Words21,
+            Self::Words24 => MnemonicType::Words24,
+        }
+    }
+}
+
+impl TryFrom<usize> for WordCount {
+    type Error = SeedPhraseError;
+
+    fn try_from(word_count: usize) -> Result<Self, Self::Error> {
+        match word_count {
+            12 => Ok(Self::Words12),
+            15 => Ok(Self::Words15),
+            18 => Ok(Self::Words18),
+            21 => Ok(Self::Words21),
+            24 => Ok(Self::Words24),
+            _ => Err(SeedPhraseError::InvalidWordCount(word_count)),
+        }
+    }
+}
+
+/// Reasons that a seed phrase is not a valid BIP-39 mnemonic
+///
+/// Errors never include the words of the phrase, so that they can be logged
+/// without leaking secrets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedPhraseError {
+    /// The phrase doesn't have 12, 15, 18, 21 or 24 words
+    InvalidWordCount(usize),
+    /// The word at this zero-based position is not in the wordlist
+    InvalidWord(usize),
+    /// The checksum encoded in the last word doesn't match the entropy
+    InvalidChecksum,
+}
+
+impl Error for SeedPhraseError {}
+
+impl fmt::Display for SeedPhraseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidWordCount(word_count) => {
+                write!(f, "invalid number of words in seed phrase: {word_count}")
+            }
+            Self::InvalidWord(position) => {
+                write!(f, "word {position} of seed phrase is not in the wordlist")
+            }
+            Self::InvalidChecksum => f.write_str("invalid seed phrase checksum"),
+        }
+    }
+}
+
+/// Generate a random seed phrase with `word_count` words from the wordlist of
+/// `language`
+pub fn generate_seed_phrase(word_count: WordCount, language: Language) -> Zeroizing<String> {
+    Zeroizing::new(Mnemonic::new(word_count.mnemonic_type(), language).into_phrase())
+}
+
+/// Normalize a seed phrase to the form that seeds are derived from: each word
+/// NFKD normalized and separated by a single space
+pub fn normalize_seed_phrase(seed_phrase: &str) -> Zeroizing<String> {
+    Zeroizing::new(normalize(seed_phrase.split_whitespace()))
+}
+
+/// NFKD normalize `words` and join them with spaces. The capacity of the
+/// result is computed up front, so that the buffer is never reallocated and
+/// no copies of the secret are left behind in freed memory.
+fn normalize<'a>(words: impl Iterator<Item = &'a str> + Clone) -> String {
+    let capacity = words
+        .clone()
+        .map(|word| {
+            word.nfkd()
+                .map(char::len_utf8)
+                .sum::<usize>()
+                .saturating_add(1)
+        })
+        .sum();
+    let mut normalized = String::with_capacity(capacity);
+    for word in words {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.extend(word.nfkd());
+    }
+    normalized
+}
+
+/// Check that `seed_phrase` is a BIP-39 mnemonic in `language`
+pub fn validate_seed_phrase(seed_phrase: &str, language: Language) -> Result<(), SeedPhraseError> {
+    let normalized = normalize_seed_phrase(seed_phrase);
+    let wordmap = language.wordmap();
+    let mut word_count: usize = 0;
+    for (position, word) in normalized
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .enumerate()
+    {
+        if wordmap.get_bits(word).is_err() {
+            return Err(SeedPhraseError::InvalidWord(position));
+        }
+        word_count = word_count.saturating_add(1);
+    }
+    WordCount::try_from(word_count)?;
+
+    match Mnemonic::validate(&normalized, language) {
+        Ok(()) => Ok(()),
+        Err(err) => match err.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::InvalidChecksum) => Err(SeedPhraseError::InvalidChecksum),
+            // Words and word count were checked above
+            _ => Err(SeedPhraseError::InvalidWordCount(word_count)),
+        },
+    }
+}
+
+/// Derive the seed of a BIP-39 mnemonic, after checking that `seed_phrase`
+/// is a valid mnemonic in `language`. The seed is zeroized when it's dropped.
+///
+/// Unlike [`generate_seed_from_seed_phrase_and_passphrase`], the phrase and
+/// passphrase are NFKD normalized first, so equivalent unicode input always
+/// derives the same seed.
+///
+/// [`generate_seed_from_seed_phrase_and_passphrase`]: crate::generate_seed_from_seed_phrase_and_passphrase
+pub fn generate_seed_from_seed_phrase_and_passphrase_checked(
+    seed_phrase: &str,
+    passphrase: &str,
+    language: Language,
+) -> Result<Zeroizing<Vec<u8>>, SeedPhraseError> {
+    validate_seed_phrase(seed_phrase, language)?;
+    let passphrase = Zeroizing::new(normalize(core::iter::once(passphrase)));
+    Ok(crate::generate_seed_from_seed_phrase_and_passphrase(
+        &normalize_seed_phrase(seed_phrase),
+        &passphrase,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, bip39::Seed};
+
+    const WORD_COUNTS: [WordCount; 5] = [
+        WordCount::Words12,
+        WordCount::Words15,
+        WordCount::Words18,
+        WordCount::Words21,
+        WordCount::Words24,
+    ];
+
+    #[test]
+    fn test_generate_seed_phrase() {
+        for word_count in WORD_COUNTS {
+            for language in [Language::English, Language::Japanese, Language::Spanish] {
+                let seed_phrase = generate_seed_phrase(word_count, language);
+                assert_eq!(
+                    seed_phrase.split_whitespace().count(),
+                    word_count.word_count()
+                );
+                assert_eq!(validate_seed_phrase(&seed_phrase, language), Ok(()));
+
+                let mnemonic = Mnemonic::from_phrase(&seed_phrase, language).unwrap();
+                assert_eq!(mnemonic.entropy().len() * 8, word_count.entropy_bits());
+                assert_eq!(
+                    generate_seed_from_seed_phrase_and_passphrase_checked(
+                        &seed_phrase,
+                        "passphrase",
+                        language
+                    ),
+                    Ok(Zeroizing::new(
+                        Seed::new(&mnemonic, "passphrase").as_bytes().to_vec()
+                    ))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_seed_phrase() {
+        let seed_phrase = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                           abandon abandon abandon about";
+        assert_eq!(validate_seed_phrase(seed_phrase, Language::English), Ok(()));
+        assert_eq!(
+            validate_seed_phrase(&seed_phrase.replace(' ', "  \n"), Language::English),
+            Ok(())
+        );
+        assert_eq!(
+            validate_seed_phrase(&seed_phrase.replace("about", "abandon"), Language::English),
+            Err(SeedPhraseError::InvalidChecksum)
+        );
+        assert_eq!(
+            validate_seed_phrase(&seed_phrase.replace("about", "abuot"), Language::English),
+            Err(SeedPhraseError::InvalidWord(11))
+        );
+        assert_eq!(
+            validate_seed_phrase(&seed_phrase[8..], Language::English),
+            Err(SeedPhraseError::InvalidWordCount(11))
+        );
+        assert_eq!(
+            validate_seed_phrase("", Language::English),
+            Err(SeedPhraseError::InvalidWordCount(0))
+        );
+        assert_eq!(
+            validate_seed_phrase(seed_phrase, Language::Korean),
+            Err(SeedPhraseError::InvalidWord(0))
+        );
+        assert_eq!(WordCount::try_from(24), Ok(WordCount::Words24));
+        assert_eq!(
+            WordCount::try_from(13),
+            Err(SeedPhraseError::InvalidWordCount(13))
+        );
+    }
+
+    #[test]
+    fn test_seed_phrase_normalization() {
+        // BIP-39 test vector: the Japanese phrase of 16 zero bytes of entropy,
+        // with ideographic spaces between words
+        let seed_phrase =
+            "あいこくしん\u{3000}あいこくしん\u{3000}あいこくしん\u{3000}あいこくしん\
+                           \u{3000}あいこくしん\u{3000}あいこくしん\u{3000}あいこくしん\u{3000}\
+                           あいこくしん\u{3000}あいこくしん\u{3000}あいこくしん\u{3000}あいこくしん\
+                           \u{3000}あおぞら";
+        let passphrase = "㍍ガバヴァぱばぐゞちぢ十人十色";
+        let mnemonic = Mnemonic::from_entropy(&[0; 16], Language::Japanese).unwrap();
+        let expected = Seed::new(&mnemonic, passphrase).as_bytes().to_vec();
+
+        // Composed and decomposed input derive the same seed
+        let composed: String = seed_phrase.nfc().collect();
+        let decomposed: String = seed_phrase.nfkd().collect();
+        for seed_phrase in [seed_phrase, &composed, &decomposed] {
+            assert_eq!(
+                generate_seed_from_seed_phrase_and_passphrase_checked(
+                    seed_phrase,
+                    passphrase,
+                    Language::Japanese
+                )
+                .as_deref(),
+                Ok(&expected)
+            );
+        }
+        assert_eq!(
+            normalize_seed_phrase(seed_phrase).as_str(),
+            mnemonic.phrase()
+        );
+    }
+}
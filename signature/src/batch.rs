@@ -0,0 +1,208 @@
+//! Batched verification of ed25519 signatures.
+//!
+//! Verifying a batch of signatures with a single multiscalar multiplication is
+//! considerably faster than verifying each signature on its own. A batch only
+//! reports whether every signature in it is valid, so when a batch fails the
+//! signatures are verified individually to find which ones are invalid.
+//!
+//! Batch verification is not equivalent to [`Signature::verify`]. The batch
+//! combines every signature with random coefficients, which lets torsion
+//! components of the public keys and nonces cancel out, while
+//! [`Signature::verify`] uses `verify_strict` and checks the cofactorless
+//! equation for each signature. Signatures with a small-order public key or
+//! nonce are rejected before batching, as `verify_strict` does, so they never
+//! pass a batch. A signature crafted with a mixed-order public key or nonce
+//! can still pass a batch and fail [`Signature::verify`]; no honest signer
+//! produces those. Callers whose results must match the runtime exactly, such
+//! as consensus code, should use [`Signature::verify`].
+use {
+    crate::Signature,
+    core::convert::TryFrom,
+    curve25519_dalek::edwards::CompressedEdwardsY,
+    std::{vec, vec::Vec},
+};
+
+/// Returns true if every signature is valid for its pubkey and message.
+///
+/// The three slices are matched up by index. Returns false if they have
+/// different lengths, or if any public key or signature nonce is not a valid
+/// point or has small order.
+pub fn verify_batch(signatures: &[Signature], pubkeys: &[&[u8]], messages: &[&[u8]]) -> bool {
+    if signatures.len() != pubkeys.len() || signatures.len() != messages.len() {
+        return false;
+    }
+    if signatures.is_empty() {
+        return true;
+    }
+    if signatures
+        .iter()
+        .zip(pubkeys)
+        .any(|(signature, pubkey)| is_weak(&signature.as_ref()[..32]) || is_weak(pubkey))
+    {
+        return false;
+    }
+
+    let Some(dalek_signatures) = signatures
+        .iter()
+        .map(|signature| ed25519_dalek::Signature::try_from(signature.as_ref()).ok())
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+    let Some(dalek_pubkeys) = pubkeys
+        .iter()
+        .map(|pubkey| ed25519_dalek::PublicKey::from_bytes(pubkey).ok())
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+
+    ed25519_dalek::verify_batch(messages, &dalek_signatures, &dalek_pubkeys).is_ok()
+}
+
+/// Returns true if `bytes` is not the encoding of a curve point, or encodes a
+/// point of small order. `verify_strict` rejects both.
+fn is_weak(bytes: &[u8]) -> bool {
+    <[u8; 32]>::try_from(bytes)
+        .ok()
+        .and_then(|bytes| CompressedEdwardsY(bytes).decompress())
+        .is_none_or(|point| point.is_small_order())
+}
+
+/// Verify a batch of signatures and return the result of each one.
+///
+/// If the batch is valid, no further work is done. Otherwise each signature
+/// is verified individually with [`Signature::verify`] to find which
+/// signatures are invalid. Entries missing from the shorter slices are
+/// reported as invalid.
+pub fn verify_batch_with_results(
+    signatures: &[Signature],
+    pubkeys: &[&[u8]],
+    messages: &[&[u8]],
+) -> Vec<bool> {
+    if verify_batch(signatures, pubkeys, messages) {
+        return vec![true; signatures.len()];
+    }
+    signatures
+        .iter()
+        .enumerate()
+        .map(|(index, signature)| {
+            matches!(
+                (pubkeys.get(index), messages.get(index)),
+                (Some(pubkey), Some(message)) if signature.verify(pubkey, message)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ed25519_dalek::Signer};
+
+    fn keypair(seed: u8) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn signed_batch(len: u8) -> (Vec<Signature>, Vec<[u8; 32]>, Vec<Vec<u8>>) {
+        let mut signatures = vec![];
+        let mut pubkeys = vec![];
+        let mut messages = vec![];
+        for i in 0..len {
+            let keypair = keypair(i);
+            let message = vec![i; usize::from(i).saturating_add(1)];
+            signatures.push(Signature::from(keypair.sign(&message).to_bytes()));
+            pubkeys.push(keypair.public.to_bytes());
+            messages.push(message);
+        }
+        (signatures, pubkeys, messages)
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let (signatures, pubkeys, messages) = signed_batch(8);
+        let pubkeys: Vec<&[u8]> = pubkeys.iter().map(|pubkey| pubkey.as_slice()).collect();
+        let messages: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+
+        assert!(verify_batch(&signatures, &pubkeys, &messages));
+        assert!(verify_batch(&[], &[], &[]));
+        assert!(!verify_batch(&signatures[1..], &pubkeys, &messages));
+        assert_eq!(
+            verify_batch_with_results(&signatures, &pubkeys, &messages),
+            vec![true; 8]
+        );
+
+        // Invalid pubkey bytes
+        let mut bad_pubkeys = pubkeys.clone();
+        bad_pubkeys[2] = &[0u8; 3];
+        assert!(!verify_batch(&signatures, &bad_pubkeys, &messages));
+        let results = verify_batch_with_results(&signatures, &bad_pubkeys, &messages);
+        assert_eq!(results.iter().position(|valid| !valid), Some(2));
+    }
+
+    #[test]
+    fn test_verify_batch_with_results_finds_invalid() {
+        let (mut signatures, pubkeys, messages) = signed_batch(8);
+        let pubkeys: Vec<&[u8]> = pubkeys.iter().map(|pubkey| pubkey.as_slice()).collect();
+        let mut messages: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+
+        signatures.swap(1, 6);
+        messages[4] = b"tampered";
+        assert!(!verify_batch(&signatures, &pubkeys, &messages));
+        assert_eq!(
+            verify_batch_with_results(&signatures, &pubkeys, &messages),
+            vec![true, false, true, true, false, true, false, true]
+        );
+
+        // Missing entries are invalid
+        assert_eq!(
+            verify_batch_with_results(&signatures[..3], &pubkeys[..2], &messages[..3]),
+            vec![true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_verify_batch_rejects_small_order_key() {
+        use curve25519_dalek::{
+            constants::ED25519_BASEPOINT_POINT, edwards::EdwardsPoint, scalar::Scalar,
+            traits::Identity,
+        };
+
+        // With the identity as public key, `s = r` satisfies the batch
+        // equation for any message, but `verify_strict` rejects the key.
+        let r = Scalar::from_bytes_mod_order([7; 32]);
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice((ED25519_BASEPOINT_POINT * r).compress().as_bytes());
+        signature[32..].copy_from_slice(r.as_bytes());
+        let small_order_signature = Signature::from(signature);
+        let small_order_pubkey = EdwardsPoint::identity().compress().to_bytes();
+        let message = b"small order";
+        assert!(!small_order_signature.verify(&small_order_pubkey, message));
+
+        let (mut signatures, pubkeys, messages) = signed_batch(4);
+        let mut pubkeys: Vec<&[u8]> = pubkeys.iter().map(|pubkey| pubkey.as_slice()).collect();
+        let mut messages: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+        signatures.push(small_order_signature);
+        pubkeys.push(&small_order_pubkey);
+        messages.push(message);
+
+        assert!(!verify_batch(
+            &signatures[4..],
+            &pubkeys[4..],
+            &messages[4..]
+        ));
+        assert!(!verify_batch(&signatures, &pubkeys, &messages));
+        let expected: Vec<bool> = signatures
+            .iter()
+            .zip(&pubkeys)
+            .zip(&messages)
+            .map(|((signature, pubkey), message)| signature.verify(pubkey, message))
+            .collect();
+        assert_eq!(expected, vec![true, true, true, true, false]);
+        assert_eq!(
+            verify_batch_with_results(&signatures, &pubkeys, &messages),
+            expected
+        );
+    }
+}
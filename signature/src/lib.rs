@@ -20,6 +20,8 @@ use {
     serde_derive::{Deserialize, Serialize},
 };
 
+#[cfg(feature = "batch-verify")]
+pub mod batch;
 pub mod error;
 
 /// Number of bytes in a signature
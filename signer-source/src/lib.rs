@@ -0,0 +1,678 @@
+//! Resolve signer URIs to [`Signer`]s.
+//!
+//! Command line tools accept signers in many forms. A [`SignerSource`] is
+//! parsed from one of:
+//!
+//! - `file:<PATH>`, or a bare path to an existing file: a keypair file,
+//!   which may be encrypted when the `encryption` feature is enabled
+//! - `stdin:` or `-`: a keypair read from standard input
+//! - `env:<NAME>`: a keypair held by an environment variable, as a JSON
+//!   byte array or a base58 string
+//! - `prompt:`, optionally with a `?key=<ACCOUNT>/<CHANGE>` or
+//!   `?full-path=<PATH>` derivation path query: a keypair derived from a
+//!   seed phrase and passphrase asked for by a [`SignerPrompt`]. Without a
+//!   query the default Solana BIP-44 derivation path `m/44'/501'` is used.
+//! - `ASK`: like `prompt:`, but the keypair is derived from the seed
+//!   directly, without a derivation path
+//! - `presigned:<PUBKEY>=<SIGNATURE>`: a [`Presigner`]
+//! - a bare base58 pubkey: a [`NullSigner`]
+//!
+//! [`SignerSource::resolve`] then turns the source into a `Box<dyn Signer>`.
+//!
+//! [`NullSigner`]: solana_signer::null_signer::NullSigner
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+
+use {
+    core::{fmt, str::FromStr},
+    percent_encoding::percent_decode_str,
+    solana_derivation_path::{DerivationPath, DerivationPathError},
+    solana_keypair::{
+        keypair_from_seed, read_keypair, seed_derivable::keypair_from_seed_and_derivation_path,
+        Keypair,
+    },
+    solana_presigner::Presigner,
+    solana_pubkey::{ParsePubkeyError, Pubkey},
+    solana_seed_phrase::mnemonic::{
+        generate_seed_from_seed_phrase_and_passphrase_checked, validate_seed_phrase, Language,
+        SeedPhraseError,
+    },
+    solana_signature::{ParseSignatureError, Signature},
+    solana_signer::{null_signer::NullSigner, EncodableKey, Signer},
+    std::{
+        env, error, fs,
+        io::{self, Read},
+    },
+    uriparse::URIReference,
+    zeroize::Zeroizing,
+};
+
+pub const SIGNER_SOURCE_FILEPATH: &str = "file";
+pub const SIGNER_SOURCE_STDIN: &str = "stdin";
+pub const SIGNER_SOURCE_ENV: &str = "env";
+pub const SIGNER_SOURCE_PROMPT: &str = "prompt";
+pub const SIGNER_SOURCE_PRESIGNED: &str = "presigned";
+/// The bare source that reads a keypair from standard input
+pub const STDIN_TOKEN: &str = "-";
+/// The bare source that prompts for a seed phrase without a derivation path
+pub const ASK_KEYWORD: &str = "ASK";
+
+/// The seed phrase languages tried, in order, when validating a prompted
+/// seed phrase
+const LANGUAGES: [Language; 8] = [
+    Language::English,
+    Language::ChineseSimplified,
+    Language::ChineseTraditional,
+    Language::French,
+    Language::Italian,
+    Language::Japanese,
+    Language::Korean,
+    Language::Spanish,
+];
+
+/// Reasons that a signer source can't be parsed or resolved
+#[derive(Debug)]
+pub enum SignerSourceError {
+    /// The source is not a URI, a pubkey or the path of an existing file
+    UnrecognizedSource(String),
+    /// The URI scheme is not one of the supported schemes
+    UnsupportedScheme(String),
+    /// The pubkey of a presigned source is invalid
+    InvalidPubkey(ParsePubkeyError),
+    /// The signature of a presigned source is missing or invalid
+    InvalidSignature(ParseSignatureError),
+    /// The derivation path query is invalid
+    DerivationPath(DerivationPathError),
+    /// A derivation path was given for a source that doesn't derive keys
+    UnexpectedDerivationPath(String),
+    /// The environment variable is not set or is not unicode
+    EnvVarNotFound(String),
+    /// Reading a keypair failed
+    Io(io::Error),
+    /// The keypair is malformed, or its password is wrong
+    InvalidKeypair(Box<dyn error::Error>),
+    /// The prompted seed phrase is not valid in any language
+    InvalidSeedPhrase(SeedPhraseError),
+    /// The prompt failed or was cancelled
+    Prompt(Box<dyn error::Error>),
+}
+
+impl error::Error for SignerSourceError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::UnrecognizedSource(_) => None,
+            Self::UnsupportedScheme(_) => None,
+            Self::InvalidPubkey(e) => Some(e),
+            Self::InvalidSignature(e) => Some(e),
+            Self::DerivationPath(e) => Some(e),
+            Self::UnexpectedDerivationPath(_) => None,
+            Self::EnvVarNotFound(_) => None,
+            Self::Io(e) => Some(e),
+            Self::InvalidKeypair(e) => Some(e.as_ref()),
+            Self::InvalidSeedPhrase(e) => Some(e),
+            Self::Prompt(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+impl fmt::Display for SignerSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnrecognizedSource(source) => write!(f, "unrecognized signer source `{source}`"),
+            Self::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported signer source scheme `{scheme}`")
+            }
+            Self::InvalidPubkey(e) => write!(f, "invalid presigner pubkey: {e}"),
+            Self::InvalidSignature(e) => write!(f, "invalid presigner signature: {e}"),
+            Self::DerivationPath(e) => e.fmt(f),
+            Self::UnexpectedDerivationPath(scheme) => {
+                write!(
+                    f,
+                    "signer source `{scheme}` does not support derivation paths"
+                )
+            }
+            Self::EnvVarNotFound(name) => write!(f, "environment variable `{name}` not found"),
+            Self::Io(e) => write!(f, "failed to read keypair: {e}"),
+            Self::InvalidKeypair(e) => write!(f, "invalid keypair: {e}"),
+            Self::InvalidSeedPhrase(e) => e.fmt(f),
+            Self::Prompt(e) => write!(f, "prompt failed: {e}"),
+        }
+    }
+}
+
+impl From<DerivationPathError> for SignerSourceError {
+    fn from(source: DerivationPathError) -> Self {
+        Self::DerivationPath(source)
+    }
+}
+
+impl From<io::Error> for SignerSourceError {
+    fn from(source: io::Error) -> Self {
+        Self::Io(source)
+    }
+}
+
+/// Asks the user for secrets while resolving a [`SignerSource`]
+pub trait SignerPrompt {
+    /// Ask for a seed phrase
+    fn seed_phrase(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>>;
+    /// Ask for the passphrase of a seed phrase, which is empty if it has
+    /// none
+    fn passphrase(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>>;
+    /// Ask for the password of an encrypted keypair file
+    fn password(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>>;
+}
+
+/// Where a signer comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSourceKind {
+    /// A keypair file
+    Filepath(String),
+    /// A keypair read from standard input
+    Stdin,
+    /// A keypair held by an environment variable
+    Env(String),
+    /// A keypair derived from a prompted seed phrase
+    Prompt,
+    /// A signature made ahead of time
+    Presigned(Pubkey, Signature),
+    /// A pubkey without a signer
+    Pubkey(Pubkey),
+}
+
+/// A parsed signer URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerSource {
+    pub kind: SignerSourceKind,
+    /// The derivation path of a [`SignerSourceKind::Prompt`] source
+    pub derivation_path: Option<DerivationPath>,
+    /// Whether a prompted keypair is derived from the seed directly, without
+    /// a derivation path
+    pub legacy: bool,
+}
+
+impl SignerSource {
+    fn new(kind: SignerSourceKind) -> Self {
+        Self {
+            kind,
+            derivation_path: None,
+            legacy: false,
+        }
+    }
+
+    /// Parse a signer URI.
+    ///
+    /// Sources which aren't valid URIs, such as file paths containing spaces,
+    /// are parsed as bare sources: `-`, `ASK`, a pubkey or an existing file.
+    pub fn parse(source: &str) -> Result<Self, SignerSourceError> {
+        let Ok(uri) = URIReference::try_from(source) else {
+            return Self::parse_bare(source);
+        };
+        let Some(scheme) = uri.scheme() else {
+            return Self::parse_bare(source);
+        };
+        let scheme = scheme.as_str().to_ascii_lowercase();
+        let path = uri.path().to_string();
+        if scheme != SIGNER_SOURCE_PROMPT && DerivationPath::from_uri_any_query(&uri)?.is_some() {
+            return Err(SignerSourceError::UnexpectedDerivationPath(scheme));
+        }
+        match scheme.as_str() {
+            SIGNER_SOURCE_FILEPATH => {
+                let path = percent_decode_str(&path)
+                    .decode_utf8()
+                    .map_err(|_| SignerSourceError::UnrecognizedSource(source.to_string()))?;
+                Ok(Self::new(SignerSourceKind::Filepath(path.into_owned())))
+            }
+            SIGNER_SOURCE_STDIN => Ok(Self::new(SignerSourceKind::Stdin)),
+            SIGNER_SOURCE_ENV => Ok(Self::new(SignerSourceKind::Env(path))),
+            SIGNER_SOURCE_PROMPT => Ok(Self {
+                kind: SignerSourceKind::Prompt,
+                derivation_path: DerivationPath::from_uri_any_query(&uri)?,
+                legacy: false,
+            }),
+            SIGNER_SOURCE_PRESIGNED => {
+                let (pubkey, signature) = path.split_once('=').unwrap_or((&path, ""));
+                let pubkey = Pubkey::from_str(pubkey).map_err(SignerSourceError::InvalidPubkey)?;
+                let signature =
+                    Signature::from_str(signature).map_err(SignerSourceError::InvalidSignature)?;
+                Ok(Self::new(SignerSourceKind::Presigned(pubkey, signature)))
+            }
+            _ => Err(SignerSourceError::UnsupportedScheme(scheme)),
+        }
+    }
+
+    fn parse_bare(source: &str) -> Result<Self, SignerSourceError> {
+        match source {
+            STDIN_TOKEN => Ok(Self::new(SignerSourceKind::Stdin)),
+            ASK_KEYWORD => Ok(Self {
+                legacy: true,
+                ..Self::new(SignerSourceKind::Prompt)
+            }),
+            _ => {
+                if let Ok(pubkey) = Pubkey::from_str(source) {
+                    Ok(Self::new(SignerSourceKind::Pubkey(pubkey)))
+                } else if fs::metadata(source).is_ok() {
+                    Ok(Self::new(SignerSourceKind::Filepath(source.to_string())))
+                } else {
+                    Err(SignerSourceError::UnrecognizedSource(source.to_string()))
+                }
+            }
+        }
+    }
+
+    /// Resolve the source to a signer, reading keypairs from files, standard
+    /// input or the environment, and asking `prompt` for secrets
+    pub fn resolve(
+        &self,
+        prompt: &mut dyn SignerPrompt,
+    ) -> Result<Box<dyn Signer>, SignerSourceError> {
+        self.resolve_with_stdin(prompt, &mut io::stdin())
+    }
+
+    fn resolve_with_stdin(
+        &self,
+        prompt: &mut dyn SignerPrompt,
+        stdin: &mut dyn Read,
+    ) -> Result<Box<dyn Signer>, SignerSourceError> {
+        match &self.kind {
+            SignerSourceKind::Filepath(path) => {
+                fs::metadata(path)?;
+                let mut password = || prompt.password().map(|password| password.to_string());
+                Keypair::read_from_file_with_password(path, &mut password)
+                    .map(|keypair| Box::new(keypair) as Box<dyn Signer>)
+                    .map_err(SignerSourceError::InvalidKeypair)
+            }
+            SignerSourceKind::Stdin => read_keypair(&mut { stdin })
+                .map(|keypair| Box::new(keypair) as Box<dyn Signer>)
+                .map_err(SignerSourceError::InvalidKeypair),
+            SignerSourceKind::Env(name) => {
+                let value = Zeroizing::new(
+                    env::var(name).map_err(|_| SignerSourceError::EnvVarNotFound(name.clone()))?,
+                );
+                keypair_from_env_value(&value)
+                    .map(|keypair| Box::new(keypair) as Box<dyn Signer>)
+                    .map_err(SignerSourceError::InvalidKeypair)
+            }
+            SignerSourceKind::Prompt => self
+                .prompt_keypair(prompt)
+                .map(|keypair| Box::new(keypair) as Box<dyn Signer>),
+            SignerSourceKind::Presigned(pubkey, signature) => {
+                Ok(Box::new(Presigner::new(pubkey, signature)))
+            }
+            SignerSourceKind::Pubkey(pubkey) => Ok(Box::new(NullSigner::new(pubkey))),
+        }
+    }
+
+    fn prompt_keypair(&self, prompt: &mut dyn SignerPrompt) -> Result<Keypair, SignerSourceError> {
+        let seed_phrase = prompt.seed_phrase().map_err(SignerSourceError::Prompt)?;
+        let language = LANGUAGES
+            .into_iter()
+            .find(|language| validate_seed_phrase(&seed_phrase, *language).is_ok())
+            .ok_or_else(|| {
+                SignerSourceError::InvalidSeedPhrase(
+                    validate_seed_phrase(&seed_phrase, Language::English).unwrap_err(),
+                )
+            })?;
+        let passphrase = prompt.passphrase().map_err(SignerSourceError::Prompt)?;
+        let seed = generate_seed_from_seed_phrase_and_passphrase_checked(
+            &seed_phrase,
+            &passphrase,
+            language,
+        )
+        .map_err(SignerSourceError::InvalidSeedPhrase)?;
+        if self.legacy {
+            keypair_from_seed(&seed)
+        } else {
+            keypair_from_seed_and_derivation_path(&seed, self.derivation_path.clone())
+        }
+        .map_err(SignerSourceError::InvalidKeypair)
+    }
+}
+
+impl FromStr for SignerSource {
+    type Err = SignerSourceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Decode a keypair from a JSON byte array or a base58 string
+fn keypair_from_env_value(value: &str) -> Result<Keypair, Box<dyn error::Error>> {
+    let value = value.trim();
+    if value.starts_with('[') {
+        return read_keypair(&mut value.as_bytes());
+    }
+    let bytes = Zeroizing::new(bs58::decode(value).into_vec()?);
+    Ok(Keypair::try_from(bytes.as_slice())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_keypair::{
+            encrypted::{EncryptedKeypair, ScryptParams},
+            write_keypair,
+        },
+        std::path::Path,
+    };
+
+    const SEED_PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                               abandon abandon abandon about";
+
+    #[derive(Default)]
+    struct TestPrompt {
+        seed_phrase: &'static str,
+        passphrase: &'static str,
+        password: &'static str,
+        asked: Vec<&'static str>,
+    }
+
+    impl SignerPrompt for TestPrompt {
+        fn seed_phrase(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+            self.asked.push("seed phrase");
+            Ok(Zeroizing::new(self.seed_phrase.to_string()))
+        }
+
+        fn passphrase(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+            self.asked.push("passphrase");
+            Ok(Zeroizing::new(self.passphrase.to_string()))
+        }
+
+        fn password(&mut self) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
+            self.asked.push("password");
+            if self.password.is_empty() {
+                Err("cancelled".into())
+            } else {
+                Ok(Zeroizing::new(self.password.to_string()))
+            }
+        }
+    }
+
+    fn resolve(source: &str, prompt: &mut TestPrompt) -> Result<Pubkey, SignerSourceError> {
+        SignerSource::parse(source)?
+            .resolve(prompt)
+            .map(|signer| signer.pubkey())
+    }
+
+    #[test]
+    fn test_parse_signer_source() {
+        let pubkey = Pubkey::new_unique();
+        let signature = Signature::from([7; 64]);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+
+        let cases = [
+            (
+                format!("file:{path}"),
+                SignerSourceKind::Filepath(path.clone()),
+            ),
+            (
+                format!("file://{path}"),
+                SignerSourceKind::Filepath(path.clone()),
+            ),
+            (path.clone(), SignerSourceKind::Filepath(path.clone())),
+            ("stdin:".to_string(), SignerSourceKind::Stdin),
+            ("-".to_string(), SignerSourceKind::Stdin),
+            (
+                "env:KEYPAIR".to_string(),
+                SignerSourceKind::Env("KEYPAIR".to_string()),
+            ),
+            (pubkey.to_string(), SignerSourceKind::Pubkey(pubkey)),
+            (
+                format!("presigned:{pubkey}={signature}"),
+                SignerSourceKind::Presigned(pubkey, signature),
+            ),
+        ];
+        for (source, kind) in cases {
+            assert_eq!(
+                source.parse::<SignerSource>().unwrap(),
+                SignerSource::new(kind),
+                "{source}"
+            );
+        }
+
+        assert_eq!(
+            SignerSource::parse("prompt:").unwrap(),
+            SignerSource::new(SignerSourceKind::Prompt)
+        );
+        assert_eq!(
+            SignerSource::parse("PROMPT://?key=1/2").unwrap(),
+            SignerSource {
+                derivation_path: Some(DerivationPath::new_bip44(Some(1), Some(2))),
+                ..SignerSource::new(SignerSourceKind::Prompt)
+            }
+        );
+        assert_eq!(
+            SignerSource::parse("prompt:?full-path=m/44/501/2").unwrap(),
+            SignerSource {
+                derivation_path: Some(
+                    DerivationPath::from_absolute_path_str("m/44/501/2").unwrap()
+                ),
+                ..SignerSource::new(SignerSourceKind::Prompt)
+            }
+        );
+        assert_eq!(
+            SignerSource::parse("ASK").unwrap(),
+            SignerSource {
+                legacy: true,
+                ..SignerSource::new(SignerSourceKind::Prompt)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_signer_source_path_with_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("my key.json");
+        fs::write(&path, "").unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        // Not a valid URI, so it's parsed as a bare path
+        assert_eq!(
+            SignerSource::parse(&path).unwrap(),
+            SignerSource::new(SignerSourceKind::Filepath(path.clone()))
+        );
+        assert_eq!(
+            SignerSource::parse(&format!("file:{}", path.replace(' ', "%20"))).unwrap(),
+            SignerSource::new(SignerSourceKind::Filepath(path.clone()))
+        );
+        assert!(matches!(
+            SignerSource::parse("/no/such/my key.json"),
+            Err(SignerSourceError::UnrecognizedSource(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_signer_source_errors() {
+        let pubkey = Pubkey::new_unique();
+        assert!(matches!(
+            SignerSource::parse("usb://ledger"),
+            Err(SignerSourceError::UnsupportedScheme(scheme)) if scheme == "usb"
+        ));
+        assert!(matches!(
+            SignerSource::parse("/no/such/keypair.json"),
+            Err(SignerSourceError::UnrecognizedSource(_))
+        ));
+        assert!(matches!(
+            SignerSource::parse("prompt:?key=a"),
+            Err(SignerSourceError::DerivationPath(_))
+        ));
+        assert!(matches!(
+            SignerSource::parse("file:/keypair.json?key=0"),
+            Err(SignerSourceError::UnexpectedDerivationPath(scheme)) if scheme == "file"
+        ));
+        assert!(matches!(
+            SignerSource::parse("presigned:notapubkey=1111"),
+            Err(SignerSourceError::InvalidPubkey(_))
+        ));
+        assert!(matches!(
+            SignerSource::parse(&format!("presigned:{pubkey}")),
+            Err(SignerSourceError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            SignerSource::parse(&format!("presigned:{pubkey}=123")),
+            Err(SignerSourceError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_keypair_sources() {
+        let keypair = Keypair::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("id.json");
+        keypair.write_to_file(&path).unwrap();
+        let path = path.to_str().unwrap();
+        let mut prompt = TestPrompt::default();
+
+        assert_eq!(resolve(path, &mut prompt).unwrap(), keypair.pubkey());
+        assert_eq!(
+            resolve(&format!("file:{path}"), &mut prompt).unwrap(),
+            keypair.pubkey()
+        );
+        assert!(prompt.asked.is_empty());
+
+        // Encrypted keypairs ask for a password
+        let encrypted_path = dir.path().join("encrypted.json");
+        EncryptedKeypair::encrypt(
+            &keypair,
+            "hunter2",
+            &ScryptParams {
+                log_n: 4,
+                r: 8,
+                p: 1,
+            },
+        )
+        .unwrap()
+        .write_to_file(&encrypted_path)
+        .unwrap();
+        let source = format!("file:{}", encrypted_path.display());
+        let mut prompt = TestPrompt {
+            password: "hunter2",
+            ..TestPrompt::default()
+        };
+        assert_eq!(resolve(&source, &mut prompt).unwrap(), keypair.pubkey());
+        assert_eq!(prompt.asked, ["password"]);
+        assert!(matches!(
+            resolve(&source, &mut TestPrompt::default()),
+            Err(SignerSourceError::InvalidKeypair(_))
+        ));
+
+        let mut stdin = Vec::new();
+        write_keypair(&keypair, &mut stdin).unwrap();
+        let signer = SignerSource::parse("stdin:")
+            .unwrap()
+            .resolve_with_stdin(&mut TestPrompt::default(), &mut stdin.as_slice())
+            .unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+
+        env::set_var("SIGNER_SOURCE_TEST_JSON", String::from_utf8(stdin).unwrap());
+        env::set_var("SIGNER_SOURCE_TEST_BASE58", keypair.to_base58_string());
+        env::set_var("SIGNER_SOURCE_TEST_INVALID", "[1, 2, 3]");
+        for name in ["SIGNER_SOURCE_TEST_JSON", "SIGNER_SOURCE_TEST_BASE58"] {
+            assert_eq!(
+                resolve(&format!("env:{name}"), &mut prompt).unwrap(),
+                keypair.pubkey()
+            );
+        }
+        assert!(matches!(
+            resolve("env:SIGNER_SOURCE_TEST_INVALID", &mut prompt),
+            Err(SignerSourceError::InvalidKeypair(_))
+        ));
+        assert!(matches!(
+            resolve("env:SIGNER_SOURCE_TEST_MISSING", &mut prompt),
+            Err(SignerSourceError::EnvVarNotFound(name)) if name == "SIGNER_SOURCE_TEST_MISSING"
+        ));
+
+        // The file was removed after the source was parsed
+        let source = SignerSource::parse(path).unwrap();
+        drop(dir);
+        assert!(!Path::new(path).exists());
+        assert!(matches!(
+            source.resolve(&mut prompt),
+            Err(SignerSourceError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_prompt() {
+        let seed = generate_seed_from_seed_phrase_and_passphrase_checked(
+            SEED_PHRASE,
+            "TREZOR",
+            Language::English,
+        )
+        .unwrap();
+        let mut prompt = TestPrompt {
+            seed_phrase: SEED_PHRASE,
+            passphrase: "TREZOR",
+            ..TestPrompt::default()
+        };
+
+        assert_eq!(
+            resolve("prompt:", &mut prompt).unwrap(),
+            keypair_from_seed_and_derivation_path(&seed, None)
+                .unwrap()
+                .pubkey()
+        );
+        assert_eq!(prompt.asked, ["seed phrase", "passphrase"]);
+        assert_eq!(
+            resolve("prompt://?key=3/1", &mut prompt).unwrap(),
+            keypair_from_seed_and_derivation_path(
+                &seed,
+                Some(DerivationPath::new_bip44(Some(3), Some(1)))
+            )
+            .unwrap()
+            .pubkey()
+        );
+        assert_eq!(
+            resolve("ASK", &mut prompt).unwrap(),
+            keypair_from_seed(&seed).unwrap().pubkey()
+        );
+
+        // Seed phrases in other languages are accepted
+        let mut prompt = TestPrompt {
+            seed_phrase: "あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　\
+                          あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　\
+                          あいこくしん　あおぞら",
+            ..TestPrompt::default()
+        };
+        assert!(resolve("prompt:", &mut prompt).is_ok());
+
+        let mut prompt = TestPrompt {
+            seed_phrase: "abandon abandon abandon",
+            ..TestPrompt::default()
+        };
+        assert!(matches!(
+            resolve("prompt:", &mut prompt),
+            Err(SignerSourceError::InvalidSeedPhrase(
+                SeedPhraseError::InvalidWordCount(3)
+            ))
+        ));
+        // The passphrase isn't asked for when the seed phrase is invalid
+        assert_eq!(prompt.asked, ["seed phrase"]);
+    }
+
+    #[test]
+    fn test_resolve_pubkey_sources() {
+        let keypair = Keypair::new();
+        let message = b"message";
+        let signature = keypair.sign_message(message);
+        let mut prompt = TestPrompt::default();
+
+        let signer = SignerSource::parse(&format!("presigned:{}={signature}", keypair.pubkey()))
+            .unwrap()
+            .resolve(&mut prompt)
+            .unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        assert_eq!(signer.try_sign_message(message), Ok(signature));
+        assert!(signer.try_sign_message(b"other message").is_err());
+
+        let signer = SignerSource::parse(&keypair.pubkey().to_string())
+            .unwrap()
+            .resolve(&mut prompt)
+            .unwrap();
+        assert_eq!(signer.pubkey(), keypair.pubkey());
+        assert_eq!(signer.try_sign_message(message), Ok(Signature::default()));
+        assert!(prompt.asked.is_empty());
+    }
+}
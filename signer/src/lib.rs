@@ -12,6 +12,7 @@ use {
         ops::Deref,
         path::Path,
     },
+    zeroize::Zeroizing,
 };
 
 pub mod null_signer;
@@ -175,6 +176,10 @@ pub fn unique_signers(signers: Vec<&dyn Signer>) -> Vec<&dyn Signer> {
     out
 }
 
+/// Supplies the password of an encrypted key. It is only called when the key
+/// being read turns out to be encrypted.
+pub type PasswordCallback<'a> = &'a mut dyn FnMut() -> Result<String, Box<dyn error::Error>>;
+
 /// The `EncodableKey` trait defines the interface by which cryptographic keys/keypairs are read,
 /// written, and derived from sources.
 pub trait EncodableKey: Sized {
@@ -183,8 +188,29 @@ pub trait EncodableKey: Sized {
         let mut file = File::open(path.as_ref())?;
         Self::read(&mut file)
     }
-    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>>;
-    fn write_to_file<F: AsRef<Path>>(&self, outfile: F) -> Result<String, Box<dyn error::Error>> {
+    /// Read a key which may be stored encrypted, calling `password` to obtain
+    /// the password if it is. Keys without an encrypted encoding are read
+    /// with [`EncodableKey::read`].
+    fn read_with_password<R: Read>(
+        reader: &mut R,
+        _password: PasswordCallback,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        Self::read(reader)
+    }
+    fn read_from_file_with_password<F: AsRef<Path>>(
+        path: F,
+        password: PasswordCallback,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let mut file = File::open(path.as_ref())?;
+        Self::read_with_password(&mut file, password)
+    }
+    /// Write the key to `writer`, returning the encoded key. The encoding
+    /// may contain secret key material, so it's zeroized on drop.
+    fn write<W: Write>(&self, writer: &mut W) -> Result<Zeroizing<String>, Box<dyn error::Error>>;
+    fn write_to_file<F: AsRef<Path>>(
+        &self,
+        outfile: F,
+    ) -> Result<Zeroizing<String>, Box<dyn error::Error>> {
         let outfile = outfile.as_ref();
 
         if let Some(outdir) = outfile.parent() {
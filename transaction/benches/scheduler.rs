@@ -0,0 +1,70 @@
+#![allow(clippy::arithmetic_side_effects)]
+
+use {
+    criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion},
+    solana_pubkey::Pubkey,
+    solana_transaction::{sanitized::TransactionAccountLocks, scheduler::BatchScheduler},
+};
+
+const NUM_TRANSACTIONS: [usize; 3] = [1_000, 4_000, 16_000];
+const ACCOUNTS_PER_TRANSACTION: usize = 8;
+
+// Every transaction writes to a fee payer of its own and locks accounts drawn
+// from a shared pool. A small pool produces heavy contention.
+fn account_locks<'a>(
+    payers: &'a [Pubkey],
+    pool: &'a [Pubkey],
+    num_transactions: usize,
+) -> Vec<TransactionAccountLocks<'a>> {
+    (0..num_transactions)
+        .map(|i| {
+            let mut locks = TransactionAccountLocks {
+                writable: vec![&payers[i]],
+                readonly: vec![],
+            };
+            for j in 0..ACCOUNTS_PER_TRANSACTION {
+                let key = &pool[(i * 31 + j * 17) % pool.len()];
+                if j % 3 == 0 {
+                    locks.writable.push(key);
+                } else {
+                    locks.readonly.push(key);
+                }
+            }
+            locks
+        })
+        .collect()
+}
+
+fn bench_scheduler(c: &mut Criterion) {
+    let payers: Vec<_> = (0..NUM_TRANSACTIONS[2])
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    for pool_size in [64, 4096] {
+        let pool: Vec<_> = (0..pool_size).map(|_| Pubkey::new_unique()).collect();
+        let mut group = c.benchmark_group(format!("scheduler/pool_{pool_size}"));
+        for num_transactions in NUM_TRANSACTIONS {
+            let scheduler = BatchScheduler::new(account_locks(&payers, &pool, num_transactions));
+            group.bench_with_input(
+                BenchmarkId::new("schedule", num_transactions),
+                &scheduler,
+                |b, scheduler| b.iter(|| black_box(scheduler.schedule())),
+            );
+            group.bench_with_input(
+                BenchmarkId::new("hot_accounts", num_transactions),
+                &scheduler,
+                |b, scheduler| b.iter(|| black_box(scheduler.hot_accounts(16))),
+            );
+            if num_transactions <= NUM_TRANSACTIONS[1] {
+                group.bench_with_input(
+                    BenchmarkId::new("conflict_graph", num_transactions),
+                    &scheduler,
+                    |b, scheduler| b.iter(|| black_box(scheduler.conflict_graph())),
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_scheduler);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+//! Batched signature verification for transactions.
+//!
+//! These functions check signatures with ed25519 batch verification first and
+//! only fall back to verifying signatures one at a time when a batch fails.
+//! Batch verification is not equivalent to the `verify` methods of each
+//! transaction type: signatures crafted with mixed-order keys or nonces can
+//! pass a batch and still be rejected by the runtime. See
+//! [`solana_signature::batch`] for the details.
+
+use {
+    crate::{sanitized::SanitizedTransaction, versioned::VersionedTransaction},
+    solana_pubkey::Pubkey,
+    solana_signature::{batch, Signature},
+    solana_transaction_error::{TransactionError, TransactionResult as Result},
+};
+
+/// Verify the signatures of many transactions in a single batch, returning
+/// the verification results of each transaction.
+///
+/// If the combined batch fails, every transaction is batch verified on its
+/// own, and the signatures of each failing transaction are then verified
+/// individually.
+pub fn verify_transactions(transactions: &[VersionedTransaction]) -> Vec<Vec<bool>> {
+    let messages: Vec<Vec<u8>> = transactions
+        .iter()
+        .map(|tx| tx.message.serialize())
+        .collect();
+    verify_signed_messages(
+        transactions
+            .iter()
+            .zip(&messages)
+            .map(|(tx, message)| SignedMessage {
+                signatures: &tx.signatures,
+                pubkeys: tx.message.static_account_keys(),
+                message,
+            }),
+    )
+}
+
+/// Verify the signatures of many sanitized transactions in a single batch.
+///
+/// Returns [`TransactionError::SignatureFailure`] for every transaction with
+/// an invalid signature.
+pub fn verify_sanitized_transactions(transactions: &[SanitizedTransaction]) -> Vec<Result<()>> {
+    let messages: Vec<Vec<u8>> = transactions
+        .iter()
+        .map(SanitizedTransaction::message_data)
+        .collect();
+    verify_signed_messages(
+        transactions
+            .iter()
+            .zip(&messages)
+            .map(|(tx, message)| SignedMessage {
+                signatures: tx.signatures(),
+                pubkeys: tx.message().static_account_keys(),
+                message,
+            }),
+    )
+    .into_iter()
+    .map(into_result)
+    .collect()
+}
+
+struct SignedMessage<'a> {
+    signatures: &'a [Signature],
+    pubkeys: &'a [Pubkey],
+    message: &'a [u8],
+}
+
+fn verify_signed_messages<'a>(
+    signed_messages: impl Iterator<Item = SignedMessage<'a>>,
+) -> Vec<Vec<bool>> {
+    let signed_messages: Vec<_> = signed_messages.collect();
+
+    let mut signatures = Vec::new();
+    let mut pubkeys = Vec::new();
+    let mut messages = Vec::new();
+    for signed_message in signed_messages.iter() {
+        for (signature, pubkey) in signed_message.signatures.iter().zip(signed_message.pubkeys) {
+            signatures.push(*signature);
+            pubkeys.push(pubkey.as_ref());
+            messages.push(signed_message.message);
+        }
+    }
+
+    if batch::verify_batch(&signatures, &pubkeys, &messages) {
+        signed_messages
+            .iter()
+            .map(|signed_message| {
+                let num_signatures = signed_message
+                    .signatures
+                    .len()
+                    .min(signed_message.pubkeys.len());
+                vec![true; num_signatures]
+            })
+            .collect()
+    } else {
+        signed_messages
+            .iter()
+            .map(|signed_message| {
+                verify_signatures(
+                    signed_message.signatures,
+                    signed_message.pubkeys,
+                    signed_message.message,
+                )
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn verify_signatures(
+    signatures: &[Signature],
+    pubkeys: &[Pubkey],
+    message: &[u8],
+) -> Vec<bool> {
+    let num_signatures = signatures.len().min(pubkeys.len());
+    let pubkeys: Vec<&[u8]> = pubkeys[..num_signatures]
+        .iter()
+        .map(AsRef::as_ref)
+        .collect();
+    let messages = vec![message; num_signatures];
+    batch::verify_batch_with_results(&signatures[..num_signatures], &pubkeys, &messages)
+}
+
+pub(crate) fn into_result(results: Vec<bool>) -> Result<()> {
+    if results.iter().all(|verified| *verified) {
+        Ok(())
+    } else {
+        Err(TransactionError::SignatureFailure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_signer::Signer,
+    };
+
+    fn signed_transaction(num_signers: usize) -> Transaction {
+        let signers: Vec<_> = (0..num_signers).map(|_| Keypair::new()).collect();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1, 2, 3],
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new(signer.pubkey(), true))
+                .collect(),
+        );
+        Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signers[0].pubkey()),
+            &signers,
+            Hash::new_unique(),
+        )
+    }
+
+    #[test]
+    fn test_batch_verify_transaction() {
+        let mut tx = signed_transaction(3);
+        assert_eq!(tx.batch_verify_with_results(), vec![true; 3]);
+        assert_eq!(tx.batch_verify(), Ok(()));
+
+        tx.signatures[1] = Signature::default();
+        assert_eq!(tx.batch_verify_with_results(), tx.verify_with_results());
+        assert_eq!(tx.batch_verify_with_results(), vec![true, false, true]);
+        assert_eq!(tx.batch_verify(), Err(TransactionError::SignatureFailure));
+
+        let versioned_tx = VersionedTransaction::from(tx);
+        assert_eq!(
+            versioned_tx.batch_verify_with_results(),
+            versioned_tx.verify_with_results()
+        );
+    }
+
+    #[test]
+    fn test_batch_verify_sanitized_transaction() {
+        let tx = signed_transaction(2);
+        let sanitized_tx = SanitizedTransaction::from_transaction_for_tests(tx.clone());
+        assert_eq!(sanitized_tx.batch_verify(), Ok(()));
+
+        let mut bad_tx = tx;
+        bad_tx.message.recent_blockhash = Hash::new_unique();
+        let sanitized_bad_tx = SanitizedTransaction::from_transaction_for_tests(bad_tx);
+        assert_eq!(
+            sanitized_bad_tx.batch_verify(),
+            Err(TransactionError::SignatureFailure)
+        );
+
+        assert_eq!(
+            verify_sanitized_transactions(&[sanitized_tx, sanitized_bad_tx]),
+            vec![Ok(()), Err(TransactionError::SignatureFailure)]
+        );
+    }
+
+    #[test]
+    fn test_verify_transactions() {
+        let mut transactions: Vec<_> = (1..=4)
+            .map(|num_signers| VersionedTransaction::from(signed_transaction(num_signers)))
+            .collect();
+        assert_eq!(
+            verify_transactions(&transactions),
+            vec![vec![true], vec![true; 2], vec![true; 3], vec![true; 4]]
+        );
+        assert!(verify_transactions(&[]).is_empty());
+
+        transactions[2].signatures[0] = transactions[3].signatures[0];
+        let results = verify_transactions(&transactions);
+        assert_eq!(
+            results,
+            vec![
+                vec![true],
+                vec![true; 2],
+                vec![false, true, true],
+                vec![true; 4]
+            ]
+        );
+        assert_eq!(
+            results,
+            transactions
+                .iter()
+                .map(VersionedTransaction::verify_with_results)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -136,9 +136,14 @@ use {
     std::result,
 };
 
+#[cfg(feature = "batch-verify")]
+pub mod batch_verify;
 pub mod sanitized;
+pub mod scheduler;
 pub mod simple_vote_transaction_checker;
 pub mod versioned;
+#[cfg(feature = "view")]
+pub mod view;
 mod wasm;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -1048,6 +1053,33 @@ impl Transaction {
         self._verify_with_results(&self.message_data())
     }
 
+    #[cfg(feature = "batch-verify")]
+    /// Verifies that all signers have signed the message, using batch
+    /// verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::SignatureFailure`] on error.
+    pub fn batch_verify(&self) -> Result<()> {
+        batch_verify::into_result(self.batch_verify_with_results())
+    }
+
+    #[cfg(feature = "batch-verify")]
+    /// Verifies that all signers have signed the message, using batch
+    /// verification.
+    ///
+    /// Signatures are only verified individually if the batch fails. The
+    /// results can differ from [`Transaction::verify_with_results`] for
+    /// signatures crafted with mixed-order keys or nonces; see
+    /// [`solana_signature::batch`].
+    pub fn batch_verify_with_results(&self) -> Vec<bool> {
+        batch_verify::verify_signatures(
+            &self.signatures,
+            &self.message.account_keys,
+            &self.message_data(),
+        )
+    }
+
     #[cfg(feature = "verify")]
     pub(crate) fn _verify_with_results(&self, message_bytes: &[u8]) -> Vec<bool> {
         self.signatures
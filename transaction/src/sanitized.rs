@@ -260,7 +260,7 @@ impl SanitizedTransaction {
 
     #[cfg(feature = "verify")]
     /// Return the serialized message data to sign.
-    fn message_data(&self) -> Vec<u8> {
+    pub(crate) fn message_data(&self) -> Vec<u8> {
         match &self.message {
             SanitizedMessage::Legacy(legacy_message) => legacy_message.message.serialize(),
             SanitizedMessage::V0(loaded_msg) => loaded_msg.message.serialize(),
@@ -284,6 +284,20 @@ impl SanitizedTransaction {
         }
     }
 
+    #[cfg(feature = "batch-verify")]
+    /// Verify the transaction signatures using batch verification
+    ///
+    /// Unlike [`SanitizedTransaction::verify`], this may accept signatures
+    /// crafted with mixed-order keys or nonces; see
+    /// [`solana_signature::batch`].
+    pub fn batch_verify(&self) -> Result<()> {
+        crate::batch_verify::into_result(crate::batch_verify::verify_signatures(
+            &self.signatures,
+            self.message.static_account_keys(),
+            &self.message_data(),
+        ))
+    }
+
     /// Validate a transaction message against locked accounts
     pub fn validate_account_locks(
         message: &SanitizedMessage,
@@ -0,0 +1,415 @@
+//! Tools for scheduling a batch of transactions by their account locks.
+//!
+//! Two transactions conflict when they lock the same account and at least one
+//! of them locks it as writable. Conflicting transactions must be executed
+//! sequentially, while non-conflicting transactions may be executed in
+//! parallel.
+//!
+//! [`BatchScheduler`] builds the [`ConflictGraph`] of a list of transactions,
+//! partitions the transactions into batches which can each be executed in
+//! parallel, and reports the most contended ("hot") accounts. The input order
+//! of the transactions is treated as their priority: if two transactions
+//! conflict, the one that comes first is always scheduled in an earlier batch.
+
+use {
+    crate::sanitized::{SanitizedTransaction, TransactionAccountLocks},
+    solana_pubkey::Pubkey,
+    solana_transaction_error::TransactionResult as Result,
+    std::collections::{hash_map::Entry, HashMap},
+};
+
+/// Lock usage of a single account across a list of transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotAccount {
+    /// The locked account
+    pub pubkey: Pubkey,
+    /// Number of transactions that lock the account as writable
+    pub write_locks: usize,
+    /// Number of transactions that lock the account as readonly
+    pub read_locks: usize,
+}
+
+/// Undirected graph of conflicts between transactions, indexed by the
+/// position of each transaction in the scheduled list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConflictGraph {
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl ConflictGraph {
+    /// Build the conflict graph for a list of transaction account locks.
+    ///
+    /// The number of edges is quadratic in the number of transactions which
+    /// write to the same account, so this is best suited for analysis rather
+    /// than for the hot path of scheduling. Use [`BatchScheduler::schedule`]
+    /// to compute batches without materializing the graph.
+    pub fn new(locks: &[TransactionAccountLocks]) -> Self {
+        let mut accounts: HashMap<&Pubkey, AccountUsers> = HashMap::new();
+        for (index, tx_locks) in locks.iter().enumerate() {
+            for key in tx_locks.writable.iter() {
+                accounts.entry(*key).or_default().add_writer(index);
+            }
+            for key in tx_locks.readonly.iter() {
+                accounts.entry(*key).or_default().add_reader(index);
+            }
+        }
+
+        let mut adjacency = vec![Vec::new(); locks.len()];
+        for users in accounts.values() {
+            for (position, writer) in users.writers.iter().enumerate() {
+                for other in users.writers[position.saturating_add(1)..]
+                    .iter()
+                    .chain(users.readers.iter())
+                {
+                    if writer != other {
+                        adjacency[*writer].push(*other);
+                        adjacency[*other].push(*writer);
+                    }
+                }
+            }
+        }
+        for neighbors in adjacency.iter_mut() {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+
+        Self { adjacency }
+    }
+
+    /// Number of transactions in the graph
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Returns true if the graph contains no transactions
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    /// Number of conflicting transaction pairs
+    pub fn num_edges(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    /// Sorted indexes of the transactions conflicting with the transaction at
+    /// `index`
+    pub fn conflicts(&self, index: usize) -> &[usize] {
+        self.adjacency
+            .get(index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the transactions at indexes `a` and `b` conflict
+    pub fn is_conflicting(&self, a: usize, b: usize) -> bool {
+        self.conflicts(a).binary_search(&b).is_ok()
+    }
+}
+
+/// Transactions partitioned into batches which can each be executed in
+/// parallel. Batches must be executed in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchSchedule {
+    batches: Vec<Vec<usize>>,
+}
+
+impl BatchSchedule {
+    /// Transaction indexes of each batch, in execution order. Indexes within
+    /// a batch are in priority order.
+    pub fn batches(&self) -> &[Vec<usize>] {
+        &self.batches
+    }
+
+    /// Number of batches in the schedule
+    pub fn num_batches(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Consume the schedule and return its batches
+    pub fn into_batches(self) -> Vec<Vec<usize>> {
+        self.batches
+    }
+}
+
+/// Schedules a list of transactions, given in priority order, by their
+/// account locks.
+#[derive(Debug, Clone, Default)]
+pub struct BatchScheduler<'a> {
+    locks: Vec<TransactionAccountLocks<'a>>,
+}
+
+impl<'a> BatchScheduler<'a> {
+    /// Create a scheduler from the account locks of each transaction
+    pub fn new(locks: Vec<TransactionAccountLocks<'a>>) -> Self {
+        Self { locks }
+    }
+
+    /// Create a scheduler from sanitized transactions, validating the account
+    /// locks of each transaction against `tx_account_lock_limit`.
+    pub fn try_from_transactions(
+        transactions: &'a [SanitizedTransaction],
+        tx_account_lock_limit: usize,
+    ) -> Result<Self> {
+        let locks = transactions
+            .iter()
+            .map(|tx| tx.get_account_locks(tx_account_lock_limit))
+            .collect::<Result<_>>()?;
+        Ok(Self::new(locks))
+    }
+
+    /// Return the account locks of each transaction
+    pub fn account_locks(&self) -> &[TransactionAccountLocks<'a>] {
+        &self.locks
+    }
+
+    /// Build the conflict graph of the transactions
+    pub fn conflict_graph(&self) -> ConflictGraph {
+        ConflictGraph::new(&self.locks)
+    }
+
+    /// Partition the transactions into batches of non-conflicting
+    /// transactions.
+    ///
+    /// Each transaction is placed in the earliest batch that comes after
+    /// every batch holding a conflicting transaction of higher priority. The
+    /// resulting schedule has the fewest batches possible without reordering
+    /// conflicting transactions.
+    pub fn schedule(&self) -> BatchSchedule {
+        // Last batch which locks each account as (writable, readonly)
+        let mut account_batches: HashMap<&Pubkey, (Option<usize>, Option<usize>)> = HashMap::new();
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+
+        for (index, tx_locks) in self.locks.iter().enumerate() {
+            let after_writes = tx_locks
+                .writable
+                .iter()
+                .chain(tx_locks.readonly.iter())
+                .filter_map(|key| account_batches.get(*key).and_then(|(write, _)| *write));
+            let after_reads = tx_locks
+                .writable
+                .iter()
+                .filter_map(|key| account_batches.get(*key).and_then(|(_, read)| *read));
+            let batch = after_writes
+                .chain(after_reads)
+                .max()
+                .map_or(0, |batch| batch.saturating_add(1));
+
+            for key in tx_locks.writable.iter() {
+                let (write, _) = account_batches.entry(*key).or_default();
+                *write = (*write).max(Some(batch));
+            }
+            for key in tx_locks.readonly.iter() {
+                let (_, read) = account_batches.entry(*key).or_default();
+                *read = (*read).max(Some(batch));
+            }
+
+            if batch == batches.len() {
+                batches.push(Vec::new());
+            }
+            batches[batch].push(index);
+        }
+
+        BatchSchedule { batches }
+    }
+
+    /// Return up to `limit` of the most contended accounts, ordered by number
+    /// of write locks and then by total number of locks.
+    pub fn hot_accounts(&self, limit: usize) -> Vec<HotAccount> {
+        let mut accounts: HashMap<&Pubkey, HotAccount> = HashMap::new();
+        for tx_locks in self.locks.iter() {
+            for key in tx_locks.writable.iter() {
+                let account = Self::hot_account(&mut accounts, key);
+                account.write_locks = account.write_locks.saturating_add(1);
+            }
+            for key in tx_locks.readonly.iter() {
+                let account = Self::hot_account(&mut accounts, key);
+                account.read_locks = account.read_locks.saturating_add(1);
+            }
+        }
+
+        let mut accounts: Vec<HotAccount> = accounts.into_values().collect();
+        accounts.sort_unstable_by(|a, b| {
+            b.write_locks
+                .cmp(&a.write_locks)
+                .then_with(|| {
+                    (b.write_locks.saturating_add(b.read_locks))
+                        .cmp(&a.write_locks.saturating_add(a.read_locks))
+                })
+                .then_with(|| a.pubkey.cmp(&b.pubkey))
+        });
+        accounts.truncate(limit);
+        accounts
+    }
+
+    fn hot_account<'b>(
+        accounts: &'b mut HashMap<&'a Pubkey, HotAccount>,
+        key: &'a Pubkey,
+    ) -> &'b mut HotAccount {
+        match accounts.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(HotAccount {
+                pubkey: *key,
+                write_locks: 0,
+                read_locks: 0,
+            }),
+        }
+    }
+}
+
+#[derive(Default)]
+struct AccountUsers {
+    writers: Vec<usize>,
+    readers: Vec<usize>,
+}
+
+impl AccountUsers {
+    fn add_writer(&mut self, index: usize) {
+        if self.writers.last() != Some(&index) {
+            self.writers.push(index);
+        }
+    }
+
+    fn add_reader(&mut self, index: usize) {
+        if self.readers.last() != Some(&index) {
+            self.readers.push(index);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::arithmetic_side_effects)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_signer::Signer,
+    };
+
+    fn locks<'a>(writable: &[&'a Pubkey], readonly: &[&'a Pubkey]) -> TransactionAccountLocks<'a> {
+        TransactionAccountLocks {
+            writable: writable.to_vec(),
+            readonly: readonly.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_conflict_graph() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let graph = ConflictGraph::new(&[
+            locks(&[&a], &[&b]),
+            locks(&[&c], &[&b]),
+            locks(&[&b], &[]),
+            locks(&[], &[&a]),
+            locks(&[], &[&c, &b]),
+        ]);
+
+        assert_eq!(graph.len(), 5);
+        assert_eq!(graph.conflicts(0), &[2, 3]);
+        assert_eq!(graph.conflicts(1), &[2, 4]);
+        assert_eq!(graph.conflicts(2), &[0, 1, 4]);
+        assert_eq!(graph.conflicts(3), &[0]);
+        assert_eq!(graph.conflicts(4), &[1, 2]);
+        assert_eq!(graph.conflicts(5), &[] as &[usize]);
+        assert_eq!(graph.num_edges(), 5);
+        assert!(graph.is_conflicting(4, 2));
+        assert!(!graph.is_conflicting(3, 4));
+    }
+
+    #[test]
+    fn test_schedule_preserves_priority() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let scheduler = BatchScheduler::new(vec![
+            locks(&[&a], &[&b]),
+            locks(&[&c], &[&b]),
+            locks(&[&b], &[]),
+            locks(&[], &[&a]),
+            locks(&[], &[&c, &b]),
+        ]);
+        let schedule = scheduler.schedule();
+        assert_eq!(schedule.batches(), &[vec![0, 1], vec![2, 3], vec![4]]);
+
+        // No two transactions in the same batch conflict, and every conflict
+        // is ordered by priority.
+        let graph = scheduler.conflict_graph();
+        let mut batch_of = vec![0; graph.len()];
+        for (batch, indexes) in schedule.batches().iter().enumerate() {
+            for index in indexes {
+                batch_of[*index] = batch;
+            }
+        }
+        for index in 0..graph.len() {
+            for other in graph.conflicts(index) {
+                assert_eq!(index < *other, batch_of[index] < batch_of[*other]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_schedule_readers_share_batch() {
+        let [a, b] = [(); 2].map(|_| Pubkey::new_unique());
+        let scheduler = BatchScheduler::new(vec![
+            locks(&[&b], &[&a]),
+            locks(&[], &[&a]),
+            locks(&[], &[&a]),
+            locks(&[&a], &[]),
+            locks(&[], &[&a]),
+        ]);
+        assert_eq!(
+            scheduler.schedule().into_batches(),
+            vec![vec![0, 1, 2], vec![3], vec![4]]
+        );
+    }
+
+    #[test]
+    fn test_hot_accounts() {
+        let [a, b, c] = [(); 3].map(|_| Pubkey::new_unique());
+        let scheduler = BatchScheduler::new(vec![
+            locks(&[&a], &[&b]),
+            locks(&[&a], &[&b, &c]),
+            locks(&[&c], &[&b]),
+        ]);
+        assert_eq!(
+            scheduler.hot_accounts(2),
+            vec![
+                HotAccount {
+                    pubkey: a,
+                    write_locks: 2,
+                    read_locks: 0,
+                },
+                HotAccount {
+                    pubkey: c,
+                    write_locks: 1,
+                    read_locks: 1,
+                },
+            ]
+        );
+        assert_eq!(scheduler.hot_accounts(usize::MAX).len(), 3);
+    }
+
+    #[test]
+    fn test_try_from_transactions() {
+        let payer = Keypair::new();
+        let shared = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let transactions: Vec<_> = (0..3)
+            .map(|_| {
+                let instruction = Instruction::new_with_bytes(
+                    program_id,
+                    &[],
+                    vec![AccountMeta::new(shared, false)],
+                );
+                SanitizedTransaction::from_transaction_for_tests(Transaction::new_with_payer(
+                    &[instruction],
+                    Some(&payer.pubkey()),
+                ))
+            })
+            .collect();
+
+        let scheduler = BatchScheduler::try_from_transactions(&transactions, 64).unwrap();
+        assert_eq!(scheduler.schedule().num_batches(), 3);
+        assert_eq!(scheduler.hot_accounts(1)[0].write_locks, 3);
+        assert!(BatchScheduler::try_from_transactions(&transactions, 1).is_err());
+    }
+}
@@ -195,6 +195,17 @@ impl VersionedTransaction {
         self._verify_with_results(&message_bytes)
     }
 
+    #[cfg(feature = "batch-verify")]
+    /// Verify the transaction using batch verification and return a list of
+    /// verification results
+    pub fn batch_verify_with_results(&self) -> Vec<bool> {
+        crate::batch_verify::verify_signatures(
+            &self.signatures,
+            self.message.static_account_keys(),
+            &self.message.serialize(),
+        )
+    }
+
     #[cfg(feature = "verify")]
     fn _verify_with_results(&self, message_bytes: &[u8]) -> Vec<bool> {
         self.signatures
@@ -0,0 +1,587 @@
+//! A borrowed, zero-copy view of a serialized transaction.
+//!
+//! [`TransactionView`] checks that a byte slice is a well-formed serialized
+//! [`VersionedTransaction`] and exposes its fields as slices borrowed from
+//! the input, without allocating. It accepts exactly the inputs that bincode
+//! deserialization of a `VersionedTransaction` accepts when trailing bytes are
+//! rejected, but like deserialization it does not sanitize the transaction.
+//!
+//! [`VersionedTransaction`]: crate::versioned::VersionedTransaction
+
+use {
+    crate::versioned::TransactionVersion,
+    core::fmt,
+    solana_hash::Hash,
+    solana_message::{MessageHeader, MESSAGE_HEADER_LENGTH, MESSAGE_VERSION_PREFIX},
+    solana_pubkey::Pubkey,
+    solana_short_vec::decode_shortu16_len,
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    std::error::Error,
+};
+
+const PUBKEY_BYTES: usize = core::mem::size_of::<Pubkey>();
+const HASH_BYTES: usize = core::mem::size_of::<Hash>();
+
+/// Reasons that bytes are not a well-formed serialized transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionViewError {
+    /// The input ended in the middle of a field
+    UnexpectedEnd,
+    /// A compact-u16 length prefix is malformed
+    InvalidLength,
+    /// The message version is not supported
+    UnsupportedVersion(u8),
+    /// The input continues after the end of the transaction
+    TrailingBytes,
+}
+
+impl Error for TransactionViewError {}
+
+impl fmt::Display for TransactionViewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("unexpected end of transaction data"),
+            Self::InvalidLength => f.write_str("invalid compact-u16 length"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported message version {version}")
+            }
+            Self::TrailingBytes => f.write_str("trailing bytes after transaction data"),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, TransactionViewError>;
+
+/// A compiled instruction borrowed from a serialized transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionView<'a> {
+    /// Index into the transaction keys, indicating the program account that
+    /// executes this instruction.
+    pub program_id_index: u8,
+    /// Ordered indices into the transaction keys, indicating which accounts to
+    /// pass to the program.
+    pub accounts: &'a [u8],
+    /// The program input data.
+    pub data: &'a [u8],
+}
+
+/// An address table lookup borrowed from a serialized transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressTableLookupView<'a> {
+    /// Address lookup table account key
+    pub account_key: &'a Pubkey,
+    /// List of indexes used to load writable account addresses
+    pub writable_indexes: &'a [u8],
+    /// List of indexes used to load readonly account addresses
+    pub readonly_indexes: &'a [u8],
+}
+
+/// A zero-copy view of a serialized versioned transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionView<'a> {
+    bytes: &'a [u8],
+    signatures: &'a [Signature],
+    message_offset: usize,
+    version: Option<u8>,
+    header: MessageHeader,
+    static_account_keys: &'a [Pubkey],
+    recent_blockhash: &'a Hash,
+    num_instructions: usize,
+    instructions: &'a [u8],
+    num_address_table_lookups: usize,
+    address_table_lookups: &'a [u8],
+}
+
+impl<'a> TransactionView<'a> {
+    /// Check that `bytes` hold exactly one serialized transaction and return
+    /// a view of it.
+    pub fn try_new(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+
+        let num_signatures = reader.read_len()?;
+        let signatures = reader.read_array_slice::<Signature, SIGNATURE_BYTES>(num_signatures)?;
+
+        let message_offset = reader.offset;
+        let version = match reader.peek_byte()? {
+            prefix if prefix & MESSAGE_VERSION_PREFIX != 0 => {
+                reader.read_bytes(1)?;
+                match prefix & !MESSAGE_VERSION_PREFIX {
+                    0 => Some(0),
+                    version => return Err(TransactionViewError::UnsupportedVersion(version)),
+                }
+            }
+            _ => None,
+        };
+
+        let header = reader.read_bytes(MESSAGE_HEADER_LENGTH)?;
+        let header = MessageHeader {
+            num_required_signatures: header[0],
+            num_readonly_signed_accounts: header[1],
+            num_readonly_unsigned_accounts: header[2],
+        };
+
+        let num_static_account_keys = reader.read_len()?;
+        let static_account_keys =
+            reader.read_array_slice::<Pubkey, PUBKEY_BYTES>(num_static_account_keys)?;
+        let recent_blockhash = &reader.read_array_slice::<Hash, HASH_BYTES>(1)?[0];
+
+        let num_instructions = reader.read_len()?;
+        let instructions_offset = reader.offset;
+        for _ in 0..num_instructions {
+            reader.read_instruction()?;
+        }
+        let instructions = &bytes[instructions_offset..reader.offset];
+
+        let (num_address_table_lookups, address_table_lookups) = if version.is_some() {
+            let num_address_table_lookups = reader.read_len()?;
+            let lookups_offset = reader.offset;
+            for _ in 0..num_address_table_lookups {
+                reader.read_address_table_lookup()?;
+            }
+            (
+                num_address_table_lookups,
+                &bytes[lookups_offset..reader.offset],
+            )
+        } else {
+            (0, &[][..])
+        };
+
+        if reader.offset != bytes.len() {
+            return Err(TransactionViewError::TrailingBytes);
+        }
+
+        Ok(Self {
+            bytes,
+            signatures,
+            message_offset,
+            version,
+            header,
+            static_account_keys,
+            recent_blockhash,
+            num_instructions,
+            instructions,
+            num_address_table_lookups,
+            address_table_lookups,
+        })
+    }
+
+    /// The serialized transaction
+    pub fn data(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The serialized message, which is the data signed by each signer
+    pub fn message_data(&self) -> &'a [u8] {
+        &self.bytes[self.message_offset..]
+    }
+
+    /// The transaction signatures
+    pub fn signatures(&self) -> &'a [Signature] {
+        self.signatures
+    }
+
+    /// The version of the transaction message
+    pub fn version(&self) -> TransactionVersion {
+        match self.version {
+            None => TransactionVersion::LEGACY,
+            Some(version) => TransactionVersion::Number(version),
+        }
+    }
+
+    /// The message header
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    /// The account keys stored in the message
+    pub fn static_account_keys(&self) -> &'a [Pubkey] {
+        self.static_account_keys
+    }
+
+    /// The id of a recent ledger entry
+    pub fn recent_blockhash(&self) -> &'a Hash {
+        self.recent_blockhash
+    }
+
+    /// Iterate over the compiled instructions of the message
+    pub fn instructions(&self) -> InstructionsIter<'a> {
+        InstructionsIter {
+            remaining: self.num_instructions,
+            reader: Reader::new(self.instructions),
+        }
+    }
+
+    /// Iterate over the address table lookups of the message. Legacy
+    /// messages have no lookups.
+    pub fn address_table_lookups(&self) -> AddressTableLookupsIter<'a> {
+        AddressTableLookupsIter {
+            remaining: self.num_address_table_lookups,
+            reader: Reader::new(self.address_table_lookups),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for TransactionView<'a> {
+    type Error = TransactionViewError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        Self::try_new(bytes)
+    }
+}
+
+/// Iterator over the instructions of a [`TransactionView`]
+#[derive(Debug, Clone)]
+pub struct InstructionsIter<'a> {
+    remaining: usize,
+    reader: Reader<'a>,
+}
+
+impl<'a> Iterator for InstructionsIter<'a> {
+    type Item = InstructionView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        // Instructions were validated when the view was created
+        self.reader.read_instruction().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for InstructionsIter<'_> {}
+
+/// Iterator over the address table lookups of a [`TransactionView`]
+#[derive(Debug, Clone)]
+pub struct AddressTableLookupsIter<'a> {
+    remaining: usize,
+    reader: Reader<'a>,
+}
+
+impl<'a> Iterator for AddressTableLookupsIter<'a> {
+    type Item = AddressTableLookupView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        // Lookups were validated when the view was created
+        self.reader.read_address_table_lookup().ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for AddressTableLookupsIter<'_> {}
+
+#[derive(Debug, Clone)]
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn peek_byte(&self) -> Result<u8> {
+        self.bytes
+            .get(self.offset)
+            .copied()
+            .ok_or(TransactionViewError::UnexpectedEnd)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .ok_or(TransactionViewError::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(TransactionViewError::UnexpectedEnd)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        let remaining = self.bytes.get(self.offset..).unwrap_or_default();
+        let (len, size) = decode_shortu16_len(remaining).map_err(|_| match remaining.last() {
+            // A truncated encoding is shorter than the maximum encoding
+            // length of 3 bytes and ends with a continuation bit
+            Some(byte) if remaining.len() < 3 && byte & 0x80 == 0 => {
+                TransactionViewError::InvalidLength
+            }
+            Some(_) if remaining.len() >= 3 => TransactionViewError::InvalidLength,
+            _ => TransactionViewError::UnexpectedEnd,
+        })?;
+        self.offset = self.offset.saturating_add(size);
+        Ok(len)
+    }
+
+    fn read_short_vec(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_len()?;
+        self.read_bytes(len)
+    }
+
+    /// Read `len` values of a `repr(transparent)` wrapper around `[u8; N]`
+    fn read_array_slice<T, const N: usize>(&mut self, len: usize) -> Result<&'a [T]> {
+        debug_assert_eq!(core::mem::size_of::<T>(), N);
+        debug_assert_eq!(core::mem::align_of::<T>(), 1);
+        let bytes = self.read_bytes(
+            len.checked_mul(N)
+                .ok_or(TransactionViewError::UnexpectedEnd)?,
+        )?;
+        // SAFETY: `T` is `Signature`, `Pubkey` or `Hash`, each of which is a
+        // `repr(transparent)` wrapper around `[u8; N]`. They have an alignment
+        // of 1, no invalid bit patterns, and `bytes` holds exactly `len` of
+        // them.
+        Ok(unsafe { core::slice::from_raw_parts(bytes.as_ptr().cast::<T>(), len) })
+    }
+
+    fn read_instruction(&mut self) -> Result<InstructionView<'a>> {
+        let program_id_index = self.read_bytes(1)?[0];
+        let accounts = self.read_short_vec()?;
+        let data = self.read_short_vec()?;
+        Ok(InstructionView {
+            program_id_index,
+            accounts,
+            data,
+        })
+    }
+
+    fn read_address_table_lookup(&mut self) -> Result<AddressTableLookupView<'a>> {
+        let account_key = &self.read_array_slice::<Pubkey, PUBKEY_BYTES>(1)?[0];
+        let writable_indexes = self.read_short_vec()?;
+        let readonly_indexes = self.read_short_vec()?;
+        Ok(AddressTableLookupView {
+            account_key,
+            writable_indexes,
+            readonly_indexes,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::arithmetic_side_effects)]
+mod tests {
+    use {
+        super::*,
+        crate::versioned::VersionedTransaction,
+        bincode::Options,
+        solana_message::{
+            compiled_instruction::CompiledInstruction,
+            v0::{self, MessageAddressTableLookup},
+            Message, VersionedMessage,
+        },
+    };
+
+    fn assert_view_eq(view: &TransactionView, tx: &VersionedTransaction) {
+        assert_eq!(view.signatures(), tx.signatures.as_slice());
+        assert_eq!(view.version(), tx.version());
+        assert_eq!(view.header(), tx.message.header());
+        assert_eq!(view.static_account_keys(), tx.message.static_account_keys());
+        assert_eq!(view.recent_blockhash(), tx.message.recent_blockhash());
+        assert_eq!(view.message_data(), tx.message.serialize().as_slice());
+
+        let instructions = view.instructions();
+        assert_eq!(instructions.len(), tx.message.instructions().len());
+        for (view, instruction) in instructions.zip(tx.message.instructions()) {
+            assert_eq!(view.program_id_index, instruction.program_id_index);
+            assert_eq!(view.accounts, instruction.accounts.as_slice());
+            assert_eq!(view.data, instruction.data.as_slice());
+        }
+
+        let lookups = view.address_table_lookups();
+        let expected_lookups = tx.message.address_table_lookups().unwrap_or_default();
+        assert_eq!(lookups.len(), expected_lookups.len());
+        for (view, lookup) in lookups.zip(expected_lookups) {
+            assert_eq!(view.account_key, &lookup.account_key);
+            assert_eq!(view.writable_indexes, lookup.writable_indexes.as_slice());
+            assert_eq!(view.readonly_indexes, lookup.readonly_indexes.as_slice());
+        }
+    }
+
+    fn deserialize(bytes: &[u8]) -> bincode::Result<VersionedTransaction> {
+        bincode::options()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize(bytes)
+    }
+
+    fn random_bytes(max_len: usize) -> Vec<u8> {
+        (0..rand::random::<usize>() % max_len)
+            .map(|_| rand::random())
+            .collect()
+    }
+
+    fn random_transaction() -> VersionedTransaction {
+        let header = MessageHeader {
+            // a legacy header with the high bit set reads as a version prefix
+            num_required_signatures: rand::random::<u8>() & !MESSAGE_VERSION_PREFIX,
+            num_readonly_signed_accounts: rand::random(),
+            num_readonly_unsigned_accounts: rand::random(),
+        };
+        let account_keys = (0..rand::random::<usize>() % 8)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let instructions = (0..rand::random::<usize>() % 4)
+            .map(|_| CompiledInstruction {
+                program_id_index: rand::random(),
+                accounts: random_bytes(8),
+                data: random_bytes(200),
+            })
+            .collect();
+        let message = if rand::random() {
+            VersionedMessage::Legacy(Message {
+                header,
+                account_keys,
+                recent_blockhash: Hash::new_unique(),
+                instructions,
+            })
+        } else {
+            VersionedMessage::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash: Hash::new_unique(),
+                instructions,
+                address_table_lookups: (0..rand::random::<usize>() % 3)
+                    .map(|_| MessageAddressTableLookup {
+                        account_key: Pubkey::new_unique(),
+                        writable_indexes: random_bytes(4),
+                        readonly_indexes: random_bytes(4),
+                    })
+                    .collect(),
+            })
+        };
+        VersionedTransaction {
+            signatures: (0..rand::random::<usize>() % 4)
+                .map(|_| Signature::new_unique())
+                .collect(),
+            message,
+        }
+    }
+
+    #[test]
+    fn test_transaction_view() {
+        let legacy_tx = VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            message: VersionedMessage::Legacy(Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction::new_from_raw_parts(
+                    1,
+                    vec![1, 2, 3],
+                    vec![0],
+                )],
+            }),
+        };
+        let bytes = bincode::serialize(&legacy_tx).unwrap();
+        let view = TransactionView::try_new(&bytes).unwrap();
+        assert_view_eq(&view, &legacy_tx);
+        assert_eq!(view.data(), bytes.as_slice());
+        assert_eq!(view.address_table_lookups().next(), None);
+
+        let v0_tx = VersionedTransaction {
+            signatures: vec![Signature::new_unique(), Signature::new_unique()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 0,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(1, vec![], vec![0, 2]),
+                    CompiledInstruction::new_from_raw_parts(1, vec![7; 300], vec![3]),
+                ],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![4],
+                    readonly_indexes: vec![5, 6],
+                }],
+            }),
+        };
+        let bytes = bincode::serialize(&v0_tx).unwrap();
+        assert_view_eq(&TransactionView::try_new(&bytes).unwrap(), &v0_tx);
+    }
+
+    #[test]
+    fn test_transaction_view_errors() {
+        let tx = VersionedTransaction::default();
+        let mut bytes = bincode::serialize(&tx).unwrap();
+        assert!(TransactionView::try_new(&bytes).is_ok());
+
+        assert_eq!(
+            TransactionView::try_new(&bytes[..bytes.len() - 1]),
+            Err(TransactionViewError::UnexpectedEnd)
+        );
+        assert_eq!(
+            TransactionView::try_new(&[]),
+            Err(TransactionViewError::UnexpectedEnd)
+        );
+        assert_eq!(
+            TransactionView::try_new(&[0x80]),
+            Err(TransactionViewError::UnexpectedEnd)
+        );
+        assert_eq!(
+            TransactionView::try_new(&[0x80, 0x00]),
+            Err(TransactionViewError::InvalidLength)
+        );
+        assert_eq!(
+            TransactionView::try_new(&[0x00, MESSAGE_VERSION_PREFIX | 1]),
+            Err(TransactionViewError::UnsupportedVersion(1))
+        );
+
+        bytes.push(0);
+        assert_eq!(
+            TransactionView::try_new(&bytes),
+            Err(TransactionViewError::TrailingBytes)
+        );
+    }
+
+    #[test]
+    fn test_transaction_view_matches_bincode() {
+        for _ in 0..1000 {
+            let tx = random_transaction();
+            let bytes = bincode::serialize(&tx).unwrap();
+            assert_view_eq(&TransactionView::try_new(&bytes).unwrap(), &tx);
+
+            // Corrupt, truncate or extend the serialized transaction and check
+            // that the view accepts exactly what bincode accepts.
+            let mut corrupted = bytes.clone();
+            match rand::random::<u8>() % 3 {
+                0 => {
+                    let index = rand::random::<usize>() % corrupted.len();
+                    corrupted[index] = rand::random();
+                }
+                1 => corrupted.truncate(rand::random::<usize>() % corrupted.len()),
+                _ => corrupted.extend(random_bytes(4)),
+            }
+            match (
+                TransactionView::try_new(&corrupted),
+                deserialize(&corrupted),
+            ) {
+                (Ok(view), Ok(tx)) => assert_view_eq(&view, &tx),
+                (Err(_), Err(_)) => {}
+                (view, tx) => panic!("view {view:?} disagrees with bincode {tx:?}"),
+            }
+        }
+
+        for _ in 0..1000 {
+            let bytes = random_bytes(256);
+            assert_eq!(
+                TransactionView::try_new(&bytes).is_ok(),
+                deserialize(&bytes).is_ok()
+            );
+        }
+    }
+}